@@ -0,0 +1,76 @@
+//! Wall time of crawler-shaped inserts, one add_edge per link against one
+//! add_edges per page, with workers contending on the same graph.
+//!
+//! cargo bench --bench edge_batching
+
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mycelia::graph::core::Graph;
+
+const WORKERS: usize = 8;
+const PAGES_PER_WORKER: usize = 500;
+const LINKS_PER_PAGE: usize = 50;
+
+// links point into a shared pool so workers keep hitting the same nodes
+const POOL: usize = 5000;
+
+fn links_of(page: usize) -> Vec<String> {
+    (0..LINKS_PER_PAGE)
+        .map(|i| format!("node_{}", (page * 31 + i * 97) % POOL))
+        .collect()
+}
+
+fn crawl(insert: fn(&Graph, &str, &[String])) -> Duration {
+    let (graph, _rx) = Graph::new();
+    let graph = Arc::new(graph);
+
+    let started = Instant::now();
+    let handles: Vec<_> = (0..WORKERS)
+        .map(|worker| {
+            let graph = graph.clone();
+            thread::spawn(move || {
+                for i in 0..PAGES_PER_WORKER {
+                    let page = worker * PAGES_PER_WORKER + i;
+                    let name = format!("node_{}", page % POOL);
+                    insert(&graph, &name, &links_of(page));
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    started.elapsed()
+}
+
+fn main() {
+    let per_link = crawl(|graph, page, links| {
+        for link in links {
+            graph.add_edge(page, link).unwrap();
+        }
+    });
+    let per_page = crawl(|graph, page, links| {
+        graph.add_edges(page, links).unwrap();
+    });
+
+    let pages = WORKERS * PAGES_PER_WORKER;
+    println!(
+        "{} workers, {} pages of {} links",
+        WORKERS, pages, LINKS_PER_PAGE
+    );
+    println!(
+        "{:<24} {:>8.1} ms  {:>6} lock rounds",
+        "add_edge per link",
+        per_link.as_secs_f64() * 1000.0,
+        pages * LINKS_PER_PAGE
+    );
+    println!(
+        "{:<24} {:>8.1} ms  {:>6} lock rounds",
+        "add_edges per page",
+        per_page.as_secs_f64() * 1000.0,
+        pages
+    );
+}
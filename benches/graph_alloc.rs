@@ -0,0 +1,63 @@
+//! Counts heap allocations made by common graph workloads.
+//!
+//! cargo bench --bench graph_alloc
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use mycelia::graph::core::Graph;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn measure(name: &str, f: impl FnOnce()) {
+    let allocs = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+
+    f();
+
+    println!(
+        "{:<32} {:>8} allocs {:>10} bytes",
+        name,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocs,
+        BYTES.load(Ordering::Relaxed) - bytes
+    );
+}
+
+fn main() {
+    let num_nodes = 1000;
+    let names: Vec<String> =
+        (0..num_nodes).map(|i| format!("node_{}", i)).collect();
+
+    let (graph, mut rx) = Graph::new();
+    measure("add_edge (new nodes)", || {
+        for name in &names {
+            graph.add_edge("root", name).unwrap();
+        }
+    });
+
+    measure("add_edge (duplicates)", || {
+        for name in &names {
+            graph.add_edge("root", name).unwrap();
+        }
+    });
+
+    measure("drain events", || while rx.try_recv().is_ok() {});
+}
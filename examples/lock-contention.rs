@@ -0,0 +1,40 @@
+// Hammers one hotspot edge and prints the lock spans on close.
+//
+// cargo run --example lock-contention --features lock-tracing
+//
+// The same filter works for the app's own log setup:
+// RUST_LOG=info,mycelia::graph::core=trace cargo run --features lock-tracing
+
+use std::sync::Arc;
+use std::thread;
+
+use mycelia::graph::core::Graph;
+use tracing_subscriber::{EnvFilter, fmt, fmt::format::FmtSpan};
+
+fn main() {
+    fmt()
+        .with_env_filter(
+            // spans only, the duplicate warn! would drown them out
+            EnvFilter::new("off,mycelia::graph::core[add_edge]=trace"),
+        )
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    let (graph, _rx) = Graph::new();
+    let graph = Arc::new(graph);
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let graph = graph.clone();
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    let _ = graph.add_edge("root", "hotspot");
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
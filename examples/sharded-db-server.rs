@@ -45,12 +45,8 @@ impl ShardedDb {
     pub fn get(&self, key: &str) -> Option<Bytes> {
         let idx = self.shard_index(key);
 
-        let val = {
-            let guard = self.shards[idx].lock().unwrap();
-            guard.get(key).cloned()
-        };
-
-        val
+        let guard = self.shards[idx].lock().unwrap();
+        guard.get(key).cloned()
     }
 }
 
@@ -1,9 +1,13 @@
 use actix_files::Files;
-use actix_web::{get, middleware::Logger, web, App, HttpServer, Responder};
+use actix_web::{
+    get,
+    middleware::{Compress, Logger},
+    App, HttpServer, Responder,
+};
 
 #[get("/hello")]
 async fn greet() -> impl Responder {
-    format!("Hello!")
+    "Hello!"
 }
 
 #[actix_web::main]
@@ -17,6 +21,8 @@ async fn main() -> std::io::Result<()> {
             .service(greet)
             .service(Files::new("/pages", "static/pages/").show_files_listing())
             .service(Files::new("/", "static/").index_file("index.html"))
+            // gzip/br for clients that send Accept-Encoding, like the crawler
+            .wrap(Compress::default())
             .wrap(Logger::default())
     })
     .bind(("127.0.0.1", 8080))?
@@ -0,0 +1,90 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use sha1::{Digest, Sha1};
+use tracing::info;
+
+pub const MANIFEST: &str = "manifest.json";
+
+/// Raw page bodies saved under one directory so extraction can be rerun
+/// offline, plus a `manifest.json` mapping each url to its file
+///
+/// Files are named by the SHA-1 of the url, so no url character ever
+/// reaches the filesystem.
+#[derive(Debug)]
+pub struct PageArchive {
+    dir: PathBuf,
+    manifest: Mutex<BTreeMap<String, String>>,
+}
+
+impl PageArchive {
+    /// Creates `dir` if needed, keeping the manifest of an earlier crawl
+    /// into it
+    pub fn create(dir: impl Into<PathBuf>) -> io::Result<PageArchive> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let manifest = match fs::read(dir.join(MANIFEST)) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(PageArchive {
+            dir,
+            manifest: Mutex::new(manifest),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// `<sha1 of url>.html`
+    pub fn file_name(url: &str) -> String {
+        let digest = Sha1::digest(url.as_bytes());
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}.html", hex)
+    }
+
+    /// Writes `body` to the page's file right away, then the manifest
+    /// with its entry, so a crawl cut short leaves no unlisted files
+    pub fn save(&self, url: &str, body: &str) -> io::Result<()> {
+        let name = PageArchive::file_name(url);
+        fs::write(self.dir.join(&name), body)?;
+
+        let mut manifest = self.manifest.lock().unwrap();
+        if manifest.insert(url.to_owned(), name).is_none() {
+            self.write(&manifest)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `manifest.json`, returns how many urls it lists
+    pub fn write_manifest(&self) -> io::Result<usize> {
+        let manifest = self.manifest.lock().unwrap();
+        self.write(&manifest)?;
+
+        info!(
+            path = %self.dir.join(MANIFEST).display(),
+            pages = manifest.len(),
+            "Wrote manifest"
+        );
+        Ok(manifest.len())
+    }
+
+    // through a tmp file, called with the manifest lock held so two
+    // writes never share it
+    fn write(&self, manifest: &BTreeMap<String, String>) -> io::Result<()> {
+        let json = serde_json::to_vec_pretty(manifest)?;
+
+        let path = self.dir.join(MANIFEST);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &path)
+    }
+}
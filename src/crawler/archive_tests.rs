@@ -0,0 +1,86 @@
+#![cfg(test)]
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Arc;
+
+use crate::crawler::archive::MANIFEST;
+use crate::crawler::crawl_tests::{MockFetcher, config_for, wiki};
+use crate::crawler::{Crawler, PageArchive};
+use crate::graph::core::Graph;
+
+#[test]
+fn test_file_name_is_hex_hash_of_url() {
+    let name = PageArchive::file_name("https://x.org/a/../b?c=d&e=<f>|\"g\"");
+    let (stem, ext) = name.split_once('.').unwrap();
+
+    assert_eq!(ext, "html");
+    assert_eq!(stem.len(), 40);
+    assert!(stem.bytes().all(|b| b.is_ascii_hexdigit()));
+    assert_ne!(name, PageArchive::file_name("https://x.org/b"));
+}
+
+#[test]
+fn test_manifest_lists_pages_as_they_are_saved() {
+    let dir = std::env::temp_dir().join("mycelia_archive_save");
+    let _ = fs::remove_dir_all(&dir);
+
+    // never reaching write_manifest, like a crawl killed midway
+    let archive = PageArchive::create(&dir).unwrap();
+    archive.save(&wiki("A"), "<p>a</p>").unwrap();
+    archive.save(&wiki("B"), "<p>b</p>").unwrap();
+    drop(archive);
+
+    let manifest: BTreeMap<String, String> =
+        serde_json::from_slice(&fs::read(dir.join(MANIFEST)).unwrap()).unwrap();
+    assert_eq!(
+        manifest.keys().collect::<Vec<_>>(),
+        [&wiki("A"), &wiki("B")]
+    );
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    // reopened, the archive keeps listing them
+    let archive = PageArchive::create(&dir).unwrap();
+    assert_eq!(archive.write_manifest().unwrap(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_crawl_archives_every_fetched_page() {
+    let dir = std::env::temp_dir().join("mycelia_archive_crawl");
+    let _ = fs::remove_dir_all(&dir);
+
+    let fetcher = Arc::new(MockFetcher::new(&[
+        ("A", &["B", "C", "Gone"]),
+        ("B", &["C"]),
+        ("C", &[]),
+    ]));
+    let mut config = config_for("A");
+    config.archive_dir = Some(dir.clone());
+    let graph = Arc::new(Graph::new_without_events());
+    Crawler::new(config, graph, fetcher.clone())
+        .run()
+        .await
+        .unwrap();
+
+    let manifest: BTreeMap<String, String> =
+        serde_json::from_slice(&fs::read(dir.join(MANIFEST)).unwrap()).unwrap();
+    let pages = ["A", "B", "C"].map(wiki);
+    assert_eq!(
+        manifest.keys().collect::<Vec<_>>(),
+        pages.iter().collect::<Vec<_>>()
+    );
+
+    for (url, name) in &manifest {
+        assert_eq!(name, &PageArchive::file_name(url));
+        assert_eq!(
+            fs::read_to_string(dir.join(name)).unwrap(),
+            fetcher.pages[url]
+        );
+    }
+
+    // the failed fetch left nothing behind, only pages and the manifest
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), pages.len() + 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::crawler::frontier::{Frontier, FrontierEntry};
+use crate::graph::core::Graph;
+use crate::graph::snapshot::GraphSnapshot;
+
+/// Saves checkpoints on the blocking pool, one at a time, and never one
+/// with fewer visited pages over a newer one
+#[derive(Debug, Default)]
+pub(crate) struct CheckpointWriter {
+    // visited count of the last checkpoint written
+    written: Mutex<usize>,
+}
+
+impl CheckpointWriter {
+    pub(crate) async fn save(
+        self: &Arc<Self>,
+        checkpoint: Checkpoint,
+        path: PathBuf,
+    ) -> Result<()> {
+        let writer = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut written = writer.written.lock().unwrap();
+            if checkpoint.visited.len() < *written {
+                return Ok(());
+            }
+            checkpoint.save(&path)?;
+            *written = checkpoint.visited.len();
+            Ok(())
+        })
+        .await?
+    }
+}
+
+/// On-disk state a crawl can be resumed from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub graph: GraphSnapshot,
+
+    /// urls queued or mid-fetch when it was taken, fetched first on resume
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<FrontierEntry>,
+
+    /// pages already processed, sorted, never fetched again on resume
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub visited: Vec<String>,
+}
+
+impl Checkpoint {
+    /// The crawl so far, see `CrawlConfig::checkpoint_path`
+    pub(crate) fn capture(
+        graph: &Graph,
+        frontier: &Frontier,
+        visited: &HashSet<String>,
+    ) -> Checkpoint {
+        let mut visited: Vec<String> = visited.iter().cloned().collect();
+        visited.sort();
+
+        Checkpoint {
+            graph: graph.snapshot(),
+            pending: frontier.pending(),
+            visited,
+        }
+    }
+
+    /// Writes to a sibling tmp file first and renames it over `path`, so a
+    /// crash mid-write never leaves a truncated checkpoint behind
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+
+        let json = serde_json::to_vec(self)?;
+        fs::write(&tmp, &json)
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("renaming to {}", path.display()))?;
+
+        info!(path = %path.display(), bytes = json.len(), "Saved checkpoint");
+        Ok(())
+    }
+
+    /// Frontier spill file kept beside the checkpoint at `path`, so urls
+    /// queued on disk survive into the resumed crawl
+    pub fn spill_path(path: impl AsRef<Path>) -> PathBuf {
+        let mut name = path.as_ref().as_os_str().to_owned();
+        name.push(".frontier");
+        PathBuf::from(name)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+
+        serde_json::from_slice(&bytes)
+            .with_context(|| format!("parsing {}", path.display()))
+    }
+}
@@ -0,0 +1,245 @@
+#![cfg(test)]
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::crawler::checkpoint::CheckpointWriter;
+use crate::crawler::crawl_tests::{MockFetcher, config_for, wiki};
+use crate::crawler::frontier::FrontierEntry;
+use crate::crawler::{Checkpoint, CrawlConfig, Crawler};
+use crate::graph::core::Graph;
+use crate::graph::snapshot::DISCOVERED_AT;
+
+fn has_edge(graph: &Graph, parent: &str, child: &str) -> bool {
+    graph
+        .get_node(&wiki(parent))
+        .unwrap()
+        .get_children()
+        .iter()
+        .any(|c| c.get_data() == wiki(child))
+}
+
+#[test]
+fn test_checkpoint_roundtrip() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.set_node_meta("A", "fetch_ms", 3.0).unwrap();
+
+    let path = std::env::temp_dir().join("mycelia_checkpoint_roundtrip.json");
+    let checkpoint = Checkpoint {
+        graph: graph.snapshot(),
+        ..Default::default()
+    };
+    checkpoint.save(&path).unwrap();
+
+    let loaded = Checkpoint::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, checkpoint);
+
+    let (rebuilt, _rx) = Graph::from_snapshot(&loaded.graph).unwrap();
+    assert_eq!(rebuilt.snapshot(), graph.snapshot());
+}
+
+#[tokio::test]
+async fn test_only_new_skips_expanded_pages() {
+    // first crawl: D and E don't exist yet, so they stay leaves
+    let fetcher =
+        MockFetcher::new(&[("A", &["B", "C"]), ("B", &["D"]), ("C", &["E"])]);
+    let (graph, _rx) = Graph::new();
+    let crawler = Crawler::new(config_for("A"), Arc::new(graph), fetcher);
+    let first = crawler.run().await.unwrap();
+    let snapshot = first.snapshot();
+
+    // second crawl: D and E now exist and link back into the known graph
+    let fetcher = Arc::new(MockFetcher::new(&[
+        ("A", &["B", "C"]),
+        ("B", &["D"]),
+        ("C", &["E"]),
+        ("D", &["A", "F"]),
+        ("E", &["B"]),
+        ("F", &[]),
+    ]));
+    let (graph, _rx) = Graph::from_snapshot(&snapshot).unwrap();
+    let config = CrawlConfig {
+        only_new: true,
+        ..config_for("A")
+    };
+    let crawler = Crawler::new(config, Arc::new(graph), fetcher.clone());
+    let second = crawler.run().await.unwrap();
+
+    // only D, E and the newly discovered F were fetched
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 3);
+    assert!(has_edge(&second, "D", "A"));
+    assert!(has_edge(&second, "D", "F"));
+    assert!(has_edge(&second, "E", "B"));
+    assert_eq!(second.node_count(), snapshot.node_count() + 1);
+}
+
+#[tokio::test]
+async fn test_only_new_resumes_a_finished_checkpoint() {
+    let path = std::env::temp_dir().join("mycelia_checkpoint_only_new.json");
+    let _ = std::fs::remove_file(&path);
+    let config = CrawlConfig {
+        checkpoint_path: Some(path.clone()),
+        ..config_for("A")
+    };
+
+    // D and E fail, but they're visited all the same
+    let fetcher =
+        MockFetcher::new(&[("A", &["B", "C"]), ("B", &["D"]), ("C", &["E"])]);
+    let graph = Arc::new(Graph::new_without_events());
+    Crawler::new(config.clone(), graph, fetcher)
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap().visited.len(), 5);
+
+    let fetcher = Arc::new(MockFetcher::new(&[
+        ("A", &["B", "C"]),
+        ("B", &["D"]),
+        ("C", &["E"]),
+        ("D", &["A", "F"]),
+        ("E", &["B"]),
+        ("F", &[]),
+    ]));
+    let config = CrawlConfig {
+        only_new: true,
+        ..config
+    };
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher.clone());
+    let graph = crawler.run().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the failed D and E are retried, then F is new
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 3);
+    assert!(has_edge(&graph, "D", "F"));
+    assert!(has_edge(&graph, "E", "B"));
+}
+
+#[test]
+fn test_spill_path_sits_beside_checkpoint() {
+    assert_eq!(
+        Checkpoint::spill_path("runs/crawl.json"),
+        Path::new("runs/crawl.json.frontier")
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint_resumes_without_refetching() {
+    let site: &[(&str, &[&str])] =
+        &[("A", &["B"]), ("B", &["C"]), ("C", &["D"]), ("D", &[])];
+    let path = std::env::temp_dir().join("mycelia_checkpoint_resume.json");
+    let _ = std::fs::remove_file(&path);
+    let config = CrawlConfig {
+        workers: 1,
+        checkpoint_path: Some(path.clone()),
+        checkpoint_every: 1,
+        ..config_for("A")
+    };
+
+    // slow enough to be cut off mid-fetch once A and B are checkpointed
+    let mut fetcher = MockFetcher::new(site);
+    fetcher.delay = Duration::from_millis(200);
+    let graph = Arc::new(Graph::new_without_events());
+    let crawl =
+        tokio::spawn(Crawler::new(config.clone(), graph, fetcher).run());
+    let visited = loop {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        if let Ok(checkpoint) = Checkpoint::load(&path)
+            && checkpoint.visited.len() >= 2
+        {
+            break checkpoint.visited.len();
+        }
+    };
+    crawl.abort();
+    let _ = crawl.await;
+
+    let fetcher = Arc::new(MockFetcher::new(site));
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher.clone());
+    let graph = crawler.run().await.unwrap();
+
+    // only the pages the first run didn't finish were fetched
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), site.len() - visited);
+    assert_eq!(graph.node_count(), 5);
+    assert!(has_edge(&graph, "A", "B"));
+    assert!(has_edge(&graph, "C", "D"));
+
+    let last = Checkpoint::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(last.pending.is_empty());
+    assert_eq!(last.visited.len(), site.len());
+}
+
+#[tokio::test]
+async fn test_checkpoint_writer_never_goes_backwards() {
+    let path = std::env::temp_dir().join("mycelia_checkpoint_writer.json");
+    let writer = Arc::new(CheckpointWriter::default());
+    let visited = |n: usize| Checkpoint {
+        visited: (0..n).map(|i| wiki(&i.to_string())).collect(),
+        ..Default::default()
+    };
+
+    // concurrent saves are serialized, each lands whole
+    let saves: Vec<_> = (1..=8)
+        .map(|n| {
+            let writer = writer.clone();
+            let path = path.clone();
+            tokio::spawn(async move { writer.save(visited(n), path).await })
+        })
+        .collect();
+    for save in saves {
+        save.await.unwrap().unwrap();
+    }
+
+    // an older capture finishing late is dropped
+    writer.save(visited(3), path.clone()).await.unwrap();
+
+    let loaded = Checkpoint::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.visited.len(), 8);
+}
+
+#[tokio::test]
+async fn test_resumed_discovery_numbers_stay_unique() {
+    // A was fetched before the checkpoint, B was found but not yet fetched
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", &wiki("A")).unwrap();
+    graph.add_edge(&wiki("A"), &wiki("B")).unwrap();
+    graph.set_node_meta(&wiki("A"), DISCOVERED_AT, 0).unwrap();
+    graph.set_node_meta(&wiki("B"), DISCOVERED_AT, 1).unwrap();
+    let checkpoint = Checkpoint {
+        graph: graph.snapshot(),
+        pending: vec![FrontierEntry {
+            url: wiki("B"),
+            depth: 1,
+        }],
+        visited: vec![wiki("A")],
+    };
+
+    let path = std::env::temp_dir().join("mycelia_checkpoint_discovery.json");
+    checkpoint.save(&path).unwrap();
+    let config = CrawlConfig {
+        workers: 1,
+        checkpoint_path: Some(path.clone()),
+        record_discovery: true,
+        ..config_for("A")
+    };
+    let fetcher =
+        MockFetcher::new(&[("A", &["B"]), ("B", &["C"]), ("C", &["D"])]);
+    let graph = Arc::new(Graph::new_without_events());
+    let graph = Crawler::new(config, graph, fetcher).run().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut numbers: Vec<u64> = graph
+        .snapshot()
+        .meta
+        .values()
+        .filter_map(|m| m.get(DISCOVERED_AT)?.as_u64())
+        .collect();
+    numbers.sort_unstable();
+    assert_eq!(numbers, [0, 1, 2, 3]);
+}
@@ -0,0 +1,132 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::crawler::{
+    extract::{DEFAULT_MAX_LINKS, Scope},
+    retry::RetryPolicy,
+    score::FrontierPolicy,
+};
+
+#[derive(Debug, Clone)]
+pub struct CrawlConfig {
+    pub start_url: String,
+
+    /// seed from every page listed in this sitemap instead of start_url
+    pub sitemap: Option<String>,
+
+    /// number of concurrent fetch workers
+    pub workers: usize,
+
+    /// requests in flight at once across all workers, `workers` if unset,
+    /// lower it to keep more workers busy extracting than fetching
+    pub max_concurrent_requests: Option<usize>,
+
+    /// exit once the frontier is empty and no fetch is in flight,
+    /// otherwise workers keep waiting for new urls to be enqueued
+    pub idle_shutdown: bool,
+
+    /// skip links from a page back to itself, including via redirect
+    pub drop_self_loops: bool,
+
+    /// on a graph loaded from a checkpoint, only fetch pages that weren't
+    /// expanded yet instead of recrawling from start_url
+    pub only_new: bool,
+
+    /// also follow `Category:` links, category nodes are tagged with
+    /// `kind: "category"` meta
+    pub crawl_categories: bool,
+
+    /// only fetch pages at most this many link hops from the seed page
+    /// (or each sitemap page), links beyond it still become edges
+    pub radius: Option<usize>,
+
+    /// stop following a url pattern (same host and path shape, differing
+    /// only in numeric or opaque parts) after this many of its urls
+    pub trap_threshold: Option<usize>,
+
+    /// which links are followed, https wiki articles only by default
+    pub scope: Scope,
+
+    /// links taken from one page at most, the rest are dropped
+    pub max_links: usize,
+
+    /// keep at most this many queued urls in memory, the rest wait in an
+    /// append-only spill file
+    pub max_frontier: Option<usize>,
+
+    /// where max_frontier spills to, a file in the temp dir if unset
+    pub spill_path: Option<PathBuf>,
+
+    /// number pages in the order they're first discovered, as
+    /// `discovered_at` meta, see `JsonOptions::order_by_discovery`
+    pub record_discovery: bool,
+
+    /// save every fetched body into this directory, see `PageArchive`
+    pub archive_dir: Option<PathBuf>,
+
+    /// save the graph, frontier and visited pages here every
+    /// checkpoint_every pages and once the crawl ends, and resume from it
+    /// on start if it exists, see `Checkpoint`
+    pub checkpoint_path: Option<PathBuf>,
+
+    /// pages processed between two checkpoint saves
+    pub checkpoint_every: usize,
+
+    /// log the CrawlReport as JSON once the crawl finishes
+    pub log_report: bool,
+
+    /// order in which discovered urls are fetched
+    pub policy: FrontierPolicy,
+
+    /// how page fetches failing with a 5xx, 429 or a broken connection
+    /// are retried, never by default
+    pub retry: RetryPolicy,
+
+    /// minimum gap between two requests to the same host
+    pub request_delay: Duration,
+
+    /// extra random gap on top of request_delay, so workers don't fire at
+    /// a host in lockstep
+    pub delay_jitter: Duration,
+}
+
+impl Default for CrawlConfig {
+    fn default() -> Self {
+        CrawlConfig {
+            start_url: String::from("http://localhost:8080/pages/linux.html"),
+            sitemap: None,
+            workers: 4,
+            max_concurrent_requests: None,
+            idle_shutdown: true,
+            drop_self_loops: false,
+            only_new: false,
+            crawl_categories: false,
+            radius: None,
+            trap_threshold: None,
+            scope: Scope::default(),
+            max_links: DEFAULT_MAX_LINKS,
+            max_frontier: None,
+            spill_path: None,
+            record_discovery: false,
+            archive_dir: None,
+            checkpoint_path: None,
+            checkpoint_every: 100,
+            log_report: false,
+            policy: FrontierPolicy::Fifo,
+            retry: RetryPolicy::default(),
+            request_delay: Duration::ZERO,
+            delay_jitter: Duration::ZERO,
+        }
+    }
+}
+
+impl CrawlConfig {
+    /// English Wikipedia from its Linux article, following `/wiki/`
+    /// articles like the defaults
+    pub fn wikipedia() -> Self {
+        CrawlConfig {
+            start_url: String::from("https://en.wikipedia.org/wiki/Linux"),
+            scope: Scope::default(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,559 @@
+#![cfg(test)]
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::crawler::{CrawlConfig, Crawler, Fetcher, HttpStatus, Page, Scope};
+use crate::graph::core::{Graph, GraphEvent};
+use crate::graph::export::JsonOptions;
+
+pub(crate) fn wiki(name: &str) -> String {
+    format!("https://en.wikipedia.org/wiki/{}", name)
+}
+
+pub(crate) fn page_linking(names: &[&str]) -> String {
+    let links: String = names
+        .iter()
+        .map(|n| format!(r#"<a href="{}">{}</a>"#, wiki(n), n))
+        .collect();
+
+    format!("<html><body>{}</body></html>", links)
+}
+
+/// In-memory site, urls missing from the map fail like a 404
+#[derive(Default)]
+pub(crate) struct MockFetcher {
+    pub pages: HashMap<String, String>,
+    /// urls failing with this status instead of being served
+    pub statuses: HashMap<String, u16>,
+    pub delay: Duration,
+    pub fetches: AtomicUsize,
+    pub in_flight: AtomicUsize,
+    /// most fetches ever running at once
+    pub peak_in_flight: AtomicUsize,
+}
+
+impl MockFetcher {
+    pub fn new(site: &[(&str, &[&str])]) -> MockFetcher {
+        MockFetcher {
+            pages: site
+                .iter()
+                .map(|(name, links)| (wiki(name), page_linking(links)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Fetcher for MockFetcher {
+    async fn fetch(&self, url: &str) -> Result<Page> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak_in_flight.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        let status = self.statuses.get(url).copied();
+        let body = match self.pages.get(url) {
+            Some(body) if status.is_none() => body.clone(),
+            _ => {
+                return Err(HttpStatus {
+                    url: url.to_owned(),
+                    status: status.unwrap_or(404),
+                    retry_after: None,
+                }
+                .into());
+            }
+        };
+
+        Ok(Page {
+            url: url.to_owned(),
+            status: 200,
+            body,
+        })
+    }
+}
+
+pub(crate) fn config_for(start: &str) -> CrawlConfig {
+    CrawlConfig {
+        start_url: wiki(start),
+        workers: 4,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_idle_shutdown_crawls_bounded_site() {
+    let fetcher = MockFetcher::new(&[
+        ("A", &["B", "C"]),
+        ("B", &["C", "A"]),
+        ("C", &["D"]),
+        ("D", &[]),
+    ]);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config_for("A"), graph, fetcher);
+
+    let graph = tokio::time::timeout(Duration::from_secs(5), crawler.run())
+        .await
+        .expect("crawl should exit once idle")
+        .unwrap();
+
+    assert_eq!(graph.node_count(), 5); // root + A..D
+    assert!(graph.contains(&wiki("D")));
+    assert_eq!(graph.get_node(&wiki("B")).unwrap().get_children().len(), 2);
+}
+
+#[tokio::test]
+async fn test_idle_shutdown_waits_for_in_flight_fetch() {
+    // a single slow seed: idle workers must not exit while it's in flight
+    let mut fetcher = MockFetcher::new(&[
+        ("Seed", &["A", "B"]),
+        ("A", &["C"]),
+        ("B", &[]),
+        ("C", &[]),
+    ]);
+    fetcher.delay = Duration::from_millis(50);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let mut config = config_for("Seed");
+    config.workers = 8;
+
+    let crawler = Crawler::new(config, graph, fetcher);
+    let graph = tokio::time::timeout(Duration::from_secs(5), crawler.run())
+        .await
+        .expect("crawl should exit once idle")
+        .unwrap();
+
+    assert_eq!(graph.node_count(), 5); // root + Seed, A, B, C
+}
+
+#[tokio::test]
+async fn test_missing_pages_dont_stall_crawl() {
+    let fetcher = MockFetcher::new(&[("A", &["Gone", "AlsoGone"])]);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config_for("A"), graph, fetcher);
+
+    let graph = tokio::time::timeout(Duration::from_secs(5), crawler.run())
+        .await
+        .expect("failed fetches should still be finished")
+        .unwrap();
+
+    assert_eq!(graph.node_count(), 4);
+}
+
+#[tokio::test]
+async fn test_without_idle_shutdown_runs_until_closed() {
+    let fetcher = MockFetcher::new(&[("A", &[])]);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let mut config = config_for("A");
+    config.idle_shutdown = false;
+
+    let crawler = Crawler::new(config, graph, fetcher);
+    let frontier = crawler.frontier();
+    let handle = tokio::spawn(crawler.run());
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!handle.is_finished(), "should keep waiting for new urls");
+
+    frontier.close();
+    let graph = tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(graph.node_count(), 2);
+}
+
+#[tokio::test]
+async fn test_urls_enqueued_from_outside_are_expanded() {
+    let fetcher = MockFetcher::new(&[("A", &[]), ("X", &["Y"]), ("Y", &[])]);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config_for("A"), graph, fetcher);
+    assert!(crawler.frontier().push(&wiki("X"), 0));
+    let graph = crawler.run().await.unwrap();
+
+    let root = graph.get_root();
+    let x = graph.get_node(&wiki("X")).unwrap();
+    assert!(root.get_children().iter().any(|c| Arc::ptr_eq(c, &x)));
+    assert!(x.get_meta("fetch_ms").is_some());
+    assert_eq!(x.get_children()[0].get_data(), wiki("Y"));
+}
+
+#[tokio::test]
+async fn test_drop_self_loops_skips_links_to_self() {
+    let fetcher = MockFetcher::new(&[("A", &["A", "B"]), ("B", &["B"])]);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let mut config = config_for("A");
+    config.drop_self_loops = true;
+
+    let graph = Crawler::new(config, graph, fetcher).run().await.unwrap();
+
+    let a = graph.get_node(&wiki("A")).unwrap();
+    let b = graph.get_node(&wiki("B")).unwrap();
+    assert_eq!(a.get_children().len(), 1);
+    assert!(b.get_children().is_empty());
+}
+
+#[tokio::test]
+async fn test_self_loops_kept_by_default() {
+    let fetcher = MockFetcher::new(&[("A", &["A"])]);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let graph = Crawler::new(config_for("A"), graph, fetcher)
+        .run()
+        .await
+        .unwrap();
+
+    let a = graph.get_node(&wiki("A")).unwrap();
+    assert!(Arc::ptr_eq(&a.get_children()[0], &a));
+}
+
+#[tokio::test]
+async fn test_fetch_latency_recorded_for_fetched_nodes_only() {
+    let mut fetcher =
+        MockFetcher::new(&[("A", &["B", "Unfetched"]), ("B", &[])]);
+    fetcher.delay = Duration::from_millis(5);
+
+    let graph = Arc::new(Graph::new_without_events());
+    let graph = Crawler::new(config_for("A"), graph, fetcher)
+        .run()
+        .await
+        .unwrap();
+
+    for name in ["A", "B"] {
+        let ms = graph
+            .get_node(&wiki(name))
+            .unwrap()
+            .get_meta("fetch_ms")
+            .and_then(|v| v.as_f64())
+            .expect("fetched node should carry fetch_ms");
+        assert!((5.0..5000.0).contains(&ms), "implausible latency {}", ms);
+    }
+
+    // discovered as a link, fetch failed: no latency
+    let unfetched = graph.get_node(&wiki("Unfetched")).unwrap();
+    assert!(unfetched.get_meta("fetch_ms").is_none());
+
+    let snapshot = graph.snapshot();
+    assert!(snapshot.meta.contains_key(&wiki("A")));
+    assert!(!snapshot.meta.contains_key(&wiki("Unfetched")));
+}
+
+#[tokio::test]
+async fn test_overlong_links_are_skipped() {
+    let long = "L".repeat(300);
+    let fetcher = MockFetcher::new(&[("A", &["B", long.as_str()]), ("B", &[])]);
+
+    let (graph, _rx) = Graph::builder().max_name_len(200).build();
+    let crawler = Crawler::new(config_for("A"), Arc::new(graph), fetcher);
+    let graph = crawler.run().await.unwrap();
+
+    // the rest of A's links still made it in
+    assert!(graph.contains(&wiki("B")));
+    assert!(!graph.contains(&wiki(&long)));
+    assert_eq!(graph.node_count(), 3);
+}
+
+#[tokio::test]
+async fn test_radius_limits_fetches_to_seed_neighborhood() {
+    let fetcher = Arc::new(MockFetcher::new(&[
+        ("A", &["B", "X"]),
+        ("B", &["C"]),
+        ("X", &["C"]),
+        ("C", &["D"]),
+        ("D", &["E"]),
+        ("E", &[]),
+    ]));
+
+    let mut config = config_for("A");
+    config.radius = Some(2);
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher.clone());
+    let graph = crawler.run().await.unwrap();
+
+    // pages with fetch_ms are the fetched ones
+    let snapshot = graph.snapshot();
+    let mut fetched: Vec<&String> = snapshot.meta.keys().collect();
+    fetched.sort();
+    assert_eq!(fetched, [&wiki("A"), &wiki("B"), &wiki("C"), &wiki("X")]);
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 4);
+
+    let within: Vec<String> = graph
+        .bfs_levels(&wiki("A"))
+        .into_iter()
+        .take(3)
+        .flatten()
+        .collect();
+    for page in fetched {
+        assert!(within.contains(page), "{} is beyond the radius", page);
+    }
+
+    // links out of the last ring are still recorded
+    assert!(graph.contains_edge(&wiki("C"), &wiki("D")));
+    assert!(!graph.contains(&wiki("E")));
+}
+
+#[tokio::test]
+async fn test_categories_followed_only_when_enabled() {
+    let fetcher = || {
+        MockFetcher::new(&[
+            ("A", &["B", "Category:Things"]),
+            ("B", &[]),
+            ("Category:Things", &["A", "C"]),
+            ("C", &[]),
+        ])
+    };
+
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config_for("A"), graph, fetcher());
+    let graph = crawler.run().await.unwrap();
+    assert!(!graph.contains(&wiki("Category:Things")));
+    assert!(!graph.contains(&wiki("C")));
+
+    let mut config = config_for("A");
+    config.crawl_categories = true;
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher());
+    let graph = crawler.run().await.unwrap();
+
+    let category = wiki("Category:Things");
+    assert!(graph.contains_edge(&wiki("A"), &category));
+    assert!(graph.contains_edge(&category, &wiki("C")));
+    assert_eq!(
+        graph.get_node(&category).unwrap().get_meta("kind"),
+        Some("category".into())
+    );
+    assert_eq!(graph.get_node(&wiki("C")).unwrap().get_meta("kind"), None);
+}
+
+#[tokio::test]
+async fn test_tiny_max_frontier_still_visits_every_page() {
+    let names: Vec<String> = (0..20).map(|i| format!("P{}", i)).collect();
+    let links: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut site: Vec<(&str, &[&str])> = vec![("Hub", &links)];
+    site.extend(links.iter().map(|name| (*name, &links[..1])));
+
+    let path = std::env::temp_dir().join("mycelia_crawl_spill.frontier");
+    let mut config = config_for("Hub");
+    config.max_frontier = Some(1);
+    config.spill_path = Some(path.clone());
+
+    let fetcher = Arc::new(MockFetcher::new(&site));
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher.clone());
+    let frontier = crawler.frontier();
+    let graph = crawler.run().await.unwrap();
+
+    assert_eq!(graph.node_count(), 22); // root + Hub + P0..P19
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 21);
+    assert_eq!(frontier.spilled(), 0);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_identical_crawls_export_identical_discovery_order() {
+    let crawl = || async {
+        let fetcher = MockFetcher::new(&[
+            ("Start", &["Zeta", "Alpha"]),
+            ("Zeta", &["Mid", "Alpha"]),
+            ("Alpha", &["Mid"]),
+            ("Mid", &["Start"]),
+        ]);
+        let config = CrawlConfig {
+            workers: 1,
+            record_discovery: true,
+            ..config_for("Start")
+        };
+
+        let graph = Arc::new(Graph::new_without_events());
+        let graph = Crawler::new(config, graph, fetcher).run().await.unwrap();
+        graph
+            .to_json_with(JsonOptions {
+                order_by_discovery: true,
+                exclude_root: true,
+                ..Default::default()
+            })
+            .unwrap()
+    };
+
+    // fetch_ms is wall-clock, everything else has to match exactly
+    let without_timings = |json: String| {
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for meta in value["meta"].as_object_mut().unwrap().values_mut() {
+            meta.as_object_mut().unwrap().remove("fetch_ms");
+        }
+        value
+    };
+    let first = without_timings(crawl().await);
+    assert_eq!(first, without_timings(crawl().await));
+
+    let expected: Vec<String> =
+        ["Start", "Zeta", "Alpha", "Mid"].map(wiki).into();
+    assert_eq!(first["nodes"], serde_json::json!(expected));
+}
+
+fn local(page: &str) -> String {
+    format!("http://localhost:8080/pages/{}", page)
+}
+
+/// The bundled local-testing-server pages, served from disk
+fn local_testing_pages() -> MockFetcher {
+    let mut fetcher = MockFetcher::default();
+    for page in ["linux.html", "links.html", "test.html"] {
+        let path = format!("local-testing-server/static/pages/{}", page);
+        let body = std::fs::read_to_string(path).unwrap();
+        fetcher.pages.insert(local(page), body);
+    }
+    fetcher
+}
+
+pub(crate) fn local_scope() -> Scope {
+    Scope {
+        schemes: vec!["https".into(), "http".into()],
+        allow_ip_hosts: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_allow_http_crawls_local_testing_pages() {
+    let fetcher = Arc::new(local_testing_pages());
+
+    let crawl = |scope: Scope| {
+        let fetcher = fetcher.clone();
+        async move {
+            let config = CrawlConfig {
+                start_url: local("linux.html"),
+                scope,
+                ..Default::default()
+            };
+            let (graph, _rx) = Graph::new();
+            Crawler::new(config, Arc::new(graph), fetcher)
+                .run()
+                .await
+                .unwrap()
+        }
+    };
+
+    // https-only by default, the seed's links are all out of scope
+    let graph = crawl(Scope::default()).await;
+    assert_eq!(graph.node_count(), 2);
+
+    let graph = crawl(local_scope()).await;
+    let children = |url: &str| {
+        let mut names: Vec<String> = graph
+            .get_node(url)
+            .unwrap()
+            .with_children(|c| c.map(|n| n.get_data().to_owned()).collect());
+        names.sort();
+        names
+    };
+    assert_eq!(
+        children(&local("linux.html")),
+        [local("links.html"), local("test.html")]
+    );
+    assert_eq!(
+        children(&local("links.html")),
+        [wiki("Hypha"), wiki("Mycelium"), wiki("Spore")]
+    );
+    // seed alone, then all three pages plus the wiki links they name
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 1 + 6);
+}
+
+#[tokio::test]
+async fn test_local_testing_pages_at_depth_one() {
+    let fetcher = Arc::new(local_testing_pages());
+    let config = CrawlConfig {
+        start_url: local("linux.html"),
+        scope: local_scope(),
+        radius: Some(1),
+        ..Default::default()
+    };
+    let (graph, _rx) = Graph::new();
+    let graph = Crawler::new(config, Arc::new(graph), fetcher.clone())
+        .run()
+        .await
+        .unwrap();
+
+    // root, linux.html, the two pages it links and the three wiki links
+    // of links.html, which are one hop too far to be fetched
+    assert_eq!(graph.node_count(), 7);
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_max_concurrent_requests_caps_in_flight_fetches() {
+    let names: Vec<String> = (0..20).map(|i| format!("P{}", i)).collect();
+    let links: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut site: Vec<(&str, &[&str])> = vec![("Hub", &links)];
+    site.extend(links.iter().map(|name| (*name, &[] as &[&str])));
+
+    let mut fetcher = MockFetcher::new(&site);
+    fetcher.delay = Duration::from_millis(20);
+    let fetcher = Arc::new(fetcher);
+
+    let config = CrawlConfig {
+        workers: 8,
+        max_concurrent_requests: Some(2),
+        ..config_for("Hub")
+    };
+    let graph = Arc::new(Graph::new_without_events());
+    Crawler::new(config, graph, fetcher.clone())
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 21);
+    assert_eq!(fetcher.peak_in_flight.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_progress_events_count_up_to_every_page() {
+    let fetcher = MockFetcher::new(&[
+        ("A", &["B", "C"]),
+        ("B", &["C", "D"]),
+        ("C", &["D"]),
+        ("D", &[]),
+    ]);
+
+    let (graph, mut rx) = Graph::new();
+    let crawler = Crawler::new(config_for("A"), Arc::new(graph), fetcher);
+    let graph = crawler.run().await.unwrap();
+    drop(graph);
+
+    let mut progress = Vec::new();
+    while let Some(event) = rx.recv().await {
+        if let GraphEvent::CrawlProgress { visited, depth, .. } = event {
+            progress.push((visited, depth));
+        }
+    }
+
+    let visited: Vec<usize> = progress.iter().map(|(v, _)| *v).collect();
+    assert_eq!(visited, [1, 2, 3, 4]);
+    assert_eq!(progress[0].1, 0); // the seed
+    assert!(progress.iter().all(|(_, depth)| *depth <= 2));
+}
+
+#[tokio::test]
+async fn test_start_page_hangs_off_renamed_root() {
+    let fetcher = MockFetcher::new(&[("A", &["B"]), ("B", &[])]);
+    let graph = Graph::builder().root_name("start").build_without_events();
+
+    let crawler = Crawler::new(config_for("A"), Arc::new(graph), fetcher);
+    let graph = crawler.run().await.unwrap();
+
+    assert_eq!(graph.node_count(), 3);
+    assert!(!graph.contains("root"));
+    assert!(graph.contains_edge("start", &wiki("A")));
+}
@@ -0,0 +1,260 @@
+use std::{collections::HashSet, sync::LazyLock};
+
+use anyhow::{Result, anyhow};
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use scraper::{Html, Selector};
+use tracing::{instrument, warn};
+use url::{Host, Url};
+
+const WIKI_HOST: &str = "en.wikipedia.org";
+const CATEGORY_NAMESPACE: &str = "Category:";
+
+/// Article paths on Wikipedia, the title is the first capture group
+pub const DEFAULT_ARTICLE_REGEX: &str = r"^/wiki/(.*)$";
+
+/// Far more links than any real article has, index pages past it are cut
+pub const DEFAULT_MAX_LINKS: usize = 10_000;
+
+static ARTICLE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(DEFAULT_ARTICLE_REGEX).unwrap());
+
+static NAMESPACE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(Category|Wikipedia|Special|Template|Help|Portal|Book|Draft|File|MediaWiki|Module|TimedText|User|Talk):"
+    ).unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// a regular, non-namespaced article
+    Article,
+
+    /// a `Category:` page, kept apart for building a taxonomy
+    Category,
+}
+
+/// Which urls are in crawl scope besides regular https wiki articles
+#[derive(Debug, Clone)]
+pub struct Scope {
+    /// MediaWiki host whose articles are followed, `en.wikipedia.org` by
+    /// default
+    pub wiki_host: String,
+
+    /// paths of articles on wiki_host, with the percent-encoded title as
+    /// the first capture group, `^/wiki/(.*)$` by default, e.g.
+    /// `^/index\.php/(.*)$` for a wiki without short urls
+    pub article_regex: Regex,
+
+    /// decoded titles of namespaced pages, which aren't followed,
+    /// Wikipedia's namespaces by default
+    pub namespace_regex: Regex,
+
+    /// CSS selector for the anchors links are taken from, every `a[href]`
+    /// by default, e.g. `#bodyContent a[href]` to skip the sidebar
+    pub link_selector: String,
+
+    /// url schemes followed, just `https` by default
+    pub schemes: Vec<String>,
+
+    /// also follow `localhost` and IP-literal hosts on any port, like the
+    /// bundled local-testing-server, where every path is an article
+    pub allow_ip_hosts: bool,
+
+    /// keep urls with a query string on those hosts instead of rejecting
+    /// them, endless generated ones like `?id=N` are for `TrapDetector`
+    /// to cut off; wiki urls with a query are never followed
+    pub allow_queries: bool,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope {
+            wiki_host: String::from(WIKI_HOST),
+            article_regex: ARTICLE_RE.clone(),
+            namespace_regex: NAMESPACE_RE.clone(),
+            link_selector: String::from("a[href]"),
+            schemes: vec![String::from("https")],
+            allow_ip_hosts: false,
+            allow_queries: false,
+        }
+    }
+}
+
+impl Scope {
+    /// Kind and canonical form of an in-scope url, see `classify_url`
+    pub fn classify(&self, href: &str) -> Option<(LinkKind, String)> {
+        let mut url = Url::parse(href).ok()?;
+
+        if !self.schemes.iter().any(|s| s == url.scheme()) {
+            return None;
+        }
+
+        if url.query().is_some() && !self.allow_queries {
+            return None;
+        }
+
+        if self.allow_ip_hosts && is_local_host(&url) {
+            url.set_fragment(None);
+            return Some((LinkKind::Article, url.into()));
+        }
+
+        // wiki pages with a query are edits, diffs or old revisions
+        if url.query().is_some() {
+            return None;
+        }
+
+        let on_wiki = url
+            .host_str()
+            .is_some_and(|host| host.eq_ignore_ascii_case(&self.wiki_host));
+        if !on_wiki || url.port().is_some() {
+            return None;
+        }
+
+        let decoded = self.title(&url)?;
+        let kind = match decoded.strip_prefix(CATEGORY_NAMESPACE) {
+            Some("") => return None,
+            Some(_) => LinkKind::Category,
+            None if decoded.is_empty()
+                || self.namespace_regex.is_match(&decoded) =>
+            {
+                return None;
+            }
+            None => LinkKind::Article,
+        };
+
+        url.set_fragment(None);
+        Some((kind, url.into()))
+    }
+
+    /// Canonical form of an in-scope article url, see
+    /// `normalize_article_url`
+    pub fn normalize_article(&self, href: &str) -> Option<String> {
+        match self.classify(href)? {
+            (LinkKind::Article, url) => Some(url),
+            (LinkKind::Category, _) => None,
+        }
+    }
+
+    /// Percent-decoded title of an article url, None if its path isn't
+    /// one article_regex matches
+    fn title(&self, url: &Url) -> Option<String> {
+        let title = self.article_regex.captures(url.path())?.get(1)?;
+        let decoded = percent_decode_str(title.as_str()).decode_utf8().ok()?;
+        Some(decoded.into_owned())
+    }
+}
+
+fn is_local_host(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Ipv4(_) | Host::Ipv6(_)) => true,
+        Some(Host::Domain(domain)) => domain == "localhost",
+        None => false,
+    }
+}
+
+/// Links of a page split by kind, each in document order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractedLinks {
+    pub articles: Vec<String>,
+    pub categories: Vec<String>,
+}
+
+/// Canonical form of a regular (non-namespaced) wiki article url, None
+/// for anything out of crawl scope
+///
+/// The fragment is dropped so `X` and `X#Section` are one page, any query
+/// string (`?action=edit`, `?oldid=...`) rejects the url outright. Title
+/// checks run on the percent-decoded path, so `Talk%3AX` is still a talk
+/// page while a `?` inside a fragment or an encoded title is harmless.
+pub fn normalize_article_url(href: &str) -> Option<String> {
+    Scope::default().normalize_article(href)
+}
+
+/// Kind and canonical form of a wiki article or category url, None for
+/// other namespaces and anything else `normalize_article_url` rejects
+pub fn classify_url(href: &str) -> Option<(LinkKind, String)> {
+    Scope::default().classify(href)
+}
+
+/// Crawl scope: true for urls of regular (non-namespaced) wiki articles
+pub fn is_article_url(href: &str) -> bool {
+    normalize_article_url(href).is_some()
+}
+
+/// Normalized in-scope links of a page, deduped, in document order
+pub fn extract_links(body: &str) -> Result<Vec<String>> {
+    extract_links_with(body, DEFAULT_MAX_LINKS)
+}
+
+/// `extract_links` stopping at `max_links` unique links
+pub fn extract_links_with(body: &str, max_links: usize) -> Result<Vec<String>> {
+    Ok(classify_links_with(body, &Scope::default(), max_links)?.articles)
+}
+
+/// Titles of a page's in-scope articles, deduped, in document order
+///
+/// Percent-decoded with underscores as spaces, the way MediaWiki shows
+/// them, so `Linux_kernel`, `Linux%20kernel` and `Linux_kernel#History`
+/// are all `Linux kernel`. Namespaced pages are filtered like in
+/// `extract_links`.
+pub fn extract_article_names(body: &str) -> Result<Vec<String>> {
+    let scope = Scope::default();
+    let mut seen = HashSet::new();
+    Ok(extract_links(body)?
+        .iter()
+        .filter_map(|url| article_name(&scope, url))
+        .filter(|name| seen.insert(name.clone()))
+        .collect())
+}
+
+fn article_name(scope: &Scope, url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    Some(scope.title(&url)?.replace('_', " "))
+}
+
+/// Normalized article and category links of a page, each deduped
+pub fn classify_links(body: &str) -> Result<ExtractedLinks> {
+    classify_links_with(body, &Scope::default(), DEFAULT_MAX_LINKS)
+}
+
+/// `classify_links` over `scope`, stopping once `max_links` unique links
+/// (articles and categories together) were collected, anything after is
+/// dropped with a warning
+///
+/// Errors if `scope.link_selector` isn't a valid CSS selector.
+#[instrument(skip(body))]
+pub fn classify_links_with(
+    body: &str,
+    scope: &Scope,
+    max_links: usize,
+) -> Result<ExtractedLinks> {
+    let anchors = Selector::parse(&scope.link_selector).map_err(|e| {
+        anyhow!("invalid link selector {:?}: {}", scope.link_selector, e)
+    })?;
+    let doc = Html::parse_document(body);
+
+    let mut seen = HashSet::new();
+    let mut links = ExtractedLinks::default();
+    for (kind, url) in doc
+        .select(&anchors)
+        .filter_map(|el| el.value().attr("href"))
+        .filter_map(|href| scope.classify(href))
+    {
+        if seen.contains(&url) {
+            continue;
+        }
+        if seen.len() == max_links {
+            warn!(max_links, "Page has too many links, truncating");
+            break;
+        }
+        seen.insert(url.clone());
+
+        match kind {
+            LinkKind::Article => links.articles.push(url),
+            LinkKind::Category => links.categories.push(url),
+        }
+    }
+
+    Ok(links)
+}
@@ -0,0 +1,279 @@
+#![cfg(test)]
+use regex::Regex;
+
+use crate::crawler::{
+    LinkKind, Scope, classify_links, classify_links_with, classify_url,
+    extract_article_names, extract_links, extract_links_with, is_article_url,
+    normalize_article_url,
+};
+
+const BASE: &str = "https://en.wikipedia.org/wiki/";
+
+fn norm(href: &str) -> Option<String> {
+    normalize_article_url(href)
+}
+
+#[test]
+fn test_plain_articles_pass_unchanged() {
+    for href in [
+        "https://en.wikipedia.org/wiki/Linux",
+        "https://en.wikipedia.org/wiki/C%2B%2B",
+        "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+    ] {
+        assert_eq!(norm(href).as_deref(), Some(href));
+    }
+}
+
+#[test]
+fn test_fragment_is_stripped() {
+    let linux = Some(format!("{}Linux", BASE));
+
+    assert_eq!(norm("https://en.wikipedia.org/wiki/Linux#History"), linux);
+    // a "?" or "action=" in the fragment is not a query string
+    assert_eq!(norm("https://en.wikipedia.org/wiki/Linux#why?"), linux);
+    assert_eq!(norm("https://en.wikipedia.org/wiki/Linux#action=x"), linux);
+}
+
+#[test]
+fn test_query_strings_are_rejected() {
+    for href in [
+        "https://en.wikipedia.org/wiki/Linux?action=edit",
+        "https://en.wikipedia.org/wiki/Linux?oldid=123",
+        "https://en.wikipedia.org/wiki/Linux?",
+        "https://en.wikipedia.org/wiki/Linux?x=1#frag",
+    ] {
+        assert_eq!(norm(href), None, "{}", href);
+    }
+}
+
+#[test]
+fn test_allow_queries_keeps_local_query_strings() {
+    let scope = Scope {
+        schemes: vec![String::from("http")],
+        allow_ip_hosts: true,
+        ..Default::default()
+    };
+    let href = "http://127.0.0.1:8080/page?id=7#top";
+    assert_eq!(scope.normalize_article(href), None);
+
+    let scope = Scope {
+        allow_queries: true,
+        ..scope
+    };
+    assert_eq!(
+        scope.normalize_article(href),
+        Some(String::from("http://127.0.0.1:8080/page?id=7"))
+    );
+    assert_eq!(scope.normalize_article(&format!("{}X?oldid=1", BASE)), None);
+}
+
+#[test]
+fn test_encoded_question_mark_is_part_of_title() {
+    // the article "Who?", the css substring filter used to drop these
+    let href = "https://en.wikipedia.org/wiki/Who%3F";
+    assert_eq!(norm(href).as_deref(), Some(href));
+
+    let href = "https://en.wikipedia.org/wiki/Do_action%3Dthings";
+    assert_eq!(norm(href).as_deref(), Some(href));
+}
+
+#[test]
+fn test_namespaces_and_other_hosts_are_rejected() {
+    for href in [
+        "https://en.wikipedia.org/wiki/Talk:Linux",
+        "https://en.wikipedia.org/wiki/Talk%3ALinux", // encoded colon
+        "https://en.wikipedia.org/wiki/Category:Operating_systems",
+        "https://en.wikipedia.org/wiki/",
+        "https://en.wikipedia.org/w/index.php",
+        "https://de.wikipedia.org/wiki/Linux",
+        "http://en.wikipedia.org/wiki/Linux",
+        "https://en.wikipedia.org:8443/wiki/Linux",
+        "/wiki/Linux",
+        "not a url",
+    ] {
+        assert!(!is_article_url(href), "{}", href);
+    }
+}
+
+#[test]
+fn test_host_case_is_normalized() {
+    assert_eq!(
+        norm("https://EN.Wikipedia.org/wiki/Linux"),
+        Some(format!("{}Linux", BASE))
+    );
+}
+
+#[test]
+fn test_extract_links_normalizes_in_order() {
+    let body = r#"<html><body>
+        <a href="https://en.wikipedia.org/wiki/B#top">b</a>
+        <a href="https://en.wikipedia.org/wiki/A?action=edit">edit</a>
+        <a href="https://en.wikipedia.org/wiki/A">a</a>
+        <a>no href</a>
+        <a href="https://en.wikipedia.org/wiki/Who%3F">who</a>
+    </body></html>"#;
+
+    assert_eq!(
+        extract_links(body).unwrap(),
+        [
+            format!("{}B", BASE),
+            format!("{}A", BASE),
+            format!("{}Who%3F", BASE)
+        ]
+    );
+}
+
+#[test]
+fn test_category_links_are_classified() {
+    let category = format!("{}Category:Operating_systems", BASE);
+    assert_eq!(
+        classify_url(&format!("{}#Members", category)),
+        Some((LinkKind::Category, category.clone()))
+    );
+    assert_eq!(
+        classify_url(&format!("{}Category%3AKernels", BASE)),
+        Some((LinkKind::Category, format!("{}Category%3AKernels", BASE)))
+    );
+    assert_eq!(
+        classify_url(&format!("{}Linux", BASE)),
+        Some((LinkKind::Article, format!("{}Linux", BASE)))
+    );
+
+    // still out of scope either way
+    for href in [
+        format!("{}Category:", BASE),
+        format!("{}Category:Operating_systems?from=L", BASE),
+        format!("{}Talk:Linux", BASE),
+    ] {
+        assert_eq!(classify_url(&href), None, "{}", href);
+    }
+}
+
+#[test]
+fn test_classify_links_keeps_categories_apart() {
+    let body = r#"<html><body>
+        <a href="https://en.wikipedia.org/wiki/Linux">linux</a>
+        <a href="https://en.wikipedia.org/wiki/Category:Kernels">kernels</a>
+        <a href="https://en.wikipedia.org/wiki/Help:Contents">help</a>
+        <a href="https://en.wikipedia.org/wiki/Unix">unix</a>
+        <a href="https://en.wikipedia.org/wiki/Category:Unix">unix cat</a>
+    </body></html>"#;
+
+    let links = classify_links(body).unwrap();
+    assert_eq!(
+        links.articles,
+        [format!("{}Linux", BASE), format!("{}Unix", BASE)]
+    );
+    assert_eq!(
+        links.categories,
+        [
+            format!("{}Category:Kernels", BASE),
+            format!("{}Category:Unix", BASE)
+        ]
+    );
+
+    // the article-only view is unchanged
+    assert_eq!(extract_links(body).unwrap(), links.articles);
+}
+
+#[test]
+fn test_max_links_counts_unique_links() {
+    let mut body = String::from("<html><body>");
+    for i in 0..20 {
+        // every link twice, so a raw anchor count would stop at 2
+        for _ in 0..2 {
+            body += &format!(r#"<a href="{}P{}">p</a>"#, BASE, i);
+        }
+    }
+    body += "</body></html>";
+
+    let links = extract_links_with(&body, 5).unwrap();
+    let expected: Vec<String> =
+        (0..5).map(|i| format!("{}P{}", BASE, i)).collect();
+    assert_eq!(links, expected);
+
+    assert_eq!(extract_links(&body).unwrap().len(), 20);
+}
+
+#[test]
+fn test_custom_wiki_host_and_link_selector() {
+    let body = r#"<html><body>
+        <div id="nav">
+            <a href="https://wiki.example.org/wiki/Main_Page">home</a>
+        </div>
+        <div id="content">
+            <a href="https://wiki.example.org/wiki/Linux">linux</a>
+            <a href="https://WIKI.example.org/wiki/Unix#History">unix</a>
+            <a href="https://wiki.example.org/wiki/Special:Random">?</a>
+            <a href="https://en.wikipedia.org/wiki/Linux">elsewhere</a>
+        </div>
+    </body></html>"#;
+
+    let scope = Scope {
+        wiki_host: String::from("wiki.example.org"),
+        link_selector: String::from("#content a[href]"),
+        ..Default::default()
+    };
+    let links = classify_links_with(body, &scope, usize::MAX).unwrap();
+    assert_eq!(
+        links.articles,
+        [
+            "https://wiki.example.org/wiki/Linux",
+            "https://wiki.example.org/wiki/Unix",
+        ]
+    );
+
+    let broken = Scope {
+        link_selector: String::from("a >> ["),
+        ..Default::default()
+    };
+    assert!(classify_links_with(body, &broken, usize::MAX).is_err());
+}
+
+#[test]
+fn test_custom_article_and_namespace_regex() {
+    let body = r#"<html><body>
+        <a href="https://wiki.example.org/index.php/Linux">linux</a>
+        <a href="https://wiki.example.org/index.php/Unix%20history">unix</a>
+        <a href="https://wiki.example.org/index.php/Project:About">about</a>
+        <a href="https://wiki.example.org/index.php/Talk:Linux">talk</a>
+        <a href="https://wiki.example.org/wiki/Linux">short url</a>
+        <a href="https://wiki.example.org/index.php">index</a>
+    </body></html>"#;
+
+    let scope = Scope {
+        wiki_host: String::from("wiki.example.org"),
+        article_regex: Regex::new(r"^/index\.php/(.*)$").unwrap(),
+        namespace_regex: Regex::new(r"^Project:").unwrap(),
+        ..Default::default()
+    };
+    let links = classify_links_with(body, &scope, usize::MAX).unwrap();
+    assert_eq!(
+        links.articles,
+        [
+            "https://wiki.example.org/index.php/Linux",
+            "https://wiki.example.org/index.php/Unix%20history",
+            "https://wiki.example.org/index.php/Talk:Linux",
+        ]
+    );
+}
+
+#[test]
+fn test_article_names_collapse_url_forms() {
+    let body = format!(
+        r#"<html><body>
+            <a href="{0}Linux_kernel">a</a>
+            <a href="{0}Linux_kernel#History">b</a>
+            <a href="{0}Linux%20kernel">c</a>
+            <a href="{0}Linux_kernel">d</a>
+            <a href="{0}C%2B%2B">e</a>
+            <a href="{0}Talk:Linux_kernel">f</a>
+        </body></html>"#,
+        BASE
+    );
+
+    assert_eq!(
+        extract_article_names(&body).unwrap(),
+        ["Linux kernel", "C++"]
+    );
+}
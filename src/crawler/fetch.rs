@@ -0,0 +1,217 @@
+use std::future::Future;
+use std::io::Read;
+use std::time::Duration;
+use std::{error, fmt};
+
+use anyhow::{Context, Result, bail};
+use flate2::read::GzDecoder;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, RETRY_AFTER};
+
+// decoded here rather than by reqwest, whose gzip/brotli features pull in
+// async-compression
+const ACCEPTED_ENCODINGS: &str = "gzip, br";
+
+/// Largest body `decode_body` returns, far above any real article, so a
+/// small compressed response can't inflate without bound
+pub const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Sent with every request unless `HttpFetcher::with_user_agent` says
+/// otherwise, so site owners can tell who's crawling
+pub const DEFAULT_USER_AGENT: &str =
+    concat!("mycelia-crawler/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// final url after redirects
+    pub url: String,
+    pub status: u16,
+    pub body: String,
+}
+
+/// A response with a 4xx or 5xx status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpStatus {
+    pub url: String,
+    pub status: u16,
+
+    /// the response's `Retry-After`, only the delay-seconds form
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.status, self.url)
+    }
+}
+
+impl error::Error for HttpStatus {}
+
+/// Status of the response a fetch error came from, if it got one
+pub fn status_of(err: &anyhow::Error) -> Option<u16> {
+    if let Some(e) = err.downcast_ref::<HttpStatus>() {
+        return Some(e.status);
+    }
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .map(|s| s.as_u16())
+}
+
+/// Source of pages for the crawler, mocked in tests
+pub trait Fetcher: Send + Sync + 'static {
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<Page>> + Send;
+}
+
+/// Fetches over one pooled `reqwest::Client`, clones share the pool
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    client: reqwest::Client,
+}
+
+impl Default for HttpFetcher {
+    fn default() -> Self {
+        HttpFetcher::with_timeouts(Timeouts::default())
+            .expect("building a client without options can't fail")
+    }
+}
+
+/// Client timeouts, None leaves reqwest's default of no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timeouts {
+    /// whole request, connecting through reading the last body byte
+    pub request: Option<Duration>,
+
+    /// establishing the TCP (and TLS) connection
+    pub connect: Option<Duration>,
+
+    /// between two reads, so a server that accepts and then stalls fails
+    /// long before the request timeout
+    pub read: Option<Duration>,
+}
+
+impl HttpFetcher {
+    pub fn new(client: reqwest::Client) -> HttpFetcher {
+        HttpFetcher { client }
+    }
+
+    pub fn with_timeouts(timeouts: Timeouts) -> Result<HttpFetcher> {
+        HttpFetcher::with_user_agent(timeouts, DEFAULT_USER_AGENT)
+    }
+
+    pub fn with_user_agent(
+        timeouts: Timeouts,
+        user_agent: &str,
+    ) -> Result<HttpFetcher> {
+        let mut builder = reqwest::Client::builder().user_agent(user_agent);
+        if let Some(timeout) = timeouts.request {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = timeouts.connect {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = timeouts.read {
+            builder = builder.read_timeout(timeout);
+        }
+
+        Ok(HttpFetcher::new(builder.build()?))
+    }
+}
+
+/// Whether a fetch error is one of the client's timeouts running out
+pub fn is_timeout(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(reqwest::Error::is_timeout)
+}
+
+impl Fetcher for HttpFetcher {
+    async fn fetch(&self, url: &str) -> Result<Page> {
+        let resp = self
+            .client
+            .get(url)
+            .header(ACCEPT_ENCODING, ACCEPTED_ENCODINGS)
+            .send()
+            .await
+            .context("Failed to connect")?;
+
+        let final_url = resp.url().to_string();
+        let status = resp.status().as_u16();
+        if resp.status().is_client_error() || resp.status().is_server_error() {
+            let retry_after = resp
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+                .map(Duration::from_secs);
+            return Err(HttpStatus {
+                url: final_url,
+                status,
+                retry_after,
+            }
+            .into());
+        }
+        let encoding = resp
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_owned);
+        let raw = resp.bytes().await.context("Failed to read body")?;
+        let body = decode_body(&raw, encoding.as_deref())?;
+
+        Ok(Page {
+            url: final_url,
+            status,
+            body,
+        })
+    }
+}
+
+/// Body bytes as text, undoing a `Content-Encoding` of gzip or br
+pub fn decode_body(raw: &[u8], encoding: Option<&str>) -> Result<String> {
+    decode_body_with(raw, encoding, MAX_BODY_BYTES)
+}
+
+/// `decode_body` failing once the body is over `max_bytes`, counted after
+/// decoding
+pub fn decode_body_with(
+    raw: &[u8],
+    encoding: Option<&str>,
+    max_bytes: usize,
+) -> Result<String> {
+    let decoded;
+    let bytes = match encoding.map(|e| e.trim().to_ascii_lowercase()) {
+        None => raw,
+        Some(e) if e.is_empty() || e == "identity" => raw,
+        Some(e) if e == "gzip" || e == "x-gzip" => {
+            decoded = read_limited(GzDecoder::new(raw), max_bytes)
+                .context("Failed to decode gzip body")?;
+            &decoded
+        }
+        Some(e) if e == "br" => {
+            let reader = brotli::Decompressor::new(raw, 4096);
+            decoded = read_limited(reader, max_bytes)
+                .context("Failed to decode br body")?;
+            &decoded
+        }
+        Some(e) => bail!("Unsupported content encoding {:?}", e),
+    };
+    if bytes.len() > max_bytes {
+        bail!("Body is over {} bytes", max_bytes);
+    }
+
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+// one byte past max_bytes is enough to tell the body is too big
+fn read_limited(reader: impl Read, max_bytes: usize) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    reader
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+/// Lets callers keep a handle on a fetcher shared with the crawler
+impl<F: Fetcher> Fetcher for std::sync::Arc<F> {
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<Page>> + Send {
+        (**self).fetch(url)
+    }
+}
@@ -0,0 +1,155 @@
+#![cfg(test)]
+use std::io::Write;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+use actix_files::Files;
+use actix_web::{
+    App, HttpRequest, HttpResponse, HttpServer, middleware::Compress, web,
+};
+use flate2::{Compression, write::GzEncoder};
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+
+use crate::crawler::fetch::{decode_body, decode_body_with};
+use crate::crawler::{
+    DEFAULT_USER_AGENT, Fetcher, HttpFetcher, Timeouts, extract_links,
+    is_timeout,
+};
+
+/// local-testing-server's pages with its compression, on a free port
+fn spawn_pages_server() -> u16 {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let server = HttpServer::new(|| {
+        App::new()
+            .wrap(Compress::default())
+            .service(Files::new("/pages", "local-testing-server/static/pages/"))
+    })
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    port
+}
+
+#[actix_web::test]
+async fn test_fetch_decodes_compressed_page() {
+    let port = spawn_pages_server();
+    let url = format!("http://127.0.0.1:{}/pages/links.html", port);
+
+    // the server really does compress for both encodings we ask for
+    let client = reqwest::Client::new();
+    for encoding in ["gzip", "br"] {
+        let resp = client
+            .get(&url)
+            .header(ACCEPT_ENCODING, encoding)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()[CONTENT_ENCODING], encoding);
+    }
+
+    let page = HttpFetcher::default().fetch(&url).await.unwrap();
+    assert_eq!(page.status, 200);
+    assert!(page.body.contains("<title>Links</title>"));
+    assert_eq!(
+        extract_links(&page.body).unwrap(),
+        [
+            "https://en.wikipedia.org/wiki/Mycelium",
+            "https://en.wikipedia.org/wiki/Hypha",
+            "https://en.wikipedia.org/wiki/Spore",
+        ]
+    );
+}
+
+#[test]
+fn test_decode_body() {
+    let html = "<a href=\"https://en.wikipedia.org/wiki/Spore\">é</a>";
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(html.as_bytes()).unwrap();
+    let gz = gz.finish().unwrap();
+
+    assert_eq!(decode_body(&gz, Some("gzip")).unwrap(), html);
+    assert_eq!(decode_body(&gz, Some(" GZIP ")).unwrap(), html);
+    assert_eq!(decode_body(html.as_bytes(), None).unwrap(), html);
+    assert_eq!(
+        decode_body(html.as_bytes(), Some("identity")).unwrap(),
+        html
+    );
+
+    assert!(decode_body(html.as_bytes(), Some("gzip")).is_err());
+    assert!(decode_body(&gz, Some("zstd")).is_err());
+}
+
+#[test]
+fn test_decode_body_stops_at_max_bytes() {
+    // 4 MiB of zeros squeeze into a few KiB
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(&vec![0; 4 << 20]).unwrap();
+    let gz = gz.finish().unwrap();
+    assert!(gz.len() < 64 << 10);
+
+    let err = decode_body_with(&gz, Some("gzip"), 1 << 20).unwrap_err();
+    assert!(err.to_string().contains("over 1048576 bytes"), "{}", err);
+    assert_eq!(
+        decode_body_with(&gz, Some("gzip"), 4 << 20).unwrap().len(),
+        4 << 20
+    );
+    assert!(decode_body_with(b"<p>hi</p>", None, 4).is_err());
+}
+
+#[tokio::test]
+async fn test_stalled_server_fails_within_read_timeout() {
+    // accepts connections, then never writes a byte
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            held.push(socket);
+        }
+    });
+
+    let fetcher = HttpFetcher::with_timeouts(Timeouts {
+        request: Some(Duration::from_secs(30)),
+        connect: Some(Duration::from_secs(1)),
+        read: Some(Duration::from_millis(200)),
+    })
+    .unwrap();
+
+    let started = Instant::now();
+    let url = format!("http://127.0.0.1:{}/pages/linux.html", port);
+    let err = fetcher.fetch(&url).await.unwrap_err();
+
+    assert!(is_timeout(&err), "{:?}", err);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[actix_web::test]
+async fn test_user_agent_is_sent() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let echo = |req: HttpRequest| async move {
+        let agent = req.headers().get("user-agent").unwrap().to_str().unwrap();
+        HttpResponse::Ok().body(agent.to_owned())
+    };
+    let server =
+        HttpServer::new(move || App::new().route("/", web::get().to(echo)))
+            .listen(listener)
+            .unwrap()
+            .run();
+    actix_web::rt::spawn(server);
+    let url = format!("http://127.0.0.1:{}/", port);
+
+    let page = HttpFetcher::default().fetch(&url).await.unwrap();
+    assert_eq!(page.body, DEFAULT_USER_AGENT);
+    assert!(page.body.starts_with("mycelia-crawler/"));
+
+    let fetcher =
+        HttpFetcher::with_user_agent(Timeouts::default(), "test-agent/1.0")
+            .unwrap();
+    assert_eq!(fetcher.fetch(&url).await.unwrap().body, "test-agent/1.0");
+}
@@ -0,0 +1,451 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet, VecDeque},
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tracing::warn;
+
+use crate::crawler::score::{FrontierPolicy, Scorer};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrontierEntry {
+    pub url: String,
+    pub depth: usize,
+}
+
+/// Entry ordered by score, then by push order among equal scores
+#[derive(Debug)]
+struct Scored {
+    score: f64,
+    seq: u64,
+    entry: FrontierEntry,
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+enum Queue {
+    Fifo(VecDeque<FrontierEntry>),
+    Priority {
+        heap: BinaryHeap<Scored>,
+        scorer: Arc<dyn Scorer>,
+        next_seq: u64,
+    },
+}
+
+impl Queue {
+    fn push(&mut self, entry: FrontierEntry) {
+        match self {
+            Queue::Fifo(queue) => queue.push_back(entry),
+            Queue::Priority {
+                heap,
+                scorer,
+                next_seq,
+            } => {
+                let score = scorer.score(&entry.url, entry.depth);
+                heap.push(Scored {
+                    score,
+                    seq: *next_seq,
+                    entry,
+                });
+                *next_seq += 1;
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<FrontierEntry> {
+        match self {
+            Queue::Fifo(queue) => queue.pop_front(),
+            Queue::Priority { heap, .. } => heap.pop().map(|s| s.entry),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Queue::Fifo(queue) => queue.len(),
+            Queue::Priority { heap, .. } => heap.len(),
+        }
+    }
+
+    /// Copies of the queued entries in the order pop would return them
+    fn entries(&self) -> Vec<FrontierEntry> {
+        match self {
+            Queue::Fifo(queue) => queue.iter().cloned().collect(),
+            Queue::Priority { heap, .. } => {
+                let mut scored: Vec<&Scored> = heap.iter().collect();
+                scored.sort_by(|a, b| b.cmp(a));
+                scored.into_iter().map(|s| s.entry.clone()).collect()
+            }
+        }
+    }
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Queue::Fifo(VecDeque::new())
+    }
+}
+
+impl fmt::Debug for Queue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Queue::Fifo(queue) => f.debug_tuple("Fifo").field(queue).finish(),
+            Queue::Priority { heap, .. } => {
+                f.debug_tuple("Priority").field(heap).finish()
+            }
+        }
+    }
+}
+
+/// Overflow of the in-memory queue, an append-only file of `depth\turl`
+/// lines read back from `read_pos` once memory drains
+#[derive(Debug)]
+struct Spill {
+    file: File,
+    read_pos: u64,
+    pending: usize,
+    max_in_memory: usize,
+}
+
+impl Spill {
+    fn append(&mut self, entry: &FrontierEntry) -> io::Result<()> {
+        writeln!(self.file, "{}\t{}", entry.depth, entry.url)?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Reads back up to `max_in_memory` entries, truncating the file once
+    /// everything in it was read
+    fn take(&mut self) -> io::Result<Vec<FrontierEntry>> {
+        self.file.seek(SeekFrom::Start(self.read_pos))?;
+        let mut reader = BufReader::new(&self.file);
+
+        let mut entries = Vec::new();
+        let mut line = String::new();
+        while entries.len() < self.max_in_memory.max(1)
+            && reader.read_line(&mut line)? > 0
+        {
+            self.read_pos += line.len() as u64;
+            self.pending -= 1;
+            match parse_spill_line(line.trim_end()) {
+                Some(entry) => entries.push(entry),
+                None => {
+                    warn!(line = %line.trim_end(), "Bad frontier spill line")
+                }
+            }
+            line.clear();
+        }
+
+        if self.pending == 0 {
+            self.file.set_len(0)?;
+            self.read_pos = 0;
+        }
+
+        Ok(entries)
+    }
+}
+
+fn parse_spill_line(line: &str) -> Option<FrontierEntry> {
+    let (depth, url) = line.split_once('\t')?;
+    Some(FrontierEntry {
+        url: url.to_owned(),
+        depth: depth.parse().ok()?,
+    })
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: Queue,
+    seen: HashSet<String>,
+    spill: Option<Spill>,
+
+    // popped but not yet finished, these may still enqueue more urls
+    in_flight: Vec<FrontierEntry>,
+    closed: bool,
+}
+
+impl State {
+    fn enqueue(&mut self, entry: FrontierEntry) {
+        // once anything is spilled new entries queue up behind it
+        if let Some(spill) = &mut self.spill
+            && (spill.pending > 0 || self.queue.len() >= spill.max_in_memory)
+        {
+            match spill.append(&entry) {
+                Ok(()) => return,
+                Err(e) => {
+                    warn!("Frontier spill failed, keeping in memory: {}", e)
+                }
+            }
+        }
+
+        self.queue.push(entry);
+    }
+
+    fn pop(&mut self) -> Option<FrontierEntry> {
+        if self.queue.len() == 0
+            && let Some(spill) = &mut self.spill
+            && spill.pending > 0
+        {
+            match spill.take() {
+                Ok(entries) => {
+                    entries.into_iter().for_each(|e| self.queue.push(e))
+                }
+                Err(e) => {
+                    warn!(
+                        lost = spill.pending,
+                        "Frontier spill unreadable: {}", e
+                    );
+                    spill.pending = 0;
+                }
+            }
+        }
+
+        self.queue.pop()
+    }
+
+    fn spilled(&self) -> usize {
+        self.spill.as_ref().map_or(0, |s| s.pending)
+    }
+}
+
+/// Shared work queue for crawl workers
+///
+/// Tracks in-flight fetches next to the queue so that "empty" can be told
+/// apart from "done": a worker mid-fetch may be about to enqueue more urls.
+#[derive(Debug, Default)]
+pub struct Frontier {
+    state: Mutex<State>,
+    notify: Notify,
+
+    // deepest entry push accepts, unlimited if None
+    radius: Option<usize>,
+}
+
+impl Frontier {
+    pub fn new() -> Frontier {
+        Frontier::default()
+    }
+
+    pub fn with_policy(policy: FrontierPolicy) -> Frontier {
+        let queue = match policy {
+            FrontierPolicy::Fifo => Queue::default(),
+            FrontierPolicy::Priority(scorer) => Queue::Priority {
+                heap: BinaryHeap::new(),
+                scorer,
+                next_seq: 0,
+            },
+        };
+
+        Frontier {
+            state: Mutex::new(State {
+                queue,
+                ..Default::default()
+            }),
+            notify: Notify::new(),
+            radius: None,
+        }
+    }
+
+    /// Refuse entries deeper than `radius` hops from their seed
+    pub fn with_radius(mut self, radius: usize) -> Frontier {
+        self.radius = Some(radius);
+        self
+    }
+
+    /// Returns false if the url was already enqueued at some point, or is
+    /// beyond the radius
+    ///
+    /// A refused url isn't marked seen, so a shorter path found later can
+    /// still enqueue it.
+    pub fn push(&self, url: &str, depth: usize) -> bool {
+        if self.radius.is_some_and(|radius| depth > radius) {
+            return false;
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.closed || !state.seen.insert(url.to_owned()) {
+                return false;
+            }
+
+            state.enqueue(FrontierEntry {
+                url: url.to_owned(),
+                depth,
+            });
+        }
+
+        self.notify.notify_waiters();
+        true
+    }
+
+    /// Keeps at most `max_in_memory` entries in memory, the rest go to an
+    /// append-only file at `path` until the in-memory part drains
+    ///
+    /// Entries a previous run left in the file are enqueued again, except
+    /// any marked seen by then. Returns how many were.
+    pub fn spill_to(
+        &self,
+        path: impl AsRef<Path>,
+        max_in_memory: usize,
+    ) -> io::Result<usize> {
+        let path = path.as_ref();
+        let previous = path.with_extension("old");
+        let leftover = match fs::rename(path, &previous) {
+            Ok(()) => Some(File::open(&previous)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let had_leftover = leftover.is_some();
+        let mut reloaded = 0;
+        {
+            let mut state = self.state.lock().unwrap();
+            state.spill = Some(Spill {
+                file,
+                read_pos: 0,
+                pending: 0,
+                max_in_memory,
+            });
+
+            if let Some(leftover) = leftover {
+                for line in BufReader::new(leftover).lines() {
+                    let Some(entry) = parse_spill_line(&line?) else {
+                        continue;
+                    };
+                    if state.seen.insert(entry.url.clone()) {
+                        state.enqueue(entry);
+                        reloaded += 1;
+                    }
+                }
+            }
+        }
+
+        if had_leftover {
+            fs::remove_file(&previous)?;
+        }
+        self.notify.notify_waiters();
+
+        Ok(reloaded)
+    }
+
+    /// Makes later pushes of `url` no-ops without enqueueing it
+    pub fn mark_seen(&self, url: &str) {
+        self.state.lock().unwrap().seen.insert(url.to_owned());
+    }
+
+    /// Waits for the next entry, counting it as in flight until `finish`
+    ///
+    /// Returns None once the frontier is closed, or with `idle_shutdown`
+    /// when the queue is empty and nothing is in flight.
+    pub async fn pop(&self, idle_shutdown: bool) -> Option<FrontierEntry> {
+        loop {
+            // registered before checking state so a notify between the
+            // check and the await isn't lost
+            let notified = self.notify.notified();
+
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    return None;
+                }
+
+                if let Some(entry) = state.pop() {
+                    state.in_flight.push(entry.clone());
+                    return Some(entry);
+                }
+
+                if idle_shutdown && state.in_flight.is_empty() {
+                    state.closed = true;
+                    drop(state);
+
+                    self.notify.notify_waiters();
+                    return None;
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Marks a popped entry as done, call after its links were pushed
+    pub fn finish(&self, entry: &FrontierEntry) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(i) =
+                state.in_flight.iter().position(|e| e.url == entry.url)
+            {
+                state.in_flight.swap_remove(i);
+            }
+        }
+
+        self.notify.notify_waiters();
+    }
+
+    /// Stops all workers waiting on `pop`
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.notify.notify_waiters();
+    }
+
+    /// Queued entries, spilled ones included
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.queue.len() + state.spilled()
+    }
+
+    /// Queued entries waiting in the spill file rather than in memory
+    pub fn spilled(&self) -> usize {
+        self.state.lock().unwrap().spilled()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight.len()
+    }
+
+    /// Entries in flight, then the ones queued in memory in pop order,
+    /// what a crawl stopped now would still have to fetch
+    ///
+    /// Spilled entries aren't included, they stay in the spill file.
+    pub fn pending(&self) -> Vec<FrontierEntry> {
+        let state = self.state.lock().unwrap();
+        let mut pending = state.in_flight.clone();
+        pending.extend(state.queue.entries());
+        pending
+    }
+}
@@ -0,0 +1,200 @@
+#![cfg(test)]
+use std::sync::Arc;
+
+use crate::crawler::crawl_tests::{MockFetcher, config_for, wiki};
+use crate::crawler::{
+    CrawlConfig, Crawler, DepthDecayScorer, Fetcher, Frontier, FrontierPolicy,
+    Page, Scorer,
+};
+use crate::graph::core::Graph;
+
+async fn drain(frontier: &Frontier) -> Vec<String> {
+    let mut urls = Vec::new();
+    while let Some(entry) = frontier.pop(true).await {
+        frontier.finish(&entry);
+        urls.push(entry.url);
+    }
+    urls
+}
+
+#[test]
+fn test_depth_decay_halves_per_half_life() {
+    let scorer = DepthDecayScorer { half_life: 2.0 };
+
+    assert_eq!(scorer.score("x", 0), 1.0);
+    assert_eq!(scorer.score("x", 2), 0.5);
+    assert_eq!(scorer.score("x", 4), 0.25);
+
+    let keyword =
+        scorer.decayed(|url| if url.contains("rust") { 4.0 } else { 1.0 });
+    assert_eq!(keyword.score("rust", 2), 2.0);
+    assert_eq!(keyword.score("go", 2), 0.5);
+}
+
+#[tokio::test]
+async fn test_priority_pops_highest_score_first() {
+    let scorer = |url: &str, _depth| url.len() as f64;
+    let frontier = Frontier::with_policy(FrontierPolicy::priority(scorer));
+
+    frontier.push("bb", 0);
+    frontier.push("a", 0);
+    frontier.push("dddd", 0);
+    frontier.push("ccc", 0);
+
+    assert_eq!(drain(&frontier).await, ["dddd", "ccc", "bb", "a"]);
+}
+
+#[tokio::test]
+async fn test_priority_ties_keep_push_order() {
+    let policy = FrontierPolicy::priority(DepthDecayScorer::default());
+    let frontier = Frontier::with_policy(policy);
+
+    frontier.push("deep", 3);
+    frontier.push("first", 1);
+    frontier.push("second", 1);
+    frontier.push("seed", 0);
+
+    assert_eq!(drain(&frontier).await, ["seed", "first", "second", "deep"]);
+}
+
+#[tokio::test]
+async fn test_fifo_is_default() {
+    let frontier = Frontier::new();
+    frontier.push("deep", 3);
+    frontier.push("seed", 0);
+
+    assert_eq!(drain(&frontier).await, ["deep", "seed"]);
+}
+
+/// Records fetch order, single worker so it's deterministic
+struct OrderedFetcher {
+    inner: MockFetcher,
+    order: std::sync::Mutex<Vec<String>>,
+}
+
+impl Fetcher for OrderedFetcher {
+    async fn fetch(&self, url: &str) -> anyhow::Result<Page> {
+        self.order.lock().unwrap().push(url.to_owned());
+        self.inner.fetch(url).await
+    }
+}
+
+#[tokio::test]
+async fn test_priority_crawl_follows_promising_branch() {
+    let fetcher = Arc::new(OrderedFetcher {
+        inner: MockFetcher::new(&[
+            ("Start", &["Boring", "Rust"]),
+            ("Boring", &[]),
+            ("Rust", &["Rust_Book"]),
+            ("Rust_Book", &[]),
+        ]),
+        order: Default::default(),
+    });
+
+    let scorer = DepthDecayScorer::default()
+        .decayed(|url| if url.contains("Rust") { 4.0 } else { 1.0 });
+    let config = CrawlConfig {
+        workers: 1,
+        policy: FrontierPolicy::priority(scorer),
+        ..config_for("Start")
+    };
+
+    let (graph, _rx) = Graph::new();
+    let crawler = Crawler::new(config, Arc::new(graph), fetcher.clone());
+    crawler.run().await.unwrap();
+
+    // Rust_Book (4 * 0.25) still beats Boring (1 * 0.5)
+    let order = fetcher.order.lock().unwrap().clone();
+    assert_eq!(
+        order,
+        [
+            wiki("Start"),
+            wiki("Rust"),
+            wiki("Rust_Book"),
+            wiki("Boring")
+        ]
+    );
+}
+
+#[test]
+fn test_radius_refuses_deeper_entries() {
+    let frontier = Frontier::new().with_radius(1);
+
+    assert!(frontier.push("a", 0));
+    assert!(!frontier.push("far", 2));
+    assert!(frontier.push("b", 1));
+    assert_eq!(frontier.len(), 2);
+
+    // refused urls stay unseen, a shorter path still gets them in
+    assert!(frontier.push("far", 1));
+}
+
+#[tokio::test]
+async fn test_spill_keeps_memory_bounded_and_fifo_order() {
+    let path = std::env::temp_dir().join("mycelia_spill_bounded.frontier");
+    let _ = std::fs::remove_file(&path);
+
+    let frontier = Frontier::new();
+    assert_eq!(frontier.spill_to(&path, 2).unwrap(), 0);
+
+    let urls: Vec<String> = (0..10).map(|i| format!("u{}", i)).collect();
+    for url in &urls {
+        frontier.push(url, 0);
+        assert!(frontier.len() - frontier.spilled() <= 2);
+    }
+    assert_eq!(frontier.len(), 10);
+    assert_eq!(frontier.spilled(), 8);
+
+    // popping pulls the spill back in batches, still at most 2 in memory
+    let mut popped = Vec::new();
+    while let Some(entry) = frontier.pop(true).await {
+        assert!(frontier.len() - frontier.spilled() <= 2);
+        frontier.finish(&entry);
+        popped.push(entry.url);
+    }
+    assert_eq!(popped, urls);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_spill_file_reloaded_on_next_run() {
+    let path = std::env::temp_dir().join("mycelia_spill_reload.frontier");
+    let _ = std::fs::remove_file(&path);
+
+    let first = Frontier::new();
+    first.spill_to(&path, 1).unwrap();
+    for url in ["a", "b", "c"] {
+        first.push(url, 1);
+    }
+    drop(first);
+
+    let second = Frontier::new();
+    second.mark_seen("b");
+    assert_eq!(second.spill_to(&path, 1).unwrap(), 1);
+    second.push("a", 0);
+
+    // "a" never reached the file, "b" was already seen, and reloaded
+    // entries queue ahead of anything pushed afterwards
+    assert_eq!(drain(&second).await, ["c", "a"]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_pending_lists_in_flight_then_queued() {
+    let frontier = Frontier::new();
+    frontier.push("a", 0);
+    frontier.push("b", 1);
+    frontier.push("c", 1);
+
+    let entry = frontier.pop(true).await.unwrap();
+    let urls: Vec<String> =
+        frontier.pending().into_iter().map(|e| e.url).collect();
+    assert_eq!(urls, ["a", "b", "c"]);
+
+    frontier.finish(&entry);
+    assert_eq!(frontier.pending().len(), 2);
+    assert_eq!(frontier.in_flight(), 0);
+}
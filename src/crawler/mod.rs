@@ -0,0 +1,625 @@
+pub mod archive;
+pub mod archive_tests;
+pub mod checkpoint;
+pub mod checkpoint_tests;
+pub mod config;
+pub mod crawl_tests;
+pub mod extract;
+pub mod extract_tests;
+pub mod fetch;
+pub mod fetch_tests;
+pub mod frontier;
+pub mod frontier_tests;
+pub mod politeness;
+pub mod politeness_tests;
+pub mod report;
+pub mod report_tests;
+pub mod retry;
+pub mod retry_tests;
+pub mod score;
+pub mod sitemap;
+pub mod sitemap_tests;
+pub mod trap;
+pub mod trap_tests;
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+use anyhow::{Context, Result};
+use rand::{SeedableRng, rngs::SmallRng};
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{info, instrument, warn};
+
+use crate::crawler::checkpoint::CheckpointWriter;
+use crate::graph::core::{Graph, GraphEvent};
+use crate::graph::error::GraphError;
+use crate::graph::snapshot::DISCOVERED_AT;
+
+pub use archive::PageArchive;
+pub use checkpoint::Checkpoint;
+pub use config::CrawlConfig;
+pub use extract::{
+    DEFAULT_ARTICLE_REGEX, DEFAULT_MAX_LINKS, ExtractedLinks, LinkKind, Scope,
+    classify_links, classify_links_with, classify_url, extract_article_names,
+    extract_links, extract_links_with, is_article_url, normalize_article_url,
+};
+pub use fetch::{
+    DEFAULT_USER_AGENT, Fetcher, HttpFetcher, HttpStatus, Page, Timeouts,
+    is_timeout, status_of,
+};
+pub use frontier::{Frontier, FrontierEntry};
+pub use politeness::HostLimiter;
+pub use report::{CrawlReport, HostReport, ReportRecorder};
+pub use retry::{RetryPolicy, fetch_with_retry, is_retryable};
+pub use score::{DepthDecayScorer, FrontierPolicy, Scorer};
+pub use sitemap::{Sitemap, parse_sitemap};
+pub use trap::{TrapDetector, url_pattern};
+
+// sitemap indexes are one level deep per the protocol, allow some slack
+const MAX_SITEMAP_NESTING: usize = 4;
+
+pub struct Crawler<F: Fetcher> {
+    config: CrawlConfig,
+    graph: Arc<Graph>,
+    fetcher: Arc<F>,
+    frontier: Arc<Frontier>,
+    limiter: Arc<HostLimiter>,
+    recorder: Arc<ReportRecorder>,
+    discovery: Option<Arc<Discovery>>,
+    trap: Option<Arc<TrapDetector>>,
+
+    // one permit per request in flight, see max_concurrent_requests
+    requests: Option<Arc<Semaphore>>,
+
+    // pages processed, held while emitting so CrawlProgress counts up and
+    // while capturing a checkpoint so none finishes mid-capture
+    visited: Arc<Mutex<HashSet<String>>>,
+
+    checkpoints: Arc<CheckpointWriter>,
+}
+
+impl<F: Fetcher> Crawler<F> {
+    pub fn new(config: CrawlConfig, graph: Arc<Graph>, fetcher: F) -> Self {
+        let mut frontier = Frontier::with_policy(config.policy.clone());
+        if let Some(radius) = config.radius {
+            frontier = frontier.with_radius(radius);
+        }
+        let limiter =
+            HostLimiter::new(config.request_delay, config.delay_jitter);
+        let discovery = config
+            .record_discovery
+            .then(|| Arc::new(Discovery::resuming(&graph)));
+        let trap = config
+            .trap_threshold
+            .map(|k| Arc::new(TrapDetector::new(k)));
+        let requests = config
+            .max_concurrent_requests
+            .map(|n| Arc::new(Semaphore::new(n.max(1))));
+
+        Crawler {
+            config,
+            graph,
+            fetcher: Arc::new(fetcher),
+            frontier: Arc::new(frontier),
+            limiter: Arc::new(limiter),
+            recorder: Arc::new(ReportRecorder::new()),
+            discovery,
+            trap,
+            requests,
+            visited: Arc::default(),
+            checkpoints: Arc::default(),
+        }
+    }
+
+    /// Handle for enqueueing urls or closing the crawl from outside
+    pub fn frontier(&self) -> Arc<Frontier> {
+        self.frontier.clone()
+    }
+
+    /// Crawls from `start_url` (or the `sitemap` urls if set) until the
+    /// frontier is closed, or until it runs dry when `idle_shutdown` is
+    /// set, then returns the graph
+    pub async fn run(self) -> Result<Arc<Graph>> {
+        self.run_with_report().await.map(|(graph, _)| graph)
+    }
+
+    /// run, also returning what the crawl did to the hosts it visited,
+    /// logged as JSON too if `log_report` is set
+    ///
+    /// Dropping the future stops the workers too, a crawl cut short that
+    /// way resumes from its last checkpoint.
+    pub async fn run_with_report(self) -> Result<(Arc<Graph>, CrawlReport)> {
+        if let Some(path) = &self.config.checkpoint_path
+            && path.exists()
+        {
+            let checkpoint = Checkpoint::load(path)?;
+            let queued = self.resume(&checkpoint)?;
+            info!(
+                visited = checkpoint.visited.len(),
+                queued,
+                path = %path.display(),
+                "Resuming from checkpoint"
+            );
+        }
+
+        if self.config.only_new {
+            let seeded = self.seed_unexpanded();
+            info!(seeded, "Seeded unexpanded pages");
+        }
+
+        // after seed_unexpanded, so spilled pages it marked seen are skipped
+        if let Some(max) = self.config.max_frontier {
+            let path = self.config.spill_path.clone().unwrap_or_else(|| {
+                std::env::temp_dir()
+                    .join(format!("mycelia-{}.frontier", std::process::id()))
+            });
+            let reloaded = self
+                .frontier
+                .spill_to(&path, max)
+                .with_context(|| format!("opening {}", path.display()))?;
+            info!(reloaded, path = %path.display(), "Spilling frontier");
+        }
+
+        let archive = match &self.config.archive_dir {
+            Some(dir) => {
+                Some(Arc::new(PageArchive::create(dir).with_context(|| {
+                    format!("creating archive in {}", dir.display())
+                })?))
+            }
+            None => None,
+        };
+
+        match &self.config.sitemap {
+            Some(sitemap) => {
+                let seeded = self.seed_from_sitemap(sitemap).await?;
+                info!(seeded, sitemap = %sitemap, "Seeded from sitemap");
+            }
+            None => {
+                let start = self.config.start_url.clone();
+                let root = self.graph.get_root();
+                self.graph.add_edge(root.get_data(), &start)?;
+                if self.frontier.push(&start, 0) {
+                    self.discovered(&start)?;
+                }
+            }
+        }
+
+        let mut workers = JoinSet::new();
+        for id in 0..self.config.workers.max(1) {
+            let worker = Worker {
+                id,
+                config: self.config.clone(),
+                graph: self.graph.clone(),
+                fetcher: self.fetcher.clone(),
+                frontier: self.frontier.clone(),
+                limiter: self.limiter.clone(),
+                recorder: self.recorder.clone(),
+                discovery: self.discovery.clone(),
+                trap: self.trap.clone(),
+                requests: self.requests.clone(),
+                visited: self.visited.clone(),
+                checkpoints: self.checkpoints.clone(),
+                archive: archive.clone(),
+                rng: SmallRng::from_os_rng(),
+            };
+            workers.spawn(worker.run());
+        }
+
+        while let Some(finished) = workers.join_next().await {
+            finished?;
+        }
+
+        let stats = self.graph.stats();
+        info!(
+            nodes = stats.node_count,
+            edges = stats.edge_count,
+            max_out_degree = stats.max_out_degree,
+            components = stats.num_components,
+            has_cycle = stats.has_cycle,
+            "Crawl finished"
+        );
+
+        if let Some(path) = &self.config.checkpoint_path {
+            let checkpoint = {
+                let visited = self.visited.lock().unwrap();
+                Checkpoint::capture(&self.graph, &self.frontier, &visited)
+            };
+            self.checkpoints.save(checkpoint, path.clone()).await?;
+        }
+
+        if let Some(archive) = archive {
+            archive.write_manifest().with_context(|| {
+                format!("writing manifest in {}", archive.dir().display())
+            })?;
+        }
+
+        let report = self.recorder.report();
+        if self.config.log_report {
+            info!(report = %serde_json::to_string(&report)?, "Crawl report");
+        }
+
+        Ok((self.graph, report))
+    }
+}
+
+impl<F: Fetcher> Crawler<F> {
+    /// Loads the checkpoint's graph into this one, marks its visited pages
+    /// as seen and enqueues its pending ones, returns how many were
+    ///
+    /// With `only_new` visited pages aren't marked, `seed_unexpanded` is
+    /// run next and refetches the ones that failed.
+    ///
+    /// Meant for a fresh graph, nodes and edges it already has are kept.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<usize> {
+        self.graph
+            .load_snapshot(&checkpoint.graph)
+            .context("loading checkpoint graph")?;
+        if let Some(discovery) = &self.discovery {
+            discovery.catch_up(&self.graph);
+        }
+
+        let mut visited = self.visited.lock().unwrap();
+        for url in &checkpoint.visited {
+            if !self.config.only_new {
+                self.frontier.mark_seen(url);
+            }
+            visited.insert(url.clone());
+        }
+
+        Ok(checkpoint
+            .pending
+            .iter()
+            .filter(|entry| self.frontier.push(&entry.url, entry.depth))
+            .count())
+    }
+
+    /// Enqueues the graph's unexpanded pages and marks the rest as seen,
+    /// returns how many were enqueued
+    ///
+    /// A page counts as expanded once it has children or a fetch_ms, so
+    /// interior pages aren't refetched while links to them still become
+    /// edges, and pages whose fetch failed are tried again.
+    pub fn seed_unexpanded(&self) -> usize {
+        let snapshot = self.graph.snapshot();
+        let root = self.graph.get_root();
+        let parents: HashSet<&str> =
+            snapshot.edges.iter().map(|(p, _)| p.as_str()).collect();
+
+        let mut seeded = 0;
+        for name in &snapshot.nodes {
+            if name.as_str() == root.get_data() {
+                continue;
+            }
+
+            let fetched = snapshot
+                .meta
+                .get(name)
+                .is_some_and(|m| m.contains_key("fetch_ms"));
+
+            if fetched || parents.contains(name.as_str()) {
+                self.frontier.mark_seen(name);
+            } else if self.frontier.push(name, 0) {
+                seeded += 1;
+            }
+        }
+
+        seeded
+    }
+
+    fn discovered(&self, url: &str) -> Result<(), GraphError> {
+        match &self.discovery {
+            Some(discovery) => discovery.stamp(&self.graph, url),
+            None => Ok(()),
+        }
+    }
+
+    /// Enqueues every in-scope page url of a sitemap as a child of root,
+    /// following sitemap indexes, returns how many were new
+    ///
+    /// Only a failure of the top-level sitemap is an error, broken
+    /// sub-sitemaps are logged and skipped.
+    pub async fn seed_from_sitemap(&self, url: &str) -> Result<usize> {
+        let root = self.graph.get_root();
+        let mut pending = VecDeque::from([(url.to_owned(), 0)]);
+        let mut seen = HashSet::new();
+        let mut seeded = 0;
+
+        while let Some((url, nesting)) = pending.pop_front() {
+            if !seen.insert(url.clone()) {
+                continue;
+            }
+
+            let sitemap = match self.fetch_sitemap(&url).await {
+                Ok(sitemap) => sitemap,
+                Err(e) if nesting > 0 => {
+                    warn!(url = %url, "Skipping sitemap: {:?}", e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            match sitemap {
+                Sitemap::Index(_) if nesting >= MAX_SITEMAP_NESTING => {
+                    warn!(url = %url, "Sitemap index nested too deep");
+                }
+                Sitemap::Index(children) => {
+                    pending
+                        .extend(children.into_iter().map(|c| (c, nesting + 1)));
+                }
+                Sitemap::UrlSet(urls) => {
+                    let scope = &self.config.scope;
+                    for page in
+                        urls.iter().filter_map(|u| scope.normalize_article(u))
+                    {
+                        if self.frontier.push(&page, 0) {
+                            self.graph.add_edge(root.get_data(), &page)?;
+                            self.discovered(&page)?;
+                            seeded += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(seeded)
+    }
+
+    async fn fetch_sitemap(&self, url: &str) -> Result<Sitemap> {
+        let mut rng = SmallRng::from_os_rng();
+        self.limiter.wait(url, &mut rng).await;
+        let page = fetch_with_retry(
+            &*self.fetcher,
+            &self.recorder,
+            &self.limiter,
+            url,
+            &self.config.retry,
+            &mut rng,
+        )
+        .await?;
+        parse_sitemap(page.body.as_bytes())
+    }
+}
+
+struct Worker<F: Fetcher> {
+    id: usize,
+    config: CrawlConfig,
+    graph: Arc<Graph>,
+    fetcher: Arc<F>,
+    frontier: Arc<Frontier>,
+    limiter: Arc<HostLimiter>,
+    recorder: Arc<ReportRecorder>,
+    discovery: Option<Arc<Discovery>>,
+    trap: Option<Arc<TrapDetector>>,
+    requests: Option<Arc<Semaphore>>,
+    visited: Arc<Mutex<HashSet<String>>>,
+    checkpoints: Arc<CheckpointWriter>,
+    archive: Option<Arc<PageArchive>>,
+
+    // per worker so jitter draws never contend
+    rng: SmallRng,
+}
+
+impl<F: Fetcher> Worker<F> {
+    async fn run(mut self) {
+        while let Some(entry) =
+            self.frontier.pop(self.config.idle_shutdown).await
+        {
+            if let Err(e) = self.process(&entry).await {
+                warn!(worker = self.id, url = %entry.url, "Fetch failed: {:?}", e);
+            }
+
+            self.progress(&entry).await;
+
+            // only after links are pushed, otherwise others may exit early
+            self.frontier.finish(&entry);
+        }
+    }
+
+    async fn progress(&self, entry: &FrontierEntry) {
+        let checkpoint = {
+            let mut visited = self.visited.lock().unwrap();
+            visited.insert(entry.url.clone());
+
+            // best-effort, a crawl without a visualizer has no one
+            // listening
+            let _ = self.graph.emit(GraphEvent::CrawlProgress {
+                visited: visited.len(),
+                queued: self.frontier.len(),
+                depth: entry.depth,
+            });
+
+            let due = visited
+                .len()
+                .is_multiple_of(self.config.checkpoint_every.max(1));
+            (self.config.checkpoint_path.is_some() && due).then(|| {
+                Checkpoint::capture(&self.graph, &self.frontier, &visited)
+            })
+        };
+
+        if let Some(checkpoint) = checkpoint {
+            self.checkpoint(checkpoint).await;
+        }
+    }
+
+    /// Saves the crawl so far, a failure is only logged so it carries on
+    ///
+    /// Captured under the visited lock, written after it's released. The
+    /// page just processed is both visited and still in flight here,
+    /// resume skips it since visited pages are marked seen before pending
+    /// ones are pushed.
+    async fn checkpoint(&self, checkpoint: Checkpoint) {
+        let Some(path) = &self.config.checkpoint_path else {
+            return;
+        };
+        if let Err(e) = self.checkpoints.save(checkpoint, path.clone()).await
+        {
+            warn!(worker = self.id, "Saving checkpoint failed: {:?}", e);
+        }
+    }
+
+    #[instrument(skip(self), fields(worker = self.id))]
+    async fn process(&mut self, entry: &FrontierEntry) -> Result<()> {
+        self.limiter.wait(&entry.url, &mut self.rng).await;
+
+        // held for the fetch only, extraction doesn't count as a request
+        let permit = match &self.requests {
+            Some(requests) => Some(requests.acquire().await?),
+            None => None,
+        };
+        let started = Instant::now();
+        let page = fetch_with_retry(
+            &*self.fetcher,
+            &self.recorder,
+            &self.limiter,
+            &entry.url,
+            &self.config.retry,
+            &mut self.rng,
+        )
+        .await?;
+        drop(permit);
+        let fetch_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        // urls pushed through Crawler::frontier hang off root like a seed
+        if self.graph.get_node(&entry.url).is_none() {
+            let root = self.graph.get_root();
+            self.graph.add_edge(root.get_data(), &entry.url)?;
+            if let Some(discovery) = &self.discovery {
+                discovery.stamp(&self.graph, &entry.url)?;
+            }
+        }
+        self.graph.set_node_meta(&entry.url, "fetch_ms", fetch_ms)?;
+
+        if let Some(archive) = &self.archive
+            && let Err(e) = archive.save(&entry.url, &page.body)
+        {
+            warn!(url = %entry.url, "Archiving page failed: {}", e);
+        }
+
+        let ExtractedLinks {
+            articles,
+            categories,
+        } = classify_links_with(
+            &page.body,
+            &self.config.scope,
+            self.config.max_links,
+        )?;
+        let crawl_categories = self.config.crawl_categories;
+        let links = articles
+            .into_iter()
+            .map(|link| (LinkKind::Article, link))
+            .chain(
+                categories
+                    .into_iter()
+                    .filter(|_| crawl_categories)
+                    .map(|link| (LinkKind::Category, link)),
+            );
+
+        // one add_edges per page, so workers contend per page not per link
+        let max_name_len = self.graph.max_name_len();
+        let mut batch = Vec::new();
+        for (kind, link) in links {
+            if self.config.drop_self_loops
+                && (link == entry.url || link == page.url)
+            {
+                continue;
+            }
+            if let Some(max) = max_name_len
+                && link.len() > max
+            {
+                let e = GraphError::NameTooLong {
+                    len: link.len(),
+                    max,
+                };
+                warn!(url = %entry.url, "Skipping link: {}", e);
+                continue;
+            }
+            batch.push((kind, link));
+        }
+
+        let children: Vec<&str> =
+            batch.iter().map(|(_, link)| link.as_str()).collect();
+        self.graph.add_edges(&entry.url, &children)?;
+
+        for (kind, link) in batch {
+            if kind == LinkKind::Category {
+                self.tag_category(&link)?;
+            }
+
+            // the edge is still recorded, the trap just isn't followed
+            if self.trap.as_ref().is_some_and(|trap| !trap.admit(&link)) {
+                continue;
+            }
+            if self.frontier.push(&link, entry.depth + 1)
+                && let Some(discovery) = &self.discovery
+            {
+                discovery.stamp(&self.graph, &link)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marks a node as a category page with `kind: "category"` meta, once
+    fn tag_category(&self, url: &str) -> Result<()> {
+        let tagged = self
+            .graph
+            .get_node(url)
+            .is_some_and(|n| n.get_meta("kind").is_some());
+        if !tagged {
+            self.graph.set_node_meta(url, "kind", "category")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Hands out `discovered_at` numbers, shared by the crawler and its workers
+#[derive(Debug, Default)]
+struct Discovery {
+    next: AtomicU64,
+}
+
+impl Discovery {
+    /// Continues after the highest number already in `graph`, so a resumed
+    /// crawl keeps counting
+    fn resuming(graph: &Graph) -> Discovery {
+        Discovery {
+            next: AtomicU64::new(Self::next_after(graph)),
+        }
+    }
+
+    /// Skips past the numbers `graph` has gained since, e.g. from a
+    /// checkpoint loaded into it
+    fn catch_up(&self, graph: &Graph) {
+        self.next.fetch_max(Self::next_after(graph), Ordering::Relaxed);
+    }
+
+    fn next_after(graph: &Graph) -> u64 {
+        graph
+            .snapshot()
+            .meta
+            .values()
+            .filter_map(|m| m.get(DISCOVERED_AT)?.as_u64())
+            .max()
+            .map_or(0, |max| max + 1)
+    }
+
+    /// Stamps `url` with the next number, unless it has one already
+    fn stamp(&self, graph: &Graph, url: &str) -> Result<(), GraphError> {
+        let stamped = graph
+            .get_node(url)
+            .is_some_and(|n| n.get_meta(DISCOVERED_AT).is_some());
+        if stamped {
+            return Ok(());
+        }
+
+        let seq = self.next.fetch_add(1, Ordering::Relaxed);
+        graph.set_node_meta(url, DISCOVERED_AT, seq)
+    }
+}
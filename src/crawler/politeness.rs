@@ -0,0 +1,67 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use tracing::trace;
+use url::Url;
+
+/// Spaces out requests to the same host, different hosts never wait on
+/// each other
+///
+/// Every request reserves the host's next free slot, so concurrent
+/// workers queue up behind one another instead of firing together.
+#[derive(Debug, Default)]
+pub struct HostLimiter {
+    delay: Duration,
+    jitter: Duration,
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostLimiter {
+    pub fn new(delay: Duration, jitter: Duration) -> HostLimiter {
+        HostLimiter {
+            delay,
+            jitter,
+            next_slot: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.delay.is_zero() && self.jitter.is_zero()
+    }
+
+    /// Sleeps until `url`'s host may be requested again
+    ///
+    /// The gap after this request is drawn from `[delay, delay + jitter]`
+    /// with the caller's rng, so each worker can keep its own.
+    pub async fn wait(&self, url: &str, rng: &mut impl Rng) {
+        if self.is_noop() {
+            return;
+        }
+
+        let host = host_of(url);
+        let gap = self.delay + self.jitter.mul_f64(rng.random::<f64>());
+
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = next_slot.get(&host).map_or(now, |&t| t.max(now));
+            next_slot.insert(host.clone(), slot + gap);
+            slot
+        };
+
+        trace!(%host, wait = ?slot.saturating_duration_since(Instant::now()));
+        tokio::time::sleep_until(slot.into()).await;
+    }
+}
+
+/// Host part of a url, the whole string if it doesn't parse as one
+pub(crate) fn host_of(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_owned))
+        .unwrap_or_else(|| url.to_owned())
+}
@@ -0,0 +1,87 @@
+#![cfg(test)]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::{SeedableRng, rngs::SmallRng};
+
+use crate::crawler::HostLimiter;
+
+/// Offsets from the start at which each of `n` concurrent waits returned
+async fn release_times(
+    limiter: HostLimiter,
+    url: &str,
+    n: u64,
+) -> Vec<Duration> {
+    let limiter = Arc::new(limiter);
+    let start = Instant::now();
+
+    let handles: Vec<_> = (0..n)
+        .map(|i| {
+            let limiter = limiter.clone();
+            let url = url.to_owned();
+            tokio::spawn(async move {
+                let mut rng = SmallRng::seed_from_u64(i);
+                limiter.wait(&url, &mut rng).await;
+                start.elapsed()
+            })
+        })
+        .collect();
+
+    let mut times = Vec::new();
+    for handle in handles {
+        times.push(handle.await.unwrap());
+    }
+    times.sort();
+    times
+}
+
+fn gaps(times: &[Duration]) -> Vec<Duration> {
+    times.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+#[tokio::test]
+async fn test_without_jitter_requests_cluster() {
+    let limiter = HostLimiter::new(Duration::ZERO, Duration::ZERO);
+    let times = release_times(limiter, "https://a.org/x", 10).await;
+
+    let span = *times.last().unwrap() - times[0];
+    assert!(span < Duration::from_millis(10), "{:?}", span);
+}
+
+#[tokio::test]
+async fn test_jitter_spreads_requests_to_one_host() {
+    let jitter = Duration::from_millis(30);
+    let limiter = HostLimiter::new(Duration::ZERO, jitter);
+    let times = release_times(limiter, "https://a.org/x", 10).await;
+    let gaps = gaps(&times);
+
+    // 9 gaps drawn from [0, 30ms] average ~135ms, 60ms is ~3 sigma below
+    let span = *times.last().unwrap() - times[0];
+    assert!(span > Duration::from_millis(60), "{:?}", span);
+    assert!(gaps.iter().all(|g| *g < jitter + Duration::from_millis(15)));
+
+    // uneven spacing, not one fixed period
+    let min = gaps.iter().min().unwrap();
+    let max = gaps.iter().max().unwrap();
+    assert!(*max - *min > Duration::from_millis(5), "{:?}", gaps);
+}
+
+#[tokio::test]
+async fn test_delay_is_per_host() {
+    let delay = Duration::from_millis(20);
+    let limiter = Arc::new(HostLimiter::new(delay, Duration::ZERO));
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        limiter.wait("https://a.org/page", &mut rng).await;
+    }
+    let a_done = start.elapsed();
+
+    // b.org never requested before, so no waiting behind a.org
+    let b_start = Instant::now();
+    limiter.wait("https://b.org/page", &mut rng).await;
+
+    assert!(a_done >= delay * 2, "{:?}", a_done);
+    assert!(b_start.elapsed() < Duration::from_millis(10));
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::crawler::politeness::host_of;
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// How a crawl treated the hosts it visited, the thing to show a site
+/// owner asking what the crawler did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CrawlReport {
+    /// fetches sent, sitemaps and failed ones included
+    pub requests: usize,
+    pub hosts: BTreeMap<String, HostReport>,
+
+    /// fetches repeated after a failure, not counted in `requests`
+    pub retries: usize,
+
+    /// responses with a 4xx status
+    pub client_errors: usize,
+    /// responses with a 5xx status
+    pub server_errors: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct HostReport {
+    pub requests: usize,
+
+    /// fetches to the host repeated after a failure
+    pub retries: usize,
+
+    /// most requests sent to the host within any one second, retries
+    /// included
+    pub max_per_second: usize,
+}
+
+/// Accumulates a CrawlReport, shared by all workers
+#[derive(Debug, Default)]
+pub struct ReportRecorder {
+    state: Mutex<RecorderState>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    report: CrawlReport,
+
+    // per host, send times of the requests within the last RATE_WINDOW
+    recent: HashMap<String, VecDeque<Instant>>,
+}
+
+impl RecorderState {
+    /// Records a send to `url`'s host now in its rate window, returns the
+    /// host's report
+    fn sent(&mut self, url: &str) -> &mut HostReport {
+        let host = host_of(url);
+        let now = Instant::now();
+
+        let recent = self.recent.entry(host.clone()).or_default();
+        while recent.front().is_some_and(|&t| now - t >= RATE_WINDOW) {
+            recent.pop_front();
+        }
+        recent.push_back(now);
+        let in_window = recent.len();
+
+        let host = self.report.hosts.entry(host).or_default();
+        host.max_per_second = host.max_per_second.max(in_window);
+        host
+    }
+}
+
+impl ReportRecorder {
+    pub fn new() -> ReportRecorder {
+        ReportRecorder::default()
+    }
+
+    /// Counts a request to `url` sent now
+    pub fn request(&self, url: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.report.requests += 1;
+        state.sent(url).requests += 1;
+    }
+
+    /// Counts a response status, only 4xx and 5xx are kept
+    pub fn status(&self, status: u16) {
+        let mut state = self.state.lock().unwrap();
+        match status {
+            400..=499 => state.report.client_errors += 1,
+            500..=599 => state.report.server_errors += 1,
+            _ => {}
+        }
+    }
+
+    /// Counts a retry of `url` sent now
+    pub fn retry(&self, url: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.report.retries += 1;
+        state.sent(url).retries += 1;
+    }
+
+    pub fn report(&self) -> CrawlReport {
+        self.state.lock().unwrap().report.clone()
+    }
+}
@@ -0,0 +1,86 @@
+#![cfg(test)]
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::crawler::crawl_tests::{MockFetcher, config_for, wiki};
+use crate::crawler::{
+    CrawlConfig, CrawlReport, Crawler, HostReport, ReportRecorder, RetryPolicy,
+    status_of,
+};
+use crate::graph::core::Graph;
+
+#[test]
+fn test_recorder_counts_error_statuses_only() {
+    let recorder = ReportRecorder::new();
+    for status in [200, 301, 404, 410, 500, 503, 599] {
+        recorder.status(status);
+    }
+
+    let report = recorder.report();
+    assert_eq!(report.client_errors, 2);
+    assert_eq!(report.server_errors, 3);
+}
+
+#[test]
+fn test_status_of_ignores_errors_without_a_response() {
+    assert_eq!(status_of(&anyhow::anyhow!("connection refused")), None);
+}
+
+#[tokio::test]
+async fn test_report_matches_mock_crawl() {
+    let mut fetcher = MockFetcher::new(&[
+        ("A", &["B", "C", "Gone", "Broken"]),
+        ("B", &["C"]),
+        ("C", &[]),
+        ("Broken", &[]),
+    ]);
+    fetcher.statuses.insert(wiki("Broken"), 502);
+
+    let config = CrawlConfig {
+        retry: RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+        },
+        ..config_for("A")
+    };
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher);
+    let (_, report) = crawler.run_with_report().await.unwrap();
+
+    // Gone is a 404, Broken a 502 retried twice, every attempt failing;
+    // the retries count towards the host's rate but not its requests
+    assert_eq!(
+        report,
+        CrawlReport {
+            requests: 5,
+            hosts: [(
+                String::from("en.wikipedia.org"),
+                HostReport {
+                    requests: 5,
+                    retries: 2,
+                    max_per_second: 7,
+                }
+            )]
+            .into(),
+            retries: 2,
+            client_errors: 1,
+            server_errors: 3,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_report_max_per_second_follows_request_delay() {
+    let fetcher = MockFetcher::new(&[("A", &["B"]), ("B", &["C"]), ("C", &[])]);
+
+    let mut config = config_for("A");
+    config.request_delay = Duration::from_millis(600);
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config, graph, fetcher);
+    let (_, report) = crawler.run_with_report().await.unwrap();
+
+    // sent at 0, 600 and 1200ms, no second holds more than two
+    assert_eq!(report.requests, 3);
+    assert_eq!(report.hosts["en.wikipedia.org"].max_per_second, 2);
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use rand::Rng;
+use tracing::debug;
+
+use crate::crawler::fetch::{Fetcher, HttpStatus, Page, status_of};
+use crate::crawler::politeness::HostLimiter;
+use crate::crawler::report::ReportRecorder;
+
+/// How often and how patiently a failed fetch is repeated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// retries after the first attempt, 0 never retries
+    pub max_retries: u32,
+
+    /// backoff before the first retry, doubled for every one after
+    pub base_delay: Duration,
+
+    /// longest backoff, a longer `Retry-After` is cut to it too
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (from 0), somewhere in the upper
+    /// half of the doubled base delay so workers don't retry in lockstep
+    pub fn backoff(&self, retry: u32, rng: &mut impl Rng) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_delay);
+        let half = delay / 2;
+        half + half.mul_f64(rng.random())
+    }
+}
+
+/// Whether a fetch error may go away on its own: 429, a 5xx, or no
+/// response at all because connecting, sending or reading failed
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    if let Some(status) = status_of(err) {
+        return status == 429 || (500..=599).contains(&status);
+    }
+    err.downcast_ref::<reqwest::Error>().is_some_and(|e| {
+        e.is_connect() || e.is_timeout() || e.is_request() || e.is_body()
+    })
+}
+
+/// Fetches `url`, retrying errors `is_retryable` accepts up to
+/// `policy.max_retries` times, anything else (4xx) fails right away
+///
+/// The first attempt counts as a request in `recorder`, the rest as
+/// retries, every status as usual. A `Retry-After` on the response is
+/// waited out instead of the backoff.
+///
+/// Each retry also waits for its slot in `limiter`, a retry is one more
+/// request to the host. Spacing the first attempt is up to the caller,
+/// so it can wait before taking a request permit.
+pub async fn fetch_with_retry<F: Fetcher>(
+    fetcher: &F,
+    recorder: &ReportRecorder,
+    limiter: &HostLimiter,
+    url: &str,
+    policy: &RetryPolicy,
+    rng: &mut impl Rng,
+) -> Result<Page> {
+    recorder.request(url);
+
+    let mut retry = 0;
+    loop {
+        let res = fetcher.fetch(url).await;
+        match &res {
+            Ok(page) => recorder.status(page.status),
+            Err(e) => {
+                if let Some(status) = status_of(e) {
+                    recorder.status(status);
+                }
+            }
+        }
+
+        let err = match res {
+            Err(e) if retry < policy.max_retries && is_retryable(&e) => e,
+            res => return res,
+        };
+
+        let wait = err
+            .downcast_ref::<HttpStatus>()
+            .and_then(|e| e.retry_after)
+            .unwrap_or_else(|| policy.backoff(retry, rng))
+            .min(policy.max_delay);
+        debug!(url, retry, ?wait, "Retrying fetch: {:?}", err);
+
+        tokio::time::sleep(wait).await;
+        limiter.wait(url, rng).await;
+        recorder.retry(url);
+        retry += 1;
+    }
+}
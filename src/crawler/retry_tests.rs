@@ -0,0 +1,177 @@
+#![cfg(test)]
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use actix_web::{App, HttpResponse, HttpServer, web};
+use rand::{SeedableRng, rngs::SmallRng};
+
+use crate::crawler::{
+    Fetcher, HostLimiter, HttpFetcher, ReportRecorder, RetryPolicy,
+    fetch_with_retry, status_of,
+};
+
+/// A server answering `/flaky` with `failures` 503s before a 200, and
+/// `/missing` with a 404, returns its port and a hit counter
+fn spawn_flaky_server(failures: usize) -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let counter = hits.clone();
+    let server = HttpServer::new(move || {
+        let counter = counter.clone();
+        App::new()
+            .route(
+                "/flaky",
+                web::get().to(move || {
+                    let hit = counter.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        if hit < failures {
+                            HttpResponse::ServiceUnavailable()
+                                .insert_header(("Retry-After", "0"))
+                                .finish()
+                        } else {
+                            HttpResponse::Ok().body("<html></html>")
+                        }
+                    }
+                }),
+            )
+            .route("/missing", web::get().to(HttpResponse::NotFound))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+
+    (port, hits)
+}
+
+fn policy(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    }
+}
+
+#[actix_web::test]
+async fn test_retries_server_errors_until_success() {
+    let (port, hits) = spawn_flaky_server(2);
+    let url = format!("http://127.0.0.1:{}/flaky", port);
+    let recorder = ReportRecorder::new();
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    let page = fetch_with_retry(
+        &HttpFetcher::default(),
+        &recorder,
+        &HostLimiter::default(),
+        &url,
+        &policy(3),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(page.status, 200);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    let report = recorder.report();
+    assert_eq!(report.requests, 1);
+    assert_eq!(report.retries, 2);
+    assert_eq!(report.server_errors, 2);
+}
+
+#[actix_web::test]
+async fn test_retries_wait_for_the_host_delay() {
+    let (port, hits) = spawn_flaky_server(2);
+    let url = format!("http://127.0.0.1:{}/flaky", port);
+    let delay = Duration::from_millis(40);
+    let limiter = HostLimiter::new(delay, Duration::ZERO);
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    // the first attempt takes the host's slot like a worker would
+    let start = Instant::now();
+    limiter.wait(&url, &mut rng).await;
+    let page = fetch_with_retry(
+        &HttpFetcher::default(),
+        &ReportRecorder::new(),
+        &limiter,
+        &url,
+        &policy(3),
+        &mut rng,
+    )
+    .await
+    .unwrap();
+
+    // Retry-After 0 and a 1ms backoff, the 2 retries wait on the limiter
+    assert_eq!(page.status, 200);
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+    assert!(start.elapsed() >= delay * 2, "{:?}", start.elapsed());
+}
+
+#[actix_web::test]
+async fn test_gives_up_after_max_retries_and_on_client_errors() {
+    let (port, hits) = spawn_flaky_server(usize::MAX);
+    let fetcher = HttpFetcher::default();
+    let recorder = ReportRecorder::new();
+    let limiter = HostLimiter::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    let url = format!("http://127.0.0.1:{}/flaky", port);
+    let err = fetch_with_retry(
+        &fetcher,
+        &recorder,
+        &limiter,
+        &url,
+        &policy(2),
+        &mut rng,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(status_of(&err), Some(503));
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    // a 404 won't change on its own, so it's never retried
+    let url = format!("http://127.0.0.1:{}/missing", port);
+    let err = fetch_with_retry(
+        &fetcher,
+        &recorder,
+        &limiter,
+        &url,
+        &policy(2),
+        &mut rng,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(status_of(&err), Some(404));
+    assert_eq!(recorder.report().retries, 2);
+
+    // nothing listening: a connection error, retried like a 5xx
+    let closed = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let url = format!("http://{}/", closed.local_addr().unwrap());
+    drop(closed);
+    assert!(fetcher.fetch(&url).await.is_err());
+    fetch_with_retry(&fetcher, &recorder, &limiter, &url, &policy(1), &mut rng)
+        .await
+        .unwrap_err();
+    assert_eq!(recorder.report().retries, 3);
+}
+
+#[test]
+fn test_backoff_doubles_within_jitter_and_caps() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+    };
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    for (retry, full) in [(0, 100), (1, 200), (2, 400), (3, 800), (8, 1000)] {
+        let full = Duration::from_millis(full);
+        let wait = policy.backoff(retry, &mut rng);
+        assert!(wait >= full / 2 && wait <= full, "{}: {:?}", retry, wait);
+    }
+}
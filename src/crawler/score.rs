@@ -0,0 +1,78 @@
+use std::{fmt, sync::Arc};
+
+/// Relevance of a candidate url, higher is fetched first by the
+/// `Priority` frontier policy
+pub trait Scorer: Send + Sync + 'static {
+    fn score(&self, url: &str, depth: usize) -> f64;
+}
+
+impl<F> Scorer for F
+where
+    F: Fn(&str, usize) -> f64 + Send + Sync + 'static,
+{
+    fn score(&self, url: &str, depth: usize) -> f64 {
+        self(url, depth)
+    }
+}
+
+/// Halves a url's score every `half_life` levels of depth
+///
+/// On its own every url at a depth ties, so it degrades to breadth-first.
+/// Wrap a relevance score with `decayed` to make it prefer promising
+/// branches without digging arbitrarily deep into them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthDecayScorer {
+    pub half_life: f64,
+}
+
+impl Default for DepthDecayScorer {
+    fn default() -> Self {
+        DepthDecayScorer { half_life: 1.0 }
+    }
+}
+
+impl DepthDecayScorer {
+    pub fn decay(&self, depth: usize) -> f64 {
+        0.5f64.powf(depth as f64 / self.half_life)
+    }
+
+    /// `relevance(url)` scaled by the decay at the url's depth
+    pub fn decayed(
+        self,
+        relevance: impl Fn(&str) -> f64 + Send + Sync + 'static,
+    ) -> impl Scorer {
+        move |url: &str, depth| relevance(url) * self.decay(depth)
+    }
+}
+
+impl Scorer for DepthDecayScorer {
+    fn score(&self, _url: &str, depth: usize) -> f64 {
+        self.decay(depth)
+    }
+}
+
+/// Order in which the frontier hands out urls
+#[derive(Clone, Default)]
+pub enum FrontierPolicy {
+    /// first in first out, i.e. breadth-first
+    #[default]
+    Fifo,
+
+    /// highest score first, ties in push order
+    Priority(Arc<dyn Scorer>),
+}
+
+impl FrontierPolicy {
+    pub fn priority(scorer: impl Scorer) -> FrontierPolicy {
+        FrontierPolicy::Priority(Arc::new(scorer))
+    }
+}
+
+impl fmt::Debug for FrontierPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrontierPolicy::Fifo => write!(f, "Fifo"),
+            FrontierPolicy::Priority(_) => write!(f, "Priority(..)"),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Minimal streaming reader for the sitemaps.org protocol
+//!
+//! Scans tag by tag without building a document, so memory stays bounded
+//! by the longest single tag, not the sitemap size.
+
+use std::io::BufRead;
+
+use anyhow::{Result, bail};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sitemap {
+    /// `<urlset>`: page urls
+    UrlSet(Vec<String>),
+
+    /// `<sitemapindex>`: urls of further sitemaps
+    Index(Vec<String>),
+}
+
+/// Collects every `<loc>` value, kind decided by the root element
+pub fn parse_sitemap(mut reader: impl BufRead) -> Result<Sitemap> {
+    let mut root: Option<String> = None;
+    let mut locs = Vec::new();
+
+    let mut in_loc = false;
+    let mut loc = String::new();
+    let mut buf = Vec::new();
+
+    loop {
+        // text up to the next tag
+        buf.clear();
+        if reader.read_until(b'<', &mut buf)? == 0 {
+            break;
+        }
+        if in_loc {
+            loc.push_str(&String::from_utf8_lossy(trim_delim(&buf, b'<')));
+        }
+
+        // tag body up to '>'
+        buf.clear();
+        if reader.read_until(b'>', &mut buf)? == 0 {
+            break;
+        }
+
+        if buf.starts_with(b"!--") {
+            while !buf.ends_with(b"-->") {
+                if reader.read_until(b'>', &mut buf)? == 0 {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if buf.starts_with(b"![CDATA[") {
+            while !buf.ends_with(b"]]>") {
+                if reader.read_until(b'>', &mut buf)? == 0 {
+                    break;
+                }
+            }
+            if in_loc {
+                let inner = &buf[8..buf.len().saturating_sub(3)];
+                loc.push_str(&String::from_utf8_lossy(inner));
+            }
+            continue;
+        }
+
+        if buf.starts_with(b"?") || buf.starts_with(b"!") {
+            continue; // declaration / doctype
+        }
+
+        let tag = String::from_utf8_lossy(trim_delim(&buf, b'>'));
+        if let Some(close) = tag.strip_prefix('/') {
+            if in_loc && local_name(close) == "loc" {
+                locs.push(unescape(loc.trim()));
+                loc.clear();
+                in_loc = false;
+            }
+            continue;
+        }
+
+        let name = local_name(&tag);
+        if root.is_none() {
+            root = Some(name.to_owned());
+        }
+        if name == "loc" && !tag.ends_with('/') {
+            in_loc = true;
+            loc.clear();
+        }
+    }
+
+    match root.as_deref() {
+        Some("urlset") => Ok(Sitemap::UrlSet(locs)),
+        Some("sitemapindex") => Ok(Sitemap::Index(locs)),
+        Some(other) => bail!("not a sitemap, root element <{}>", other),
+        None => bail!("empty sitemap"),
+    }
+}
+
+fn trim_delim(buf: &[u8], delim: u8) -> &[u8] {
+    buf.strip_suffix(&[delim]).unwrap_or(buf)
+}
+
+/// Element name without attributes or namespace prefix
+fn local_name(tag: &str) -> &str {
+    let name = tag
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or("");
+
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
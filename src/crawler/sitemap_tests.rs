@@ -0,0 +1,152 @@
+#![cfg(test)]
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use crate::crawler::crawl_tests::{MockFetcher, config_for, wiki};
+use crate::crawler::{Crawler, Sitemap, parse_sitemap};
+use crate::graph::core::Graph;
+
+fn urlset(locs: &[&str]) -> String {
+    let urls: String = locs
+        .iter()
+        .map(|l| {
+            format!("<url><loc>{}</loc><lastmod>2024-01-01</lastmod></url>", l)
+        })
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">{}</urlset>"#,
+        urls
+    )
+}
+
+fn index(locs: &[&str]) -> String {
+    let maps: String = locs
+        .iter()
+        .map(|l| format!("<sitemap>\n  <loc>\n    {}\n  </loc>\n</sitemap>", l))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0"?>
+<!-- generated -->
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">{}</sitemapindex>"#,
+        maps
+    )
+}
+
+#[test]
+fn test_parse_urlset() {
+    let xml = urlset(&[&wiki("A"), &wiki("B")]);
+    let parsed = parse_sitemap(xml.as_bytes()).unwrap();
+
+    assert_eq!(parsed, Sitemap::UrlSet(vec![wiki("A"), wiki("B")]));
+}
+
+#[test]
+fn test_parse_index_trims_whitespace() {
+    let xml = index(&["https://x.org/s1.xml", "https://x.org/s2.xml"]);
+    let parsed = parse_sitemap(xml.as_bytes()).unwrap();
+
+    assert_eq!(
+        parsed,
+        Sitemap::Index(vec![
+            "https://x.org/s1.xml".to_owned(),
+            "https://x.org/s2.xml".to_owned()
+        ])
+    );
+}
+
+#[test]
+fn test_parse_entities_cdata_and_prefixes() {
+    let xml = r#"<s:urlset xmlns:s="http://www.sitemaps.org/schemas/sitemap/0.9">
+        <s:url><s:loc>https://x.org/?a=1&amp;b=2</s:loc></s:url>
+        <s:url><s:loc><![CDATA[https://x.org/<raw>]]></s:loc></s:url>
+        <!-- <url><loc>https://x.org/commented</loc></url> -->
+    </s:urlset>"#;
+
+    let parsed = parse_sitemap(xml.as_bytes()).unwrap();
+    assert_eq!(
+        parsed,
+        Sitemap::UrlSet(vec![
+            "https://x.org/?a=1&b=2".to_owned(),
+            "https://x.org/<raw>".to_owned()
+        ])
+    );
+}
+
+#[test]
+fn test_parse_rejects_non_sitemap() {
+    assert!(parse_sitemap("<html><body/></html>".as_bytes()).is_err());
+    assert!(parse_sitemap("".as_bytes()).is_err());
+}
+
+#[tokio::test]
+async fn test_crawl_seeds_from_nested_sitemap_index() {
+    let mut fetcher =
+        MockFetcher::new(&[("A", &["C"]), ("B", &[]), ("C", &[])]);
+
+    let root_map = "https://en.wikipedia.org/sitemap.xml";
+    let part1 = "https://en.wikipedia.org/sitemap-1.xml";
+    let part2 = "https://en.wikipedia.org/sitemap-2.xml";
+    let broken = "https://en.wikipedia.org/sitemap-missing.xml";
+
+    fetcher
+        .pages
+        .insert(root_map.to_owned(), index(&[part1, part2, broken]));
+    fetcher.pages.insert(
+        part1.to_owned(),
+        // out of scope urls are dropped
+        urlset(&[&wiki("A"), "https://example.com/elsewhere"]),
+    );
+    fetcher.pages.insert(
+        part2.to_owned(),
+        urlset(&[&wiki("B"), &wiki("Category:Things")]),
+    );
+
+    let mut config = config_for("unused");
+    config.sitemap = Some(root_map.to_owned());
+
+    // seeds hang off the graph's own root, whatever it's called
+    let graph = Graph::builder().root_name("seeds").build_without_events();
+    let crawler = Crawler::new(config, Arc::new(graph), fetcher);
+
+    let graph = crawler.run().await.unwrap();
+
+    // root + A, B seeded + C discovered
+    assert_eq!(graph.node_count(), 4);
+    assert_eq!(graph.get_root().get_children().len(), 2);
+    assert!(!graph.contains("root"));
+    assert!(graph.contains(&wiki("C")));
+    assert!(!graph.contains(&wiki("unused")));
+}
+
+#[tokio::test]
+async fn test_seed_from_sitemap_missing_root_is_error() {
+    let fetcher = MockFetcher::new(&[]);
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config_for("A"), graph, fetcher);
+
+    assert!(
+        crawler
+            .seed_from_sitemap("https://en.wikipedia.org/nope.xml")
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_sitemap_cycle_fetched_once() {
+    let mut fetcher = MockFetcher::new(&[]);
+    let a = "https://en.wikipedia.org/a.xml";
+    let b = "https://en.wikipedia.org/b.xml";
+    fetcher.pages.insert(a.to_owned(), index(&[b]));
+    fetcher.pages.insert(b.to_owned(), index(&[a]));
+
+    let fetcher = Arc::new(fetcher);
+    let graph = Arc::new(Graph::new_without_events());
+    let crawler = Crawler::new(config_for("A"), graph, fetcher.clone());
+
+    assert_eq!(crawler.seed_from_sitemap(a).await.unwrap(), 0);
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,137 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use tracing::warn;
+use url::Url;
+
+// shorter mixed segments are too likely to be real words, like `mp3`
+const MIN_OPAQUE_LEN: usize = 16;
+
+/// Cuts off crawler traps: endless generated urls like calendars or
+/// session ids, told apart from real pages by their shape
+///
+/// Urls are grouped by host and path shape, opaque path segments and
+/// numeric or opaque query values replaced by a placeholder. Numeric path
+/// segments stay, they're real titles as often as not, like `/wiki/1984`.
+/// Once more than
+/// `threshold` urls of one such pattern were admitted, the rest of it is
+/// refused.
+#[derive(Debug)]
+pub struct TrapDetector {
+    threshold: usize,
+    patterns: Mutex<HashMap<String, Pattern>>,
+}
+
+#[derive(Debug, Default)]
+struct Pattern {
+    // at most threshold of them, so memory stays bounded per pattern
+    admitted: HashSet<String>,
+    trapped: bool,
+}
+
+impl TrapDetector {
+    pub fn new(threshold: usize) -> TrapDetector {
+        TrapDetector {
+            threshold,
+            patterns: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `url` may be followed, false for new urls of a pattern
+    /// that already had `threshold` admitted, logged the first time
+    ///
+    /// Urls without a templated part are always admitted, as are urls
+    /// admitted before, so links seen twice don't count twice.
+    pub fn admit(&self, url: &str) -> bool {
+        let Some(pattern) = url_pattern(url) else {
+            return true;
+        };
+
+        let mut patterns = self.patterns.lock().unwrap();
+        let entry = patterns.entry(pattern.clone()).or_default();
+        if entry.admitted.contains(url) {
+            return true;
+        }
+        if entry.admitted.len() < self.threshold {
+            entry.admitted.insert(url.to_owned());
+            return true;
+        }
+
+        if !entry.trapped {
+            entry.trapped = true;
+            warn!(
+                url,
+                pattern,
+                threshold = self.threshold,
+                "Crawler trap detected, no longer following pattern"
+            );
+        }
+        false
+    }
+
+    /// Patterns that went over the threshold, sorted
+    pub fn trapped(&self) -> Vec<String> {
+        let mut trapped: Vec<String> = self
+            .patterns
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, p)| p.trapped)
+            .map(|(pattern, _)| pattern.clone())
+            .collect();
+        trapped.sort();
+        trapped
+    }
+}
+
+/// `host/path?query` with opaque path segments and numeric or opaque
+/// query values replaced by `*`, None if nothing was replaced or it
+/// doesn't parse as a url
+pub fn url_pattern(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let mut templated = false;
+    let mut template = |part: &str, numeric: bool| {
+        if (numeric && is_numeric(part)) || is_opaque(part) {
+            templated = true;
+            "*".to_owned()
+        } else {
+            part.to_owned()
+        }
+    };
+
+    let mut pattern = url.host_str()?.to_owned();
+    for segment in url.path_segments()? {
+        pattern.push('/');
+        pattern.push_str(&template(segment, false));
+    }
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), template(&value, true)))
+        .collect();
+    query.sort();
+    for (i, (key, value)) in query.iter().enumerate() {
+        pattern.push(if i == 0 { '?' } else { '&' });
+        pattern.push_str(&format!("{}={}", key, value));
+    }
+
+    templated.then_some(pattern)
+}
+
+fn is_numeric(part: &str) -> bool {
+    !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// A long token like a hash or session id, at least a quarter of it
+/// digits so titles like `List_of_2020s_films` don't qualify
+fn is_opaque(part: &str) -> bool {
+    let digits = part.bytes().filter(u8::is_ascii_digit).count();
+
+    part.len() >= MIN_OPAQUE_LEN
+        && digits * 4 >= part.len()
+        && part
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
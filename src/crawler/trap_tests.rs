@@ -0,0 +1,115 @@
+#![cfg(test)]
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use crate::crawler::crawl_tests::{MockFetcher, config_for, local_scope};
+use crate::crawler::{CrawlConfig, Crawler, Scope, TrapDetector, url_pattern};
+use crate::graph::core::Graph;
+
+#[test]
+fn test_url_pattern_templates_numeric_and_opaque_parts() {
+    assert_eq!(
+        url_pattern("http://example.com/page?id=17").as_deref(),
+        Some("example.com/page?id=*")
+    );
+    assert_eq!(
+        url_pattern(
+            "http://example.com/cal/2024/05?view=month&sid=9f8e7d6c5b4a39281716"
+        )
+        .as_deref(),
+        Some("example.com/cal/2024/05?sid=*&view=month")
+    );
+    assert_eq!(
+        url_pattern("http://example.com/s/9f8e7d6c5b4a39281716/cart")
+            .as_deref(),
+        Some("example.com/s/*/cart")
+    );
+
+    // nothing to template, every url is its own page
+    assert_eq!(url_pattern("http://example.com/about?lang=en"), None);
+    assert_eq!(url_pattern("https://en.wikipedia.org/wiki/Mp3"), None);
+    assert_eq!(url_pattern("https://en.wikipedia.org/wiki/1984"), None);
+    assert_eq!(
+        url_pattern("https://en.wikipedia.org/wiki/List_of_2020s_films"),
+        None
+    );
+    assert_eq!(url_pattern("not a url"), None);
+}
+
+#[test]
+fn test_detector_cuts_off_page_id_trap() {
+    let detector = TrapDetector::new(5);
+    let page = |n: usize| format!("http://example.com/page?id={}", n);
+
+    let admitted = (0..100).filter(|&n| detector.admit(&page(n))).count();
+    assert_eq!(admitted, 5);
+    assert_eq!(detector.trapped(), ["example.com/page?id=*"]);
+
+    // already admitted urls stay admitted, other pages are unaffected
+    assert!(detector.admit(&page(3)));
+    assert!(detector.admit("http://example.com/about"));
+    assert!(detector.admit("http://example.com/page/1"));
+}
+
+#[test]
+fn test_detector_counts_each_url_once() {
+    let detector = TrapDetector::new(2);
+    for _ in 0..10 {
+        assert!(detector.admit("http://example.com/page?id=1"));
+    }
+
+    assert!(detector.admit("http://example.com/page?id=2"));
+    assert!(!detector.admit("http://example.com/page?id=3"));
+}
+
+#[tokio::test]
+async fn test_crawl_stops_following_page_id_trap() {
+    // every /page?id=N links to the next one, forever
+    let page = |n: usize| format!("http://127.0.0.1:8080/page?id={}", n);
+    let mut fetcher = MockFetcher::default();
+    for n in 0..50 {
+        let body = format!(r#"<a href="{}">next</a>"#, page(n + 1));
+        fetcher.pages.insert(page(n), body);
+    }
+
+    let config = CrawlConfig {
+        start_url: page(0),
+        scope: Scope {
+            allow_queries: true,
+            ..local_scope()
+        },
+        trap_threshold: Some(5),
+        ..config_for("unused")
+    };
+    let fetcher = Arc::new(fetcher);
+    let graph = Arc::new(Graph::new_without_events());
+    let graph = Crawler::new(config, graph, fetcher.clone())
+        .run()
+        .await
+        .unwrap();
+
+    // the start page plus the five admitted links, the link to 6 is only
+    // recorded
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 6);
+    assert!(graph.contains_edge(&page(5), &page(6)));
+    assert!(!graph.contains(&page(7)));
+}
+
+#[tokio::test]
+async fn test_crawl_follows_numeric_titles() {
+    let names: Vec<String> = (1980..1990).map(|n| n.to_string()).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut site: Vec<(&str, &[&str])> = vec![("Start", &names)];
+    site.extend(names.iter().map(|name| (*name, &[][..])));
+
+    let mut config = config_for("Start");
+    config.trap_threshold = Some(3);
+    let fetcher = Arc::new(MockFetcher::new(&site));
+    let graph = Arc::new(Graph::new_without_events());
+    Crawler::new(config, graph, fetcher.clone())
+        .run()
+        .await
+        .unwrap();
+
+    assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 11);
+}
@@ -0,0 +1,614 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+};
+
+use crate::graph::core::{Graph, Node};
+use crate::graph::snapshot::GraphSnapshot;
+
+impl Graph {
+    /// Every node reachable from `start`, `start` included
+    ///
+    /// Visited by pointer, children are snapshotted one node at a time so
+    /// no lock is held across the traversal.
+    pub(crate) fn reachable_from(&self, start: &Arc<Node>) -> Vec<Arc<Node>> {
+        self.within_hops(start, usize::MAX)
+    }
+
+    /// Nodes at most `hops` edges away from `start`, in BFS order
+    pub(crate) fn within_hops(
+        &self,
+        start: &Arc<Node>,
+        hops: usize,
+    ) -> Vec<Arc<Node>> {
+        let mut visited: HashSet<*const Node> = HashSet::new();
+        let mut queue = VecDeque::from([(start.clone(), 0)]);
+        let mut out = Vec::new();
+
+        visited.insert(Arc::as_ptr(start));
+        while let Some((node, dist)) = queue.pop_front() {
+            if dist < hops {
+                node.with_children(|children| {
+                    for child in children {
+                        if visited.insert(Arc::as_ptr(&child)) {
+                            queue.push_back((child, dist + 1));
+                        }
+                    }
+                });
+            }
+            out.push(node);
+        }
+
+        out
+    }
+
+    /// Nodes reachable from `start` in BFS order, `start` first, each once,
+    /// empty if `start` is missing
+    ///
+    /// Lazy, each step takes a fresh `get_children` so no lock is held
+    /// between items, edges added mid-walk may or may not be seen.
+    pub fn bfs(&self, start: &str) -> impl Iterator<Item = Arc<Node>> {
+        let start = self.get_node(start);
+        Bfs {
+            visited: start.iter().map(|n| n.get_data().to_owned()).collect(),
+            queue: start.into_iter().collect(),
+        }
+    }
+
+    /// Nodes reachable from `start` in DFS pre-order, children in insertion
+    /// order, each once, empty if `start` is missing
+    ///
+    /// Iterative over an explicit stack, so deep chains can't overflow the
+    /// call stack.
+    pub fn dfs(&self, start: &str) -> Vec<Arc<Node>> {
+        let Some(start) = self.get_node(start) else {
+            return Vec::new();
+        };
+
+        let mut visited: HashSet<String> = HashSet::new();
+        let mut stack = vec![start];
+        let mut out = Vec::new();
+        while let Some(node) = stack.pop() {
+            if !visited.insert(node.get_data().to_owned()) {
+                continue;
+            }
+
+            let mut children = node.get_children();
+            children.retain(|c| !visited.contains(c.get_data()));
+            stack.extend(children.into_iter().rev());
+            out.push(node);
+        }
+
+        out
+    }
+
+    /// Node names grouped by BFS distance from `start`, each level sorted,
+    /// empty if `start` is missing
+    pub fn bfs_levels(&self, start: &str) -> Vec<Vec<String>> {
+        let Some(start) = self.get_node(start) else {
+            return Vec::new();
+        };
+
+        let mut visited: HashSet<*const Node> =
+            HashSet::from([Arc::as_ptr(&start)]);
+        let mut level = vec![start];
+        let mut levels = Vec::new();
+
+        while !level.is_empty() {
+            let mut next = Vec::new();
+            for node in &level {
+                node.with_children(|children| {
+                    next.extend(
+                        children.filter(|c| visited.insert(Arc::as_ptr(c))),
+                    );
+                });
+            }
+
+            let mut names: Vec<String> =
+                level.iter().map(|n| n.get_data().to_owned()).collect();
+            names.sort();
+            levels.push(names);
+            level = next;
+        }
+
+        levels
+    }
+
+    /// Leaves (nodes without children) reachable from root, paired with
+    /// their BFS depth, shallowest first and by name within a depth
+    ///
+    /// Works off one snapshot, leaves root can't reach have no depth and
+    /// are left out.
+    pub fn leaves_by_depth(&self) -> Vec<(String, usize)> {
+        let snapshot = self.snapshot();
+        let mut adj: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (parent, child) in &snapshot.edges {
+            adj.entry(parent).or_default().push(child);
+        }
+
+        let root = self.get_root();
+        let mut depths = HashMap::from([(root.get_data(), 0)]);
+        let mut queue = VecDeque::from([root.get_data()]);
+        while let Some(name) = queue.pop_front() {
+            let depth = depths[name];
+            for &child in adj.get(name).into_iter().flatten() {
+                if !depths.contains_key(child) {
+                    depths.insert(child, depth + 1);
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        let mut leaves: Vec<(String, usize)> = depths
+            .into_iter()
+            .filter(|(name, _)| !adj.contains_key(name))
+            .map(|(name, depth)| (name.to_owned(), depth))
+            .collect();
+        leaves.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        leaves
+    }
+
+    /// BFS spanning tree from root, one `(node, tree children)` entry per
+    /// reachable node in BFS order, for tree views that can't take cycles
+    ///
+    /// Each node hangs under whichever parent discovered it first, edges to
+    /// a node already in the tree (back-edges, shared nodes) are left out.
+    pub fn spanning_tree_from_root(&self) -> Vec<(String, Vec<String>)> {
+        let root = self.get_root();
+        let mut visited: HashSet<*const Node> =
+            HashSet::from([Arc::as_ptr(&root)]);
+        let mut queue = VecDeque::from([root]);
+        let mut tree = Vec::new();
+
+        while let Some(node) = queue.pop_front() {
+            let mut kids = Vec::new();
+            node.with_children(|children| {
+                for child in children {
+                    if visited.insert(Arc::as_ptr(&child)) {
+                        kids.push(child.get_data().to_owned());
+                        queue.push_back(child);
+                    }
+                }
+            });
+            tree.push((node.get_data().to_owned(), kids));
+        }
+
+        tree
+    }
+
+    /// Node names along a fewest-edges path over outgoing edges, `from`
+    /// and `to` included, None if either is missing or `to` is unreachable
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let start = self.get_node(from)?;
+        let goal = self.get_node(to)?;
+
+        let mut came_from: HashMap<*const Node, Arc<Node>> = HashMap::new();
+        let mut queue = VecDeque::from([start.clone()]);
+        came_from.insert(Arc::as_ptr(&start), start.clone());
+
+        while let Some(node) = queue.pop_front() {
+            if Arc::ptr_eq(&node, &goal) {
+                let mut path = vec![node.get_data().to_owned()];
+                let mut cur = node;
+                while !Arc::ptr_eq(&cur, &start) {
+                    cur = came_from[&Arc::as_ptr(&cur)].clone();
+                    path.push(cur.get_data().to_owned());
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            node.with_children(|children| {
+                for child in children {
+                    came_from.entry(Arc::as_ptr(&child)).or_insert_with(|| {
+                        queue.push_back(child.clone());
+                        node.clone()
+                    });
+                }
+            });
+        }
+
+        None
+    }
+
+    /// True if `node` can reach itself over a nonempty path, false if it's
+    /// missing
+    ///
+    /// A DFS from `node` that stops at the first edge back to it, cheaper
+    /// than `strongly_connected_components` when only one node matters.
+    pub fn is_in_cycle(&self, node: &str) -> bool {
+        let Some(start) = self.get_node(node) else {
+            return false;
+        };
+
+        let mut visited: HashSet<*const Node> = HashSet::new();
+        let mut stack = vec![start.clone()];
+        while let Some(node) = stack.pop() {
+            let back_edge = node.with_children(|children| {
+                for child in children {
+                    if Arc::ptr_eq(&child, &start) {
+                        return true;
+                    }
+                    if visited.insert(Arc::as_ptr(&child)) {
+                        stack.push(child);
+                    }
+                }
+                false
+            });
+            if back_edge {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Whether `to` can be reached from `from` over zero or more edges,
+    /// false if either is missing
+    pub fn is_reachable(&self, from: &str, to: &str) -> bool {
+        let (Some(start), Some(goal)) =
+            (self.get_node(from), self.get_node(to))
+        else {
+            return false;
+        };
+
+        Arc::ptr_eq(&start, &goal)
+            || walk(&start, Node::get_children)
+                .iter()
+                .any(|n| Arc::ptr_eq(n, &goal))
+    }
+
+    /// Names reachable from `content` over a nonempty path, so `content`
+    /// itself only when it's on a cycle, empty if it's missing
+    pub fn descendants(&self, content: &str) -> HashSet<String> {
+        self.get_node(content).map_or_else(HashSet::new, |start| {
+            names(walk(&start, Node::get_children))
+        })
+    }
+
+    /// Names that reach `content` over a nonempty path, walked up the
+    /// parent backlinks, same convention as `descendants`
+    pub fn ancestors(&self, content: &str) -> HashSet<String> {
+        self.get_node(content).map_or_else(HashSet::new, |start| {
+            names(walk(&start, Node::get_parents))
+        })
+    }
+
+    /// Children `a` and `b` have in common, sorted, empty if either is
+    /// missing
+    pub fn shared_children(&self, a: &str, b: &str) -> Vec<String> {
+        let (Some(a), Some(b)) = (self.child_names(a), self.child_names(b))
+        else {
+            return Vec::new();
+        };
+
+        let mut shared: Vec<String> = a.intersection(&b).cloned().collect();
+        shared.sort();
+        shared
+    }
+
+    /// Shared children over all children of either, 0.0 if either is
+    /// missing or neither has children
+    pub fn jaccard_similarity(&self, a: &str, b: &str) -> f64 {
+        let (Some(a), Some(b)) = (self.child_names(a), self.child_names(b))
+        else {
+            return 0.0;
+        };
+
+        let union = a.union(&b).count();
+        if union == 0 {
+            return 0.0;
+        }
+        a.intersection(&b).count() as f64 / union as f64
+    }
+
+    /// Names of a node's children in insertion order, None if it's missing
+    pub fn neighbors(&self, content: &str) -> Option<Vec<String>> {
+        let node = self.get_node(content)?;
+        Some(node.with_children(|children| {
+            children.map(|c| c.get_data().to_owned()).collect()
+        }))
+    }
+
+    /// Number of live children, None if the node is missing
+    pub fn out_degree(&self, content: &str) -> Option<usize> {
+        Some(self.get_node(content)?.with_children(|c| c.count()))
+    }
+
+    /// Number of live parents, None if the node is missing
+    ///
+    /// O(in-degree) off the node's backlinks, see `Node::get_parents`, no
+    /// scan over the other nodes.
+    pub fn in_degree(&self, content: &str) -> Option<usize> {
+        Some(self.get_node(content)?.get_parents().len())
+    }
+
+    fn child_names(&self, name: &str) -> Option<HashSet<String>> {
+        let node = self.get_node(name)?;
+        Some(node.with_children(|children| {
+            children.map(|c| c.get_data().to_owned()).collect()
+        }))
+    }
+
+    /// Edges from a node in `from_set` to a node in `to_set`, sorted
+    pub fn edges_between(
+        &self,
+        from_set: &HashSet<String>,
+        to_set: &HashSet<String>,
+    ) -> Vec<(String, String)> {
+        self.snapshot()
+            .edges
+            .into_iter()
+            .filter(|(p, c)| from_set.contains(p) && to_set.contains(c))
+            .collect()
+    }
+
+    /// Strongly connected components, each sorted, sorted by first name
+    ///
+    /// Iterative Tarjan over one snapshot, nodes on no cycle come back as
+    /// their own single-node component.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        strongly_connected(&self.snapshot())
+    }
+
+    /// Weakly connected components, edges taken in both directions, each
+    /// sorted, the one holding root first and the rest by first name
+    ///
+    /// BFS over one snapshot, islands the crawler left unlinked from root
+    /// come back as their own components.
+    pub fn connected_components(&self) -> Vec<Vec<String>> {
+        let snapshot = self.snapshot();
+        let root = self.get_root();
+        connected_components(&snapshot, root.get_data())
+    }
+
+    /// Whether any node can reach itself, self-loops included
+    pub fn has_cycle(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+
+    /// One cycle as node names in edge order, the last name linking back
+    /// to the first, None for a DAG
+    ///
+    /// White/gray/black DFS over every node of one snapshot, the first
+    /// edge to a gray node closes the cycle.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        find_cycle(&self.snapshot())
+    }
+}
+
+/// find_cycle on an existing snapshot
+pub(crate) fn find_cycle(snapshot: &GraphSnapshot) -> Option<Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Color {
+        White,
+        Gray,
+        Black,
+    }
+
+    let names = &snapshot.nodes;
+    let ids: HashMap<&str, usize> = names
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.as_str(), i))
+        .collect();
+
+    // edges to or from unlisted nodes are skipped, a hand-built snapshot
+    // may have them
+    let mut adj = vec![Vec::new(); names.len()];
+    for (parent, child) in &snapshot.edges {
+        if let (Some(&p), Some(&c)) =
+            (ids.get(parent.as_str()), ids.get(child.as_str()))
+        {
+            adj[p].push(c);
+        }
+    }
+
+    let mut color = vec![Color::White; names.len()];
+    for start in 0..names.len() {
+        if color[start] != Color::White {
+            continue;
+        }
+
+        // explicit call stack of (node, next child to look at), exactly
+        // the gray nodes in path order
+        let mut calls = vec![(start, 0)];
+        color[start] = Color::Gray;
+
+        while let Some(&(v, i)) = calls.last() {
+            let Some(&w) = adj[v].get(i) else {
+                color[v] = Color::Black;
+                calls.pop();
+                continue;
+            };
+            calls.last_mut().unwrap().1 += 1;
+
+            match color[w] {
+                Color::White => {
+                    color[w] = Color::Gray;
+                    calls.push((w, 0));
+                }
+                Color::Gray => {
+                    let from = calls.iter().position(|&(u, _)| u == w)?;
+                    return Some(
+                        calls[from..]
+                            .iter()
+                            .map(|&(u, _)| names[u].clone())
+                            .collect(),
+                    );
+                }
+                Color::Black => {}
+            }
+        }
+    }
+
+    None
+}
+
+/// Nodes reached from `start` over one or more `next` steps, `start`
+/// only if a step leads back to it
+fn walk(
+    start: &Arc<Node>,
+    next: impl Fn(&Node) -> Vec<Arc<Node>>,
+) -> Vec<Arc<Node>> {
+    let mut visited: HashSet<*const Node> = HashSet::new();
+    let mut stack = vec![start.clone()];
+    let mut out = Vec::new();
+
+    while let Some(node) = stack.pop() {
+        for n in next(&node) {
+            if visited.insert(Arc::as_ptr(&n)) {
+                out.push(n.clone());
+                stack.push(n);
+            }
+        }
+    }
+
+    out
+}
+
+fn names(nodes: Vec<Arc<Node>>) -> HashSet<String> {
+    nodes.iter().map(|n| n.get_data().to_owned()).collect()
+}
+
+/// connected_components on an existing snapshot
+pub(crate) fn connected_components(
+    snapshot: &GraphSnapshot,
+    root: &str,
+) -> Vec<Vec<String>> {
+    let mut adj: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (parent, child) in &snapshot.edges {
+        adj.entry(parent).or_default().push(child);
+        adj.entry(child).or_default().push(parent);
+    }
+
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut components = Vec::new();
+    for start in &snapshot.nodes {
+        if !visited.insert(start) {
+            continue;
+        }
+
+        let mut component = Vec::new();
+        let mut queue = VecDeque::from([start.as_str()]);
+        while let Some(name) = queue.pop_front() {
+            component.push(name.to_owned());
+            for &next in adj.get(name).into_iter().flatten() {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+
+    components.sort_by(|a, b| {
+        let a_root = a.binary_search_by(|n| n.as_str().cmp(root)).is_ok();
+        let b_root = b.binary_search_by(|n| n.as_str().cmp(root)).is_ok();
+        b_root.cmp(&a_root).then_with(|| a.cmp(b))
+    });
+    components
+}
+
+/// strongly_connected_components on an existing snapshot
+pub(crate) fn strongly_connected(snapshot: &GraphSnapshot) -> Vec<Vec<String>> {
+    let names = &snapshot.nodes;
+    let ids: HashMap<&str, usize> = names
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.as_str(), i))
+        .collect();
+
+    // like find_cycle, edges to or from unlisted nodes are skipped
+    let mut adj = vec![Vec::new(); names.len()];
+    for (parent, child) in &snapshot.edges {
+        if let (Some(&p), Some(&c)) =
+            (ids.get(parent.as_str()), ids.get(child.as_str()))
+        {
+            adj[p].push(c);
+        }
+    }
+
+    const UNVISITED: usize = usize::MAX;
+    let mut index = vec![UNVISITED; names.len()];
+    let mut low = vec![0; names.len()];
+    let mut on_stack = vec![false; names.len()];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+
+    for start in 0..names.len() {
+        if index[start] != UNVISITED {
+            continue;
+        }
+
+        // explicit call stack of (node, next child to look at)
+        let mut calls = vec![(start, 0)];
+        index[start] = next_index;
+        low[start] = next_index;
+        next_index += 1;
+        stack.push(start);
+        on_stack[start] = true;
+
+        while let Some(&(v, i)) = calls.last() {
+            if let Some(&w) = adj[v].get(i) {
+                calls.last_mut().unwrap().1 += 1;
+
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    low[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(u, _)) = calls.last() {
+                low[u] = low[u].min(low[v]);
+            }
+
+            if low[v] == index[v] {
+                let mut component = Vec::new();
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    component.push(names[w].clone());
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+
+    components.sort();
+    components
+}
+
+/// Iterator behind `Graph::bfs`
+struct Bfs {
+    queue: VecDeque<Arc<Node>>,
+    visited: HashSet<String>,
+}
+
+impl Iterator for Bfs {
+    type Item = Arc<Node>;
+
+    fn next(&mut self) -> Option<Arc<Node>> {
+        let node = self.queue.pop_front()?;
+        for child in node.get_children() {
+            if self.visited.insert(child.get_data().to_owned()) {
+                self.queue.push_back(child);
+            }
+        }
+        Some(node)
+    }
+}
@@ -0,0 +1,479 @@
+#![cfg(test)]
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+
+use crate::graph::algo::{find_cycle, strongly_connected};
+use crate::graph::core::Graph;
+use crate::graph::snapshot::GraphSnapshot;
+
+#[test]
+fn test_reachable_count_lone_root() {
+    let graph = Graph::new_without_events();
+    assert_eq!(graph.reachable_from_root_count(), 1);
+}
+
+#[test]
+fn test_reachable_count_ignores_islands_until_connected() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("X", "Y").unwrap(); // orphan island
+    graph.add_edge("Y", "X").unwrap();
+
+    assert_eq!(graph.reachable_from_root_count(), 2);
+    assert_eq!(graph.reachable_from_root_count(), 2); // cached
+
+    // connecting the island invalidates the cache
+    graph.add_edge("A", "X").unwrap();
+    assert_eq!(graph.reachable_from_root_count(), 4);
+}
+
+#[test]
+fn test_reachable_count_after_removal() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "root").unwrap();
+    graph.add_edge("root", "A").unwrap();
+    assert_eq!(graph.reachable_from_root_count(), 2);
+
+    let generation = graph.generation();
+    graph.remove_self_loops();
+    assert!(graph.generation() > generation);
+    assert_eq!(graph.reachable_from_root_count(), 2);
+}
+
+#[test]
+fn test_generation_unchanged_by_duplicates() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+
+    let generation = graph.generation();
+    graph.add_edge("root", "A").unwrap();
+    assert_eq!(graph.generation(), generation);
+}
+
+#[test]
+fn test_reachable_count_correct_after_concurrent_inserts() {
+    let graph = Arc::new(Graph::new_without_events());
+    let mut handles = vec![];
+
+    for t in 0..8 {
+        let g = Arc::clone(&graph);
+        handles.push(thread::spawn(move || {
+            for i in 0..100 {
+                g.add_edge("root", &format!("n_{}_{}", t, i)).unwrap();
+                let _ = g.reachable_from_root_count(); // race the cache
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(graph.reachable_from_root_count(), 801);
+}
+
+#[test]
+fn test_edges_between_sets() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "a1").unwrap();
+    graph.add_edge("root", "a2").unwrap();
+    graph.add_edge("a1", "a2").unwrap(); // inside A
+    graph.add_edge("a2", "b2").unwrap();
+    graph.add_edge("a1", "b1").unwrap();
+    graph.add_edge("b1", "a1").unwrap(); // B back into A
+    graph.add_edge("b1", "b2").unwrap(); // inside B
+
+    let set = |names: &[&str]| -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    };
+    let a = set(&["a1", "a2"]);
+    let b = set(&["b1", "b2"]);
+
+    assert_eq!(
+        graph.edges_between(&a, &b),
+        [("a1".into(), "b1".into()), ("a2".into(), "b2".into())]
+    );
+    assert_eq!(graph.edges_between(&b, &a), [("b1".into(), "a1".into())]);
+    assert!(graph.edges_between(&a, &HashSet::new()).is_empty());
+}
+
+#[test]
+fn test_scc_finds_cycle_and_singletons() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "A").unwrap();
+    graph.add_edge("C", "D").unwrap();
+    graph.add_edge("D", "D").unwrap(); // self-loop, still just one node
+
+    let sccs = graph.strongly_connected_components();
+    assert_eq!(
+        sccs,
+        vec![
+            vec!["A".to_string(), "B".into(), "C".into()],
+            vec!["D".into()],
+            vec!["root".into()],
+        ]
+    );
+}
+
+#[test]
+fn test_scc_partitions_every_node() {
+    let graph = Graph::new_without_events();
+    // two cycles joined one way, plus a long chain for depth
+    for (p, c) in [("root", "X"), ("X", "Y"), ("Y", "X"), ("Y", "P")] {
+        graph.add_edge(p, c).unwrap();
+    }
+    graph.add_edge("P", "Q").unwrap();
+    graph.add_edge("Q", "P").unwrap();
+    for i in 0..2000 {
+        let (p, c) = (format!("chain_{}", i), format!("chain_{}", i + 1));
+        graph.add_edge(&p, &c).unwrap();
+    }
+
+    let sccs = graph.strongly_connected_components();
+    let total: usize = sccs.iter().map(Vec::len).sum();
+
+    assert_eq!(total, graph.node_count());
+    assert!(sccs.contains(&vec!["X".to_string(), "Y".into()]));
+    assert!(sccs.contains(&vec!["P".to_string(), "Q".into()]));
+    assert_eq!(sccs.iter().filter(|c| c.len() == 1).count(), 2002);
+}
+
+#[test]
+fn test_bfs_levels_on_diamond() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "root").unwrap(); // back edge doesn't add a level
+
+    assert_eq!(
+        graph.bfs_levels("root"),
+        vec![
+            vec!["root".to_string()],
+            vec!["A".into(), "B".into()],
+            vec!["C".into()],
+        ]
+    );
+    assert_eq!(graph.bfs_levels("C")[1], ["root"]);
+    assert!(graph.bfs_levels("missing").is_empty());
+}
+
+#[test]
+fn test_bfs_levels_minimum_distance() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("root", "C").unwrap(); // shortcut
+
+    let levels = graph.bfs_levels("root");
+    assert_eq!(levels.len(), 3);
+    assert_eq!(levels[1], ["A", "C"]);
+    assert_eq!(levels[2], ["B"]);
+
+    let total: usize = levels.iter().map(Vec::len).sum();
+    assert_eq!(total, graph.node_count());
+}
+
+#[test]
+fn test_shared_children_and_jaccard() {
+    let graph = Graph::new_without_events();
+    for child in ["Kernel", "GNU", "Torvalds", "POSIX"] {
+        graph.add_edge("Linux", child).unwrap();
+    }
+    for child in ["POSIX", "Kernel", "BSD"] {
+        graph.add_edge("FreeBSD", child).unwrap();
+    }
+    graph.get_or_create_node("Leaf").unwrap();
+
+    assert_eq!(
+        graph.shared_children("Linux", "FreeBSD"),
+        ["Kernel", "POSIX"]
+    );
+    // 2 shared of 5 distinct children
+    assert_eq!(graph.jaccard_similarity("Linux", "FreeBSD"), 0.4);
+    assert_eq!(graph.jaccard_similarity("Linux", "Linux"), 1.0);
+
+    assert!(graph.shared_children("Linux", "missing").is_empty());
+    assert_eq!(graph.jaccard_similarity("missing", "Linux"), 0.0);
+    assert_eq!(graph.jaccard_similarity("Leaf", "Leaf"), 0.0);
+}
+
+#[test]
+fn test_leaves_by_depth_shallowest_first() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "Z").unwrap(); // leaf at 1
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("A", "B").unwrap(); // leaves at 2
+    graph.add_edge("C", "D").unwrap();
+    graph.add_edge("root", "D").unwrap(); // shortcut, D is at 1
+    graph.add_edge("C", "E").unwrap(); // leaf at 3
+    graph.add_edge("island", "F").unwrap(); // unreachable leaf
+
+    let leaves = graph.leaves_by_depth();
+    assert_eq!(
+        leaves,
+        [
+            ("D".to_string(), 1),
+            ("Z".to_string(), 1),
+            ("B".to_string(), 2),
+            ("E".to_string(), 3),
+        ]
+    );
+
+    let lone = Graph::new_without_events();
+    assert_eq!(lone.leaves_by_depth(), [("root".to_string(), 0)]);
+}
+
+#[test]
+fn test_is_in_cycle_only_for_nodes_on_a_loop() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "A").unwrap();
+    graph.add_edge("B", "Leaf").unwrap();
+
+    assert!(graph.is_in_cycle("A"));
+    assert!(graph.is_in_cycle("B"));
+    assert!(!graph.is_in_cycle("Leaf"));
+    // reaches the loop but isn't on it
+    assert!(!graph.is_in_cycle("root"));
+    assert!(!graph.is_in_cycle("missing"));
+
+    graph.add_edge("Leaf", "Leaf").unwrap();
+    assert!(graph.is_in_cycle("Leaf"));
+}
+
+#[test]
+fn test_spanning_tree_is_acyclic_and_covers_reachable() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap(); // shared, stays under A
+    graph.add_edge("C", "A").unwrap(); // back-edge
+    graph.add_edge("C", "C").unwrap();
+    graph.add_edge("island", "D").unwrap();
+
+    let tree = graph.spanning_tree_from_root();
+    let kids = |name: &str| {
+        tree.iter()
+            .find(|(node, _)| node == name)
+            .map(|(_, kids)| kids.clone())
+            .unwrap()
+    };
+    assert_eq!(kids("root"), ["A", "B"]);
+    assert_eq!(kids("A"), ["C"]);
+    assert!(kids("B").is_empty());
+    assert!(kids("C").is_empty());
+
+    // one entry per reachable node, every non-root node has one parent
+    let nodes: HashSet<&str> = tree.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(nodes, HashSet::from(["root", "A", "B", "C"]));
+    let mut parented: Vec<&str> = tree
+        .iter()
+        .flat_map(|(_, kids)| kids.iter().map(String::as_str))
+        .collect();
+    parented.sort();
+    assert_eq!(parented, ["A", "B", "C"]);
+    assert!(
+        tree.iter()
+            .all(|(_, kids)| !kids.iter().any(|k| k == "root"))
+    );
+}
+
+#[test]
+fn test_bfs_visits_diamond_nodes_once() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "root").unwrap(); // cycle back to the start
+
+    let order: Vec<String> =
+        graph.bfs("root").map(|n| n.get_data().to_owned()).collect();
+    assert_eq!(order, ["root", "A", "B", "C"]);
+
+    let from_b: Vec<String> =
+        graph.bfs("B").map(|n| n.get_data().to_owned()).collect();
+    assert_eq!(from_b, ["B", "C", "root", "A"]);
+
+    assert_eq!(graph.bfs("missing").count(), 0);
+}
+
+#[test]
+fn test_dfs_preorder_terminates_on_cycle() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "A").unwrap();
+    graph.add_edge("C", "C").unwrap();
+
+    let names = |start: &str| -> Vec<String> {
+        graph
+            .dfs(start)
+            .iter()
+            .map(|n| n.get_data().to_owned())
+            .collect()
+    };
+    assert_eq!(names("A"), ["A", "B", "C"]);
+
+    // pre-order goes deep into the first child before the second
+    graph.add_edge("A", "D").unwrap();
+    graph.add_edge("B", "E").unwrap();
+    assert_eq!(names("A"), ["A", "B", "C", "E", "D"]);
+    assert!(graph.dfs("missing").is_empty());
+}
+
+#[test]
+fn test_shortest_path_on_chain_and_unreachable_pair() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "D").unwrap();
+    graph.add_edge("A", "C").unwrap(); // shortcut past B
+    graph.add_edge("island", "E").unwrap();
+
+    assert_eq!(
+        graph.shortest_path("root", "D").unwrap(),
+        ["root", "A", "C", "D"]
+    );
+    assert_eq!(graph.shortest_path("B", "B").unwrap(), ["B"]);
+
+    // edges are followed forwards only
+    assert_eq!(graph.shortest_path("D", "root"), None);
+    assert_eq!(graph.shortest_path("root", "E"), None);
+}
+
+#[test]
+fn test_find_cycle_self_loop_pair_and_diamond() {
+    let diamond = Graph::new_without_events();
+    diamond.add_edge("root", "A").unwrap();
+    diamond.add_edge("root", "B").unwrap();
+    diamond.add_edge("A", "C").unwrap();
+    diamond.add_edge("B", "C").unwrap();
+    assert!(!diamond.has_cycle());
+    assert_eq!(diamond.find_cycle(), None);
+
+    let self_loop = Graph::new_without_events();
+    self_loop.add_edge("root", "A").unwrap();
+    self_loop.add_edge("A", "A").unwrap();
+    assert!(self_loop.has_cycle());
+    assert_eq!(self_loop.find_cycle().unwrap(), ["A"]);
+
+    let pair = Graph::new_without_events();
+    pair.add_edge("root", "A").unwrap();
+    pair.add_edge("A", "B").unwrap();
+    pair.add_edge("B", "A").unwrap();
+    assert!(pair.has_cycle());
+    let cycle = pair.find_cycle().unwrap();
+    assert_eq!(cycle.len(), 2);
+    // consecutive names, and the last one, are real edges
+    for (i, name) in cycle.iter().enumerate() {
+        let next = &cycle[(i + 1) % cycle.len()];
+        assert!(pair.contains_edge(name, next), "{} -> {}", name, next);
+    }
+}
+
+#[test]
+fn test_degrees_and_neighbors_on_diamond() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+
+    assert_eq!(graph.in_degree("C"), Some(2));
+    assert_eq!(graph.out_degree("C"), Some(0));
+    assert_eq!(graph.in_degree("root"), Some(0));
+    assert_eq!(graph.out_degree("root"), Some(2));
+    assert_eq!(graph.neighbors("root").unwrap(), ["A", "B"]);
+    assert_eq!(graph.neighbors("C").unwrap(), Vec::<String>::new());
+
+    assert_eq!(graph.neighbors("missing"), None);
+    assert_eq!(graph.out_degree("missing"), None);
+    assert_eq!(graph.in_degree("missing"), None);
+}
+
+#[test]
+fn test_connected_components_root_first() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "M").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    // Z and X only share a child, still one component undirected
+    graph.add_edge("Z", "Y").unwrap();
+    graph.add_edge("X", "Y").unwrap();
+
+    let components = graph.connected_components();
+    assert_eq!(components.len(), 3);
+    assert_eq!(components[0], ["M", "root"]);
+    assert_eq!(components[1], ["A", "B", "C"]);
+    assert_eq!(components[2], ["X", "Y", "Z"]);
+}
+
+#[test]
+fn test_descendants_and_ancestors_terminate_on_cycle() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "A").unwrap();
+    graph.add_edge("C", "D").unwrap();
+
+    let set = |names: &[&str]| -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    };
+
+    // A is on the cycle, so it reaches itself
+    assert_eq!(graph.descendants("A"), set(&["A", "B", "C", "D"]));
+    assert_eq!(graph.ancestors("A"), set(&["A", "B", "C"]));
+    assert_eq!(graph.descendants("D"), set(&[]));
+    assert_eq!(graph.ancestors("D"), set(&["A", "B", "C"]));
+
+    assert!(graph.is_reachable("B", "A"));
+    assert!(graph.is_reachable("D", "D"));
+    assert!(!graph.is_reachable("D", "A"));
+
+    assert!(graph.descendants("missing").is_empty());
+    assert!(graph.ancestors("missing").is_empty());
+    assert!(!graph.is_reachable("missing", "A"));
+    assert!(!graph.is_reachable("A", "missing"));
+}
+
+#[test]
+fn test_find_cycle_skips_edges_to_unlisted_nodes() {
+    let snapshot = GraphSnapshot {
+        nodes: vec!["A".into(), "B".into()],
+        edges: vec![
+            ("A".into(), "B".into()),
+            ("B".into(), "Gone".into()),
+            ("Gone".into(), "A".into()),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(find_cycle(&snapshot), None);
+}
+
+#[test]
+fn test_strongly_connected_skips_edges_to_unlisted_nodes() {
+    let snapshot = GraphSnapshot {
+        nodes: vec!["A".into(), "B".into()],
+        edges: vec![
+            ("A".into(), "B".into()),
+            ("B".into(), "A".into()),
+            ("B".into(), "Gone".into()),
+        ],
+        ..Default::default()
+    };
+    let mut sccs = strongly_connected(&snapshot);
+    sccs.iter_mut().for_each(|scc| scc.sort());
+    assert_eq!(sccs, [["A", "B"]]);
+}
@@ -2,7 +2,7 @@
 use std::sync::{Arc, Barrier};
 use std::thread;
 
-use crate::graph::core::Graph;
+use crate::graph::core::{EdgeOutcome, Graph};
 
 #[test]
 fn test_concurrent_add_edges_no_conflicts() {
@@ -54,7 +54,7 @@ fn test_concurrent_add_same_edge_duplicate_detection() {
 
     for handle in handles {
         match handle.join().unwrap() {
-            Ok(true) => success_count += 1,
+            Ok(EdgeOutcome::Inserted) => success_count += 1,
             _ => {
                 error_count += 1;
             }
@@ -352,7 +352,7 @@ fn test_concurrent_self_loop_creation() {
 
     for handle in handles {
         match handle.join().unwrap() {
-            Ok(true) => success_count += 1,
+            Ok(EdgeOutcome::Inserted) => success_count += 1,
             _ => error_count += 1,
         }
     }
@@ -438,8 +438,10 @@ fn test_concurrent_get_or_create_node() {
     }
 
     // only 3 edges should succeed (one per unique target)
-    let success_count =
-        results.iter().filter(|r| matches!(r, Ok(true))).count();
+    let success_count = results
+        .iter()
+        .filter(|r| matches!(r, Ok(EdgeOutcome::Inserted)))
+        .count();
     assert_eq!(success_count, target_nodes.len());
 
     // verify only the target nodes were created
@@ -577,3 +579,46 @@ fn test_concurrent_contains_get_node_consistency() {
     writer.join().unwrap();
     reader.join().unwrap();
 }
+
+#[test]
+fn test_edge_index_matches_children_under_stress() {
+    let (graph, _rx) = Graph::builder().edge_index(true).build();
+    let graph = Arc::new(graph);
+    let num_threads = 8;
+    let barrier = Arc::new(Barrier::new(num_threads));
+    let mut handles = vec![];
+
+    for i in 0..num_threads {
+        let graph_clone = Arc::clone(&graph);
+        let barrier_clone = Arc::clone(&barrier);
+        let handle = thread::spawn(move || {
+            barrier_clone.wait();
+            for j in 0..200 {
+                // every thread hammers the same edge, and overlapping others
+                graph_clone.add_edge("root", "shared").unwrap();
+                let parent = format!("p{}", j % 10);
+                let child = format!("c{}", (i + j) % 25);
+                graph_clone.add_edge(&parent, &child).unwrap();
+                if j % 7 == 0 {
+                    graph_clone.remove_edge(&parent, &child).unwrap();
+                }
+            }
+        });
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let edges = graph.snapshot().edges;
+    assert_eq!(graph.indexed_edges().unwrap(), edges);
+
+    let shared: Vec<_> = edges.iter().filter(|(_, c)| c == "shared").collect();
+    assert_eq!(shared.len(), 1);
+    assert!(graph.contains_edge("root", "shared"));
+
+    for (parent, child) in &edges {
+        assert!(graph.contains_edge(parent, child));
+    }
+}
@@ -1,111 +1,783 @@
 use std::{
-    collections::{HashMap, hash_map::Entry},
-    sync::{Arc, RwLock, Weak},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    ops::Bound,
+    slice,
+    sync::{
+        Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak,
+        atomic::{AtomicU64, Ordering},
+    },
 };
 
-use anyhow::anyhow;
-use tokio::sync::mpsc;
+use serde_json::Value;
+use tokio::sync::{
+    broadcast,
+    mpsc::{self, error::TrySendError},
+};
 use tracing::warn;
 
+pub use crate::graph::error::GraphError;
+use crate::graph::lock_order::{Ordered, Rank, ordered};
+use crate::graph::lock_trace::{LockTimer, record_duplicate};
+use crate::graph::stats::GraphStats;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GraphEvent {
     NodeAdded(String),
     EdgeAdded(String, String),
+    EdgeRemoved(String, String),
+
+    /// add_edge found the edge already there, nothing changed
+    DuplicateEdge(String, String),
+
+    /// sent after the EdgeRemoved events of every edge the node had
+    NodeRemoved(String),
+
+    /// node metadata changed, read the new values off the node
+    NodeUpdated(String),
+
+    /// periodic aggregate metrics, see `start_metrics_task`
+    Stats(GraphStats),
+
+    /// sent by the crawler after each page, never by the graph itself
+    CrawlProgress {
+        /// pages processed so far, fetched or failed
+        visited: usize,
+        /// urls waiting in the frontier
+        queued: usize,
+        /// link hops of the page just processed from its seed
+        depth: usize,
+    },
+}
+
+/// What add_edge did, a duplicate is routine while crawling so it isn't an
+/// error, see `Graph::add_new_edge` for the strict version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeOutcome {
+    Inserted,
+    AlreadyExisted,
+}
+
+impl EdgeOutcome {
+    pub fn is_inserted(self) -> bool {
+        self == EdgeOutcome::Inserted
+    }
 }
 
+pub(crate) type NodeMap<T = String> = HashMap<Arc<str>, Arc<Node<T>>>;
+
+/// parent name -> child names
+type EdgeIndex = HashMap<Arc<str>, HashSet<Arc<str>>>;
+
 // NOTE: Tokio's RwLock might be marginally better but idk
 
+/// Locks are taken in the order nodes, name_index, a node's children, a
+/// node's parents, a node's weights, edge_index, a node's meta, checked at
+/// runtime in tests, see `lock_order`
+///
+/// Nodes carry a `T` and are keyed by the name it derives, see `NodeData`.
 #[derive(Debug)]
-pub struct Graph {
-    root: Arc<Node>,
+pub struct Graph<T = String> {
+    root: Arc<Node<T>>,
 
-    pub(crate) nodes: RwLock<HashMap<String, Arc<Node>>>,
+    // keys share their allocation with Node::key
+    pub(crate) nodes: RwLock<NodeMap<T>>,
+
+    // sorted names for prefix search, only written while holding the
+    // nodes write lock, a leaf lock otherwise
+    name_index: RwLock<BTreeSet<Arc<str>>>,
     // TODO: add bloomfilter back in when doing distributed
     // filter: RwLock<Bloom<String>>
-    events_tx: Option<tokio::sync::mpsc::UnboundedSender<GraphEvent>>,
+    events_tx: Option<EventSender>,
+
+    // fan-out for `subscribe`, only sent to while someone is subscribed
+    subscribers: broadcast::Sender<GraphEvent>,
+
+    // bumped on every structural change, inside the mutating lock scope
+    generation: AtomicU64,
+
+    // events a full bounded channel turned away, see `dropped_events`
+    dropped_events: AtomicU64,
+
+    // (generation it was computed at, count)
+    reachable_cache: Mutex<Option<(u64, usize)>>,
+
+    max_name_len: Option<usize>,
+
+    // GraphBuilder::count_duplicates, add_edge on an existing edge bumps
+    // its weight
+    count_duplicates: bool,
+
+    // only with GraphBuilder::edge_index, written under the parent's
+    // children write lock so it never disagrees with the children vecs
+    edge_index: Option<RwLock<EdgeIndex>>,
 }
 
+/// The graph the crawler builds, a node's data is its name
+pub type StringGraph = Graph<String>;
+
+/// Data a node carries, and how to get the name it's keyed by from it
+///
+/// from_key makes the data of a node only known by name, like the far end
+/// of add_edge, `Graph::insert_node` takes the data itself
+pub trait NodeData {
+    fn key(&self) -> &str;
+
+    fn from_key(key: &str) -> Self;
+}
+
+impl NodeData for String {
+    fn key(&self) -> &str {
+        self
+    }
+
+    fn from_key(key: &str) -> String {
+        key.to_owned()
+    }
+}
+
+/// Graph construction options, start from `Graph::builder()`
+#[derive(Debug, Clone, Default)]
+pub struct GraphBuilder {
+    root_name: Option<String>,
+    max_name_len: Option<usize>,
+    edge_index: bool,
+    count_duplicates: bool,
+}
+
+impl GraphBuilder {
+    /// Name of the root node, `"root"` by default
+    pub fn root_name(mut self, name: impl Into<String>) -> GraphBuilder {
+        self.root_name = Some(name.into());
+        self
+    }
+
+    /// Reject node names longer than `max` bytes with
+    /// `GraphError::NameTooLong`, unlimited by default
+    pub fn max_name_len(mut self, max: usize) -> GraphBuilder {
+        self.max_name_len = Some(max);
+        self
+    }
+
+    /// Keep a name set of every edge so `contains_edge` is O(1) instead
+    /// of a scan of the parent's children, off by default since it holds
+    /// a second copy of the edge set
+    pub fn edge_index(mut self, enabled: bool) -> GraphBuilder {
+        self.edge_index = enabled;
+        self
+    }
+
+    /// Have add_edge and add_edges count an edge seen again as one more
+    /// to its weight, see `Node::edge_weight_to`, instead of only
+    /// reporting the duplicate
+    pub fn count_duplicates(mut self, enabled: bool) -> GraphBuilder {
+        self.count_duplicates = enabled;
+        self
+    }
+
+    pub fn build(self) -> (Graph, mpsc::UnboundedReceiver<GraphEvent>) {
+        self.build_for()
+    }
+
+    /// build for a graph whose nodes carry a `T`, root's data comes from
+    /// its name
+    pub fn build_for<T: NodeData>(
+        self,
+    ) -> (Graph<T>, mpsc::UnboundedReceiver<GraphEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (self.build_with(Some(EventSender::Unbounded(tx))), rx)
+    }
+
+    /// Graph that emits no events, for callers with no consumer, e.g. a
+    /// batch export; `subscribe` still works
+    pub fn build_without_events(self) -> Graph {
+        self.build_with(None)
+    }
+
+    /// See `Graph::new_bounded`
+    pub fn build_bounded(
+        self,
+        capacity: usize,
+    ) -> (Graph, mpsc::Receiver<GraphEvent>) {
+        let (tx, rx) = mpsc::channel(capacity);
+        (self.build_with(Some(EventSender::Bounded(tx))), rx)
+    }
+
+    pub(crate) fn build_with<T: NodeData>(
+        self,
+        events_tx: Option<EventSender>,
+    ) -> Graph<T> {
+        let root = Arc::new(Node::from_key(
+            self.root_name.as_deref().unwrap_or("root"),
+        ));
+        let mut map = HashMap::new();
+        map.insert(root.get_data_arc(), root.clone());
+
+        Graph {
+            nodes: RwLock::new(map),
+            name_index: RwLock::new(BTreeSet::from([root.get_data_arc()])),
+            root,
+            events_tx,
+            subscribers: broadcast::channel(SUBSCRIBER_CAPACITY).0,
+            generation: AtomicU64::new(0),
+            dropped_events: AtomicU64::new(0),
+            reachable_cache: Mutex::new(None),
+            max_name_len: self.max_name_len,
+            count_duplicates: self.count_duplicates,
+            edge_index: self.edge_index.then(RwLock::default),
+        }
+    }
+}
+
+/// Events a `subscribe` receiver can fall behind by before it lags
+pub const SUBSCRIBER_CAPACITY: usize = 1024;
+
 #[derive(Debug)]
-pub struct Node {
-    data: String,
+pub(crate) enum EventSender {
+    Unbounded(mpsc::UnboundedSender<GraphEvent>),
+    Bounded(mpsc::Sender<GraphEvent>),
+}
+
+/// How an event is handed to a bounded channel that's full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Delivery {
+    /// drop it with a warning, never blocks the caller
+    Try,
 
-    children: RwLock<Vec<Weak<Node>>>,
+    /// wait for capacity, only valid outside the async runtime
+    Block,
+}
+
+#[derive(Debug)]
+pub struct Node<T = String> {
+    // derived from data, shared with the graph's maps
+    key: Arc<str>,
+
+    data: T,
+
+    pub(crate) children: RwLock<Vec<Weak<Node<T>>>>,
+
+    // backlinks, only written under the parent's children write lock
+    parents: RwLock<Vec<Weak<Node<T>>>>,
+
+    // weights of edges to children above 1, by child name, only written
+    // under this node's children write lock
+    weights: RwLock<HashMap<Arc<str>, u32>>,
+
+    // leaf lock, never held while taking another
+    meta: RwLock<BTreeMap<String, Value>>,
 }
 
 impl Node {
     pub fn new(data: &str) -> Node {
+        Node::with_data(data.to_owned())
+    }
+}
+
+impl<T: NodeData> Node<T> {
+    pub fn with_data(data: T) -> Node<T> {
         Node {
-            data: data.to_owned(),
+            key: Arc::from(data.key()),
+            data,
             children: RwLock::new(vec![]),
+            parents: RwLock::new(vec![]),
+            weights: RwLock::new(HashMap::new()),
+            meta: RwLock::new(BTreeMap::new()),
         }
     }
 
+    pub fn from_key(key: &str) -> Node<T> {
+        Node::with_data(T::from_key(key))
+    }
+}
+
+impl<T> Node<T> {
+    /// The node's name, its key in the graph, see `NodeData::key`
     pub fn get_data(&self) -> &str {
+        &self.key
+    }
+
+    /// Cheap clone of the name, shares the allocation with the graph
+    pub fn get_data_arc(&self) -> Arc<str> {
+        self.key.clone()
+    }
+
+    /// What the node carries, for a StringGraph the name again
+    pub fn data(&self) -> &T {
         &self.data
     }
 
-    pub fn get_children(&self) -> Vec<Arc<Node>> {
-        self.children
-            .read()
-            .unwrap()
+    pub fn get_meta(&self, key: &str) -> Option<Value> {
+        ordered(Rank::Meta, || self.meta.read().unwrap())
+            .get(key)
+            .cloned()
+    }
+
+    /// A string metadata value, None if unset or not a string, see
+    /// `Graph::set_node_attr`
+    pub fn get_attr(&self, key: &str) -> Option<String> {
+        match self.get_meta(key)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_all_meta(&self) -> BTreeMap<String, Value> {
+        ordered(Rank::Meta, || self.meta.read().unwrap()).clone()
+    }
+
+    pub fn get_children(&self) -> Vec<Arc<Node<T>>> {
+        self.children_read()
             .iter()
             .filter_map(|weak| weak.upgrade()) // filter rejects all dead refs
             .collect()
     }
+
+    /// Nodes with an edge to this one, in the order those edges were added
+    pub fn get_parents(&self) -> Vec<Arc<Node<T>>> {
+        ordered(Rank::Parents, || self.parents.read().unwrap())
+            .iter()
+            .filter_map(|weak| weak.upgrade())
+            .collect()
+    }
+
+    /// Weight of the edge to `child`, 1 unless it was added with more or
+    /// counted again, None without such an edge
+    pub fn edge_weight_to(&self, child: &Arc<Node<T>>) -> Option<u32> {
+        let children = self.children_read();
+        if !children.iter().any(|c| c.as_ptr() == Arc::as_ptr(child)) {
+            return None;
+        }
+
+        let weights = ordered(Rank::Weights, || self.weights.read().unwrap());
+        Some(weights.get(child.get_data()).copied().unwrap_or(1))
+    }
+
+    /// Adds `by` to the weight of an edge to `child`, call with this
+    /// node's children write lock held and the edge present
+    fn add_weight(&self, child: &Node<T>, by: u32) {
+        let mut weights =
+            ordered(Rank::Weights, || self.weights.write().unwrap());
+        let weight = weights.entry(child.get_data_arc()).or_insert(1);
+        *weight = weight.saturating_add(by);
+    }
+
+    fn link_parent(&self, parent: &Arc<Node<T>>, added: bool) {
+        let mut parents =
+            ordered(Rank::Parents, || self.parents.write().unwrap());
+        if added {
+            parents.push(Arc::downgrade(parent));
+        } else {
+            parents.retain(|p| p.as_ptr() != Arc::as_ptr(parent));
+        }
+    }
+
+    /// Runs `f` over the live children under the children read lock,
+    /// without collecting them into a Vec first
+    ///
+    /// WARN: `f` must not take a graph lock, an add_edge from inside it
+    /// deadlocks on the children of this node
+    pub fn with_children<R>(&self, f: impl FnOnce(Children<'_, T>) -> R) -> R {
+        let children = self.children_read();
+        f(Children(children.iter()))
+    }
+
+    pub(crate) fn children_read(
+        &self,
+    ) -> Ordered<RwLockReadGuard<'_, Vec<Weak<Node<T>>>>> {
+        ordered(Rank::Children, || self.children.read().unwrap())
+    }
+
+    pub(crate) fn children_write(
+        &self,
+    ) -> Ordered<RwLockWriteGuard<'_, Vec<Weak<Node<T>>>>> {
+        ordered(Rank::Children, || self.children.write().unwrap())
+    }
+}
+
+/// Nodes compare and hash by name alone, not by identity or children
+///
+/// A graph never holds two nodes with the same name, so within one graph
+/// this agrees with `Arc::ptr_eq`. Nodes from different graphs (or built
+/// with `Node::new`) are equal whenever their names are.
+impl<T> PartialEq for Node<T> {
+    fn eq(&self, other: &Node<T>) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<T> Eq for Node<T> {}
+
+impl<T> Hash for Node<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+/// Live children of a node in insertion order, see `Node::with_children`
+pub struct Children<'a, T = String>(slice::Iter<'a, Weak<Node<T>>>);
+
+impl<T> Iterator for Children<'_, T> {
+    type Item = Arc<Node<T>>;
+
+    fn next(&mut self) -> Option<Arc<Node<T>>> {
+        self.0.find_map(Weak::upgrade)
+    }
 }
 
 impl Graph {
     pub fn new() -> (Graph, mpsc::UnboundedReceiver<GraphEvent>) {
-        let root = Arc::new(Node::new("root"));
-        let mut map = HashMap::new();
-        map.insert(String::from("root"), root.clone());
+        GraphBuilder::default().build()
+    }
 
-        let (tx, rx) = mpsc::unbounded_channel();
+    pub fn builder() -> GraphBuilder {
+        GraphBuilder::default()
+    }
 
-        (
-            Graph {
-                nodes: RwLock::new(map),
-                root: root,
-                events_tx: Some(tx),
-            },
-            rx,
-        )
+    /// Graph whose events go through a channel of `capacity`
+    ///
+    /// add_edge drops events with a warning while the channel is full,
+    /// add_edge_blocking waits for the consumer instead.
+    pub fn new_bounded(capacity: usize) -> (Graph, mpsc::Receiver<GraphEvent>) {
+        GraphBuilder::default().build_bounded(capacity)
     }
 
-    pub fn get_root(&self) -> Arc<Node> {
+    /// See `GraphBuilder::build_without_events`
+    pub fn new_without_events() -> Graph {
+        GraphBuilder::default().build_without_events()
+    }
+
+    /// Nodes reachable from root, root included
+    ///
+    /// Cached per generation, so repeated reads between mutations are O(1)
+    /// and the first read after a change pays for one traversal.
+    pub fn reachable_from_root_count(&self) -> usize {
+        let generation = self.generation();
+
+        let mut cache = self.reachable_cache.lock().unwrap();
+        if let Some((cached_at, count)) = *cache
+            && cached_at == generation
+        {
+            return count;
+        }
+
+        // generation was read before traversing, so a concurrent insert
+        // leaves the cache stale and the next read recomputes
+        let count = self.reachable_from(&self.root).len();
+        *cache = Some((generation, count));
+        count
+    }
+}
+
+impl<T: NodeData> Graph<T> {
+    /// Builder with this graph's root name and settings, for a graph
+    /// that should behave like this one
+    pub fn to_builder(&self) -> GraphBuilder {
+        GraphBuilder {
+            root_name: Some(self.root.get_data().to_owned()),
+            max_name_len: self.max_name_len,
+            edge_index: self.edge_index.is_some(),
+            count_duplicates: self.count_duplicates,
+        }
+    }
+
+    pub fn get_root(&self) -> Arc<Node<T>> {
         self.root.clone()
     }
 
+    /// Counter that changes whenever a node or edge is added or removed,
+    /// or node metadata is set
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Events dropped because the bounded channel was full, see
+    /// `Graph::new_bounded`, always 0 for other graphs
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn nodes_read(
+        &self,
+    ) -> Ordered<RwLockReadGuard<'_, NodeMap<T>>> {
+        ordered(Rank::Nodes, || self.nodes.read().unwrap())
+    }
+
+    pub(crate) fn nodes_write(
+        &self,
+    ) -> Ordered<RwLockWriteGuard<'_, NodeMap<T>>> {
+        ordered(Rank::Nodes, || self.nodes.write().unwrap())
+    }
+
+    pub(crate) fn bump_generation(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Longest node name accepted, see `GraphBuilder::max_name_len`
+    pub fn max_name_len(&self) -> Option<usize> {
+        self.max_name_len
+    }
+
     /// WARN: acquires nodes lock
     pub fn node_count(&self) -> usize {
-        self.nodes.read().unwrap().len()
+        self.nodes_read().len()
     }
 
     /// WARN: acquires nodes lock
     pub fn contains(&self, content: &str) -> bool {
-        self.nodes.read().unwrap().contains_key(content)
+        self.nodes_read().contains_key(content)
     }
 
     /// WARN: acquires nodes lock
-    pub fn get_node(&self, content: &str) -> Option<Arc<Node>> {
-        self.nodes.read().unwrap().get(content).cloned()
+    pub fn get_node(&self, content: &str) -> Option<Arc<Node<T>>> {
+        self.nodes_read().get(content).cloned()
+    }
+
+    /// Up to `limit` node names starting with `prefix`, in sorted order
+    ///
+    /// WARN: acquires the name index lock
+    pub fn search_prefix(&self, prefix: &str, limit: usize) -> Vec<String> {
+        ordered(Rank::NameIndex, || self.name_index.read().unwrap())
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|name| name.starts_with(prefix))
+            .take(limit)
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Whether the edge `parent -> child` exists
+    ///
+    /// WARN: acquires the edge index lock, or without the index the nodes
+    /// lock, then the parent's children lock
+    pub fn contains_edge(&self, parent: &str, child: &str) -> bool {
+        match &self.edge_index {
+            Some(index) => ordered(Rank::EdgeIndex, || index.read().unwrap())
+                .get(parent)
+                .is_some_and(|children| children.contains(child)),
+            None => self.get_node(parent).is_some_and(|node| {
+                node.with_children(|mut children| {
+                    children.any(|c| c.get_data() == child)
+                })
+            }),
+        }
+    }
+
+    /// Mirrors an added or removed edge into the child's parents and the
+    /// edge index, if any, call with the parent's children write lock held
+    fn index_edge(&self, parent: &Arc<Node<T>>, child: &Node<T>, added: bool) {
+        child.link_parent(parent, added);
+        if !added {
+            ordered(Rank::Weights, || parent.weights.write().unwrap())
+                .remove(child.get_data());
+        }
+
+        let Some(index) = &self.edge_index else {
+            return;
+        };
+
+        let mut index = ordered(Rank::EdgeIndex, || index.write().unwrap());
+        if added {
+            index
+                .entry(parent.get_data_arc())
+                .or_default()
+                .insert(child.get_data_arc());
+        } else if let Some(children) = index.get_mut(parent.get_data()) {
+            children.remove(child.get_data());
+        }
     }
 
     // TODO: disjointed graphs allowed for now
-    /// Returns Ok(true) if edge was added
-    /// Returns Ok(false) if edge already exists
+    /// Returns Ok(Inserted) if edge was added
+    /// Returns Ok(AlreadyExisted) if edge already exists
     /// Returns Err(...) for actual errors
+    #[cfg_attr(
+        feature = "lock-tracing",
+        tracing::instrument(
+            level = "trace",
+            skip(self),
+            fields(
+                duplicate = tracing::field::Empty,
+                children_wait_us = tracing::field::Empty,
+                children_held_us = tracing::field::Empty,
+            )
+        )
+    )]
     pub fn add_edge(
         &self,
         parent_content: &str,
         child_content: &str,
-    ) -> anyhow::Result<bool> {
+    ) -> Result<EdgeOutcome, GraphError> {
+        let weight = self.count_duplicates.then_some(1);
+        self.insert_edge(parent_content, child_content, Delivery::Try, weight)
+    }
+
+    /// add_edge with the edge counted `weight` times: a new edge starts
+    /// at `weight`, an existing one has `weight` added to it
+    ///
+    /// Returns Ok(Inserted) if the edge is new, emits like add_edge. A weight
+    /// of 0 still adds the edge, at weight 1.
+    pub fn add_weighted_edge(
+        &self,
+        parent_content: &str,
+        child_content: &str,
+        weight: u32,
+    ) -> Result<EdgeOutcome, GraphError> {
+        self.insert_edge(
+            parent_content,
+            child_content,
+            Delivery::Try,
+            Some(weight),
+        )
+    }
+
+    /// add_edge for synchronous callers such as `spawn_blocking` closures
+    ///
+    /// On a bounded graph this waits for channel capacity instead of
+    /// dropping events, so it must never be called from async code.
+    pub fn add_edge_blocking(
+        &self,
+        parent_content: &str,
+        child_content: &str,
+    ) -> Result<EdgeOutcome, GraphError> {
+        let weight = self.count_duplicates.then_some(1);
+        self.insert_edge(parent_content, child_content, Delivery::Block, weight)
+    }
+
+    /// add_edge for callers that need the edge to be new, an existing edge
+    /// is Err(EdgeExists) and its weight is left alone
+    ///
+    /// Emits like add_edge, DuplicateEdge included.
+    pub fn add_new_edge(
+        &self,
+        parent_content: &str,
+        child_content: &str,
+    ) -> Result<(), GraphError> {
+        let outcome = self.insert_edge(
+            parent_content,
+            child_content,
+            Delivery::Try,
+            None,
+        )?;
+        if !outcome.is_inserted() {
+            return Err(GraphError::EdgeExists {
+                parent: parent_content.to_owned(),
+                child: child_content.to_owned(),
+            });
+        }
+        Ok(())
+    }
+
+    /// add_edge for many children of one parent, like the links of a page
+    ///
+    /// Takes the nodes lock and the parent's children lock once for the
+    /// whole batch instead of once per edge. Every name is checked against
+    /// max_name_len before anything changes. Emits NodeAdded for each new
+    /// node, then EdgeAdded for each new edge or DuplicateEdge for one that
+    /// existed, all in `children` order. Returns how many edges were
+    /// added, duplicates are skipped.
+    ///
+    /// WARN: acquires nodes lock, then the parent's children lock
+    pub fn add_edges<S: AsRef<str>>(
+        &self,
+        parent_content: &str,
+        children: &[S],
+    ) -> Result<usize, GraphError> {
+        let names = || {
+            std::iter::once(parent_content)
+                .chain(children.iter().map(AsRef::as_ref))
+        };
+        if let Some(max) = self.max_name_len
+            && let Some(name) = names().find(|name| name.len() > max)
+        {
+            return Err(GraphError::NameTooLong {
+                len: name.len(),
+                max,
+            });
+        }
+
+        let mut new_nodes = Vec::new();
+        let mut resolved: Vec<Arc<Node<T>>> = {
+            let mut nodes = self.nodes_write();
+            let resolved = names()
+                .map(|name| match nodes.get(name) {
+                    Some(node) => node.clone(),
+                    None => {
+                        let node = Arc::new(Node::from_key(name));
+                        nodes.insert(node.get_data_arc(), node.clone());
+                        new_nodes.push(node.clone());
+                        node
+                    }
+                })
+                .collect();
+
+            if !new_nodes.is_empty() {
+                let mut index = ordered(Rank::NameIndex, || {
+                    self.name_index.write().unwrap()
+                });
+                index.extend(new_nodes.iter().map(|n| n.get_data_arc()));
+                self.bump_generation();
+            }
+            resolved
+        };
+        let parent = resolved.remove(0);
+
+        // EdgeAdded or DuplicateEdge per child, in order
+        let mut edge_events = Vec::new();
+        let mut added = 0;
+        {
+            let mut existing = parent.children_write();
+            let mut present: HashSet<*const Node<T>> =
+                existing.iter().map(Weak::as_ptr).collect();
+
+            for child in &resolved {
+                let edge = (parent_content.to_owned(), child.get_data().into());
+                if present.insert(Arc::as_ptr(child)) {
+                    existing.push(Arc::downgrade(child));
+                    self.index_edge(&parent, child, true);
+                    edge_events.push(GraphEvent::EdgeAdded(edge.0, edge.1));
+                    added += 1;
+                } else {
+                    if self.count_duplicates {
+                        parent.add_weight(child, 1);
+                    }
+                    edge_events.push(GraphEvent::DuplicateEdge(edge.0, edge.1));
+                }
+            }
+            if added > 0 {
+                self.bump_generation();
+            }
+        }
+
+        for node in new_nodes {
+            self.emit(GraphEvent::NodeAdded(node.get_data().to_owned()))?;
+        }
+        for event in edge_events {
+            self.emit(event)?;
+        }
+
+        Ok(added)
+    }
+
+    /// With a `weight`, a duplicate adds it to the edge's weight and a new
+    /// edge starts at it
+    fn insert_edge(
+        &self,
+        parent_content: &str,
+        child_content: &str,
+        delivery: Delivery,
+        weight: Option<u32>,
+    ) -> Result<EdgeOutcome, GraphError> {
         // get canonical nodes (creates if needed, returns existing if present)
-        let parent = self.get_or_create_node(parent_content)?;
-        let child = self.get_or_create_node(child_content)?;
+        let parent = self.node_or_insert(parent_content, None, delivery)?;
+        let child = self.node_or_insert(child_content, None, delivery)?;
 
         {
-            // check duplicate edge using ptr_eq
-            let mut children = parent.children.write().unwrap();
+            // check duplicate edge using ptr_eq, identity rather than the
+            // name-based PartialEq so a stale same-named node never matches
+            let wait = LockTimer::start();
+            let mut children = parent.children_write();
+            wait.record("children_wait_us");
+            let held = LockTimer::start();
 
             if children.iter().any(|c| {
                 match c.upgrade() {
@@ -116,47 +788,541 @@ impl Graph {
                     None => false,
                 }
             }) {
-                warn!(
-                    "Edge ({} -> {}) already exists",
-                    parent_content, child_content
-                );
-                return Ok(false);
+                if let Some(weight) = weight {
+                    parent.add_weight(&child, weight);
+                }
+                drop(children);
+                held.record("children_held_us");
+                record_duplicate(true);
+
+                // routine when duplicates are what's being counted
+                if weight.is_none() {
+                    warn!(
+                        "Edge ({} -> {}) already exists",
+                        parent_content, child_content
+                    );
+                }
+                self.emit_with(
+                    GraphEvent::DuplicateEdge(
+                        parent_content.to_owned(),
+                        child_content.to_owned(),
+                    ),
+                    delivery,
+                )?;
+                return Ok(EdgeOutcome::AlreadyExisted);
             }
 
             children.push(Arc::downgrade(&child));
+            self.index_edge(&parent, &child, true);
+            if let Some(weight) = weight
+                && weight > 1
+            {
+                parent.add_weight(&child, weight - 1);
+            }
+            self.bump_generation();
+
+            drop(children);
+            held.record("children_held_us");
+            record_duplicate(false);
         } // scoped to drop lock before channel stuff
 
-        if let Some(tx) = &self.events_tx {
-            tx.send(GraphEvent::EdgeAdded(
+        self.emit_with(
+            GraphEvent::EdgeAdded(
                 parent_content.to_owned(),
                 child_content.to_owned(),
-            ))
-            .map_err(|e| anyhow!("Event dropped: {}", e))?;
+            ),
+            delivery,
+        )?;
+
+        Ok(EdgeOutcome::Inserted)
+    }
+
+    /// Attaches a metadata value to an existing node, emits NodeUpdated
+    ///
+    /// WARN: acquires nodes lock, then the node's meta lock
+    pub fn set_node_meta(
+        &self,
+        content: &str,
+        key: &str,
+        value: impl Into<Value>,
+    ) -> Result<(), GraphError> {
+        let node = self
+            .get_node(content)
+            .ok_or_else(|| GraphError::NodeNotFound(content.to_owned()))?;
+
+        ordered(Rank::Meta, || node.meta.write().unwrap())
+            .insert(key.to_owned(), value.into());
+        self.bump_generation();
+
+        self.emit(GraphEvent::NodeUpdated(content.to_owned()))
+    }
+
+    /// set_node_meta for plain string attributes like a page's `title`,
+    /// read back with `Node::get_attr`
+    pub fn set_node_attr(
+        &self,
+        content: &str,
+        key: &str,
+        value: impl Into<String>,
+    ) -> Result<(), GraphError> {
+        self.set_node_meta(content, key, value.into())
+    }
+
+    /// Removes the edge and emits EdgeRemoved, Ok(false) if there was none
+    ///
+    /// WARN: acquires nodes lock, then the parent's children lock
+    pub fn remove_edge(
+        &self,
+        parent_content: &str,
+        child_content: &str,
+    ) -> Result<bool, GraphError> {
+        let node = |content: &str| {
+            self.get_node(content)
+                .ok_or_else(|| GraphError::NodeNotFound(content.to_owned()))
+        };
+        let parent = node(parent_content)?;
+        let child = node(child_content)?;
+
+        {
+            let mut children = parent.children_write();
+            let Some(i) = children
+                .iter()
+                .position(|c| c.as_ptr() == Arc::as_ptr(&child))
+            else {
+                return Ok(false);
+            };
+            children.remove(i);
+            self.index_edge(&parent, &child, false);
+            self.bump_generation();
         }
 
+        self.emit(GraphEvent::EdgeRemoved(
+            parent_content.to_owned(),
+            child_content.to_owned(),
+        ))?;
+
         Ok(true)
     }
 
-    fn get_or_create_node(&self, content: &str) -> anyhow::Result<Arc<Node>> {
+    /// Strips every edge whose child is its own parent, returns how many
+    ///
+    /// WARN: acquires nodes lock, then each node's children lock in turn
+    pub fn remove_self_loops(&self) -> usize {
+        let nodes: Vec<Arc<Node<T>>> =
+            self.nodes_read().values().cloned().collect();
+
+        let mut removed = 0;
+        for node in nodes {
+            let count = {
+                let mut children = node.children_write();
+                let before = children.len();
+                children.retain(|c| c.as_ptr() != Arc::as_ptr(&node));
+                if children.len() != before {
+                    self.index_edge(&node, &node, false);
+                    self.bump_generation();
+                }
+                before - children.len()
+            };
+
+            for _ in 0..count {
+                let name = node.get_data().to_owned();
+                if let Err(e) =
+                    self.emit(GraphEvent::EdgeRemoved(name.clone(), name))
+                {
+                    warn!("{}", e);
+                }
+            }
+            removed += count;
+        }
+
+        removed
+    }
+
+    /// Folds `absorb` into `keep`: its parents link to `keep` instead, its
+    /// children become `keep`'s, then `absorb` is removed
+    ///
+    /// Edges `keep` already has aren't duplicated. Edges between the two,
+    /// and a self-loop on `absorb`, would become a self-loop on `keep` and
+    /// are dropped instead, a self-loop `keep` already had stays. Metadata
+    /// of `absorb` is dropped. Emits EdgeRemoved for each of `absorb`'s
+    /// edges and EdgeAdded for each edge `keep` gains, then NodeRemoved.
+    ///
+    /// WARN: acquires nodes lock, then each node's children lock in turn
+    pub fn merge_nodes(
+        &self,
+        keep: &str,
+        absorb: &str,
+    ) -> Result<(), GraphError> {
+        if keep == absorb {
+            return Err(GraphError::SelfMerge(keep.to_owned()));
+        }
+
+        let node = |content: &str| {
+            self.get_node(content)
+                .ok_or_else(|| GraphError::NodeNotFound(content.to_owned()))
+        };
+        let keep_node = node(keep)?;
+        let absorb_node = node(absorb)?;
+        if Arc::ptr_eq(&absorb_node, &self.root) {
+            return Err(GraphError::RootRemovalForbidden);
+        }
+
+        let is = |weak: &Weak<Node<T>>, node: &Arc<Node<T>>| {
+            weak.as_ptr() == Arc::as_ptr(node)
+        };
+        let nodes: Vec<Arc<Node<T>>> =
+            self.nodes_read().values().cloned().collect();
+        let mut events = Vec::new();
+
+        // redirect parents, absorb's own self-loop goes with its children
+        for parent in nodes.iter().filter(|n| !Arc::ptr_eq(n, &absorb_node)) {
+            let mut children = parent.children_write();
+            let before = children.len();
+            children.retain(|c| !is(c, &absorb_node));
+            if children.len() == before {
+                continue;
+            }
+
+            self.index_edge(parent, &absorb_node, false);
+            events.push(GraphEvent::EdgeRemoved(
+                parent.get_data().to_owned(),
+                absorb.to_owned(),
+            ));
+
+            if !Arc::ptr_eq(parent, &keep_node)
+                && !children.iter().any(|c| is(c, &keep_node))
+            {
+                children.push(Arc::downgrade(&keep_node));
+                self.index_edge(parent, &keep_node, true);
+                events.push(GraphEvent::EdgeAdded(
+                    parent.get_data().to_owned(),
+                    keep.to_owned(),
+                ));
+            }
+            self.bump_generation();
+        }
+
+        let absorbed: Vec<Arc<Node<T>>> = {
+            let mut children = absorb_node.children_write();
+            let absorbed: Vec<Arc<Node<T>>> =
+                children.drain(..).filter_map(|c| c.upgrade()).collect();
+            for child in &absorbed {
+                self.index_edge(&absorb_node, child, false);
+            }
+            absorbed
+        };
+
+        {
+            let mut children = keep_node.children_write();
+            for child in absorbed {
+                events.push(GraphEvent::EdgeRemoved(
+                    absorb.to_owned(),
+                    child.get_data().to_owned(),
+                ));
+
+                let self_loop = Arc::ptr_eq(&child, &absorb_node)
+                    || Arc::ptr_eq(&child, &keep_node);
+                if self_loop || children.iter().any(|c| is(c, &child)) {
+                    continue;
+                }
+
+                children.push(Arc::downgrade(&child));
+                self.index_edge(&keep_node, &child, true);
+                events.push(GraphEvent::EdgeAdded(
+                    keep.to_owned(),
+                    child.get_data().to_owned(),
+                ));
+            }
+        }
+
+        self.unlink_node(&absorb_node);
+        events.push(GraphEvent::NodeRemoved(absorb.to_owned()));
+
+        for event in events {
+            self.emit(event)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes a node with every edge into and out of it, false for root
+    /// or a missing node
+    ///
+    /// Emits EdgeRemoved for each of its edges, then NodeRemoved.
+    ///
+    /// WARN: acquires each parent's children lock in turn, then the node's
+    /// own, then the nodes lock
+    pub fn remove_node(&self, content: &str) -> bool {
+        let Some(node) = self.get_node(content) else {
+            return false;
+        };
+        if Arc::ptr_eq(&node, &self.root) {
+            return false;
+        }
+
+        let mut events = Vec::new();
+        // a self-loop goes with the children below
+        for parent in node.get_parents() {
+            if Arc::ptr_eq(&parent, &node) {
+                continue;
+            }
+
+            let mut children = parent.children_write();
+            let before = children.len();
+            children.retain(|c| c.as_ptr() != Arc::as_ptr(&node));
+            if children.len() != before {
+                self.index_edge(&parent, &node, false);
+                events.push(GraphEvent::EdgeRemoved(
+                    parent.get_data().to_owned(),
+                    content.to_owned(),
+                ));
+            }
+        }
+
+        {
+            let mut children = node.children_write();
+            for child in children.drain(..).filter_map(|c| c.upgrade()) {
+                self.index_edge(&node, &child, false);
+                events.push(GraphEvent::EdgeRemoved(
+                    content.to_owned(),
+                    child.get_data().to_owned(),
+                ));
+            }
+        }
+
+        self.unlink_node(&node);
+        events.push(GraphEvent::NodeRemoved(content.to_owned()));
+
+        for event in events {
+            if let Err(e) = self.emit(event) {
+                warn!("{}", e);
+            }
+        }
+
+        true
+    }
+
+    /// Back to a lone root: every other node is removed like with
+    /// remove_node, root loses its self-loop and metadata
+    pub fn clear(&self) {
+        let root = self.root.get_data();
+        let names: Vec<Arc<str>> = self
+            .nodes_read()
+            .keys()
+            .filter(|name| &***name != root)
+            .cloned()
+            .collect();
+        for name in names {
+            self.remove_node(&name);
+        }
+
+        if let Err(e) = self.remove_edge(root, root) {
+            warn!("{}", e);
+        }
+
+        let had_meta = {
+            let mut meta =
+                ordered(Rank::Meta, || self.root.meta.write().unwrap());
+            let had_meta = !meta.is_empty();
+            meta.clear();
+            had_meta
+        };
+        if had_meta {
+            self.bump_generation();
+            if let Err(e) = self.emit(GraphEvent::NodeUpdated(root.to_owned()))
+            {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    /// Drops `node` from the node map and the indexes, without touching
+    /// the edges pointing at it
+    pub(crate) fn unlink_node(&self, node: &Node<T>) {
+        let mut nodes = self.nodes_write();
+        nodes.remove(node.get_data());
+        ordered(Rank::NameIndex, || self.name_index.write().unwrap())
+            .remove(node.get_data());
+        if let Some(index) = &self.edge_index {
+            ordered(Rank::EdgeIndex, || index.write().unwrap())
+                .remove(node.get_data());
+        }
+        self.bump_generation();
+    }
+
+    /// Correctness probe: (parent, child, count) for every live child that
+    /// appears more than once in a parent's children vec, sorted
+    ///
+    /// add_edge dedups by pointer, so this should always come back empty.
+    ///
+    /// WARN: acquires nodes lock, then each node's children lock in turn
+    pub fn audit_duplicate_edges(&self) -> Vec<(String, String, usize)> {
+        let nodes: Vec<Arc<Node<T>>> =
+            self.nodes_read().values().cloned().collect();
+
+        let mut duplicates = Vec::new();
+        for node in nodes {
+            let mut counts: HashMap<*const Node<T>, (Arc<Node<T>>, usize)> =
+                HashMap::new();
+
+            node.with_children(|children| {
+                for child in children {
+                    counts
+                        .entry(Arc::as_ptr(&child))
+                        .or_insert((child, 0))
+                        .1 += 1;
+                }
+            });
+
+            duplicates.extend(
+                counts.into_values().filter(|(_, n)| *n > 1).map(
+                    |(child, n)| {
+                        (
+                            node.get_data().to_owned(),
+                            child.get_data().to_owned(),
+                            n,
+                        )
+                    },
+                ),
+            );
+        }
+
+        duplicates.sort();
+        duplicates
+    }
+
+    /// Another receiver of every event from now on, alongside the one
+    /// handed out at construction, works on event-less graphs too
+    ///
+    /// Each subscriber gets its own copy of every event. One that falls
+    /// more than `SUBSCRIBER_CAPACITY` events behind doesn't slow the
+    /// graph down, its next `recv` returns `RecvError::Lagged(n)` and it
+    /// continues from the oldest event still buffered, the `n` before it
+    /// are lost to that subscriber only.
+    pub fn subscribe(&self) -> broadcast::Receiver<GraphEvent> {
+        self.subscribers.subscribe()
+    }
+
+    pub(crate) fn emit(&self, event: GraphEvent) -> Result<(), GraphError> {
+        self.emit_with(event, Delivery::Try)
+    }
+
+    fn emit_with(
+        &self,
+        event: GraphEvent,
+        delivery: Delivery,
+    ) -> Result<(), GraphError> {
+        if self.subscribers.receiver_count() > 0 {
+            // only fails once every subscriber is gone
+            let _ = self.subscribers.send(event.clone());
+        }
+
+        let closed = match (&self.events_tx, delivery) {
+            (None, _) => false,
+            (Some(EventSender::Unbounded(tx)), _) => tx.send(event).is_err(),
+            (Some(EventSender::Bounded(tx)), Delivery::Block) => {
+                tx.blocking_send(event).is_err()
+            }
+            (Some(EventSender::Bounded(tx)), Delivery::Try) => {
+                match tx.try_send(event) {
+                    Ok(()) => false,
+                    Err(TrySendError::Full(event)) => {
+                        self.dropped_events.fetch_add(1, Ordering::Relaxed);
+                        warn!("Event channel full, dropped {:?}", event);
+                        false
+                    }
+                    Err(TrySendError::Closed(_)) => true,
+                }
+            }
+        };
+
+        if closed {
+            return Err(GraphError::EventChannelClosed);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_or_create_node(
+        &self,
+        content: &str,
+    ) -> Result<Arc<Node<T>>, GraphError> {
+        self.node_or_insert(content, None, Delivery::Try)
+    }
+
+    /// Adds a node carrying `data` under `data.key()`, emits NodeAdded
+    ///
+    /// A node already under that key is returned as it is, its data isn't
+    /// replaced.
+    ///
+    /// WARN: acquires nodes lock
+    pub fn insert_node(&self, data: T) -> Result<Arc<Node<T>>, GraphError> {
+        let key = data.key().to_owned();
+        self.node_or_insert(&key, Some(data), Delivery::Try)
+    }
+
+    #[cfg_attr(
+        feature = "lock-tracing",
+        tracing::instrument(
+            name = "get_or_create_node",
+            level = "trace",
+            skip(self, data, delivery),
+            fields(
+                nodes_wait_us = tracing::field::Empty,
+                nodes_held_us = tracing::field::Empty,
+            )
+        )
+    )]
+    /// A new node gets `data`, or without it data made from the name
+    fn node_or_insert(
+        &self,
+        content: &str,
+        data: Option<T>,
+        delivery: Delivery,
+    ) -> Result<Arc<Node<T>>, GraphError> {
+        // before any lock or allocation, that's the point of the limit
+        if let Some(max) = self.max_name_len
+            && content.len() > max
+        {
+            return Err(GraphError::NameTooLong {
+                len: content.len(),
+                max,
+            });
+        }
+
         let (node, is_new) = {
-            let mut nodes = self.nodes.write().unwrap();
+            let wait = LockTimer::start();
+            let mut nodes = self.nodes_write();
+            wait.record("nodes_wait_us");
+            let held = LockTimer::start();
 
-            match nodes.entry(content.to_owned()) {
-                Entry::Vacant(e) => {
-                    let node = Arc::new(Node::new(content));
-                    e.insert(node.clone());
+            // lookup by &str first so existing nodes cost no allocation
+            let found = match nodes.get(content) {
+                Some(node) => (node.clone(), false),
+                None => {
+                    let node = Arc::new(match data {
+                        Some(data) => Node::with_data(data),
+                        None => Node::from_key(content),
+                    });
+                    nodes.insert(node.get_data_arc(), node.clone());
+                    ordered(Rank::NameIndex, || {
+                        self.name_index.write().unwrap()
+                    })
+                    .insert(node.get_data_arc());
+                    self.bump_generation();
                     (node, true)
                 }
-                Entry::Occupied(e) => (e.get().clone(), false),
-            }
+            };
+
+            drop(nodes);
+            held.record("nodes_held_us");
+            found
         };
 
         if is_new {
-            if let Some(tx) = &self.events_tx {
-                tx.send(GraphEvent::NodeAdded(content.to_owned())).map_err(
-                    |e| anyhow!("Failed to send NodeAdded event: {}", e),
-                )?;
-            }
+            self.emit_with(
+                GraphEvent::NodeAdded(content.to_owned()),
+                delivery,
+            )?;
         }
 
         Ok(node)
@@ -165,15 +1331,17 @@ impl Graph {
 
 #[cfg(test)]
 impl Graph {
-    pub fn new_without_events() -> Graph {
-        let root = Arc::new(Node::new("root"));
-        let mut map = HashMap::new();
-        map.insert(String::from("root"), root.clone());
+    /// Every edge in the edge index, sorted, None without one
+    pub fn indexed_edges(&self) -> Option<Vec<(String, String)>> {
+        let index = self.edge_index.as_ref()?.read().unwrap();
+        let mut edges: Vec<(String, String)> = index
+            .iter()
+            .flat_map(|(parent, children)| {
+                children.iter().map(|c| (parent.to_string(), c.to_string()))
+            })
+            .collect();
+        edges.sort();
 
-        Graph {
-            nodes: RwLock::new(map),
-            root: root,
-            events_tx: None,
-        }
+        Some(edges)
     }
 }
@@ -0,0 +1,102 @@
+#![cfg(test)]
+//! Every query API on the smallest graphs there are: just root, and the
+//! empty snapshot left by excluding it
+
+use std::collections::HashSet;
+
+use serde_json::{Value, json};
+
+use crate::graph::algo::strongly_connected;
+use crate::graph::core::Graph;
+use crate::graph::export::{JsonOptions, parse_adjacency_json};
+use crate::graph::snapshot::GraphSnapshot;
+use crate::graph::stats::GraphStats;
+
+fn names(list: &[&str]) -> HashSet<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_traversals_on_lone_root() {
+    let graph = Graph::new_without_events();
+
+    assert_eq!(graph.strongly_connected_components(), [["root"]]);
+    assert_eq!(graph.connected_components(), [["root"]]);
+    assert_eq!(graph.find_cycle(), None);
+    assert!(!graph.has_cycle());
+    assert_eq!(graph.bfs_levels("root"), [["root"]]);
+    assert_eq!(graph.shortest_path("root", "root").unwrap(), ["root"]);
+    assert_eq!(graph.shortest_path("root", "missing"), None);
+    assert_eq!(graph.reachable_from_root_count(), 1);
+    assert_eq!(graph.within_hops(&graph.get_root(), 3).len(), 1);
+
+    let root = names(&["root"]);
+    assert!(graph.edges_between(&root, &root).is_empty());
+    assert_eq!(graph.search_prefix("", 10), ["root"]);
+    assert!(graph.audit_duplicate_edges().is_empty());
+    assert_eq!(graph.remove_self_loops(), 0);
+    assert!(!graph.contains_edge("root", "root"));
+}
+
+#[test]
+fn test_stats_on_lone_root() {
+    let graph = Graph::new_without_events();
+
+    assert_eq!(
+        graph.stats(),
+        GraphStats {
+            node_count: 1,
+            num_components: 1,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_exports_on_lone_root() {
+    let graph = Graph::new_without_events();
+
+    let value: Value = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+    assert_eq!(value, json!({"nodes": ["root"], "edges": []}));
+
+    let adjacency = graph.to_adjacency_json();
+    assert_eq!(adjacency, r#"{"root":[]}"#);
+    assert_eq!(parse_adjacency_json(&adjacency).unwrap(), graph.snapshot());
+
+    assert_eq!(graph.to_ndjson().lines().count(), 1);
+    assert!(graph.to_dot().contains("\"root\""));
+    assert_eq!(graph.to_graphml().matches("<node ").count(), 1);
+    assert_eq!(graph.clone_structure().snapshot(), graph.snapshot());
+}
+
+#[test]
+fn test_exports_with_root_excluded_are_empty() {
+    let graph = Graph::new_without_events();
+    let opts = JsonOptions {
+        exclude_root: true,
+        ..Default::default()
+    };
+
+    assert_eq!(graph.snapshot_with(opts), GraphSnapshot::default());
+    assert_eq!(
+        strongly_connected(&GraphSnapshot::default()),
+        Vec::<Vec<String>>::new()
+    );
+
+    let value: Value =
+        serde_json::from_str(&graph.to_json_with(opts).unwrap()).unwrap();
+    assert_eq!(value, json!({"nodes": [], "edges": []}));
+    assert_eq!(graph.to_adjacency_json_with(opts), "{}");
+    assert_eq!(graph.to_ndjson_with(opts), "");
+
+    let cyto: Value =
+        serde_json::from_str(&graph.to_cytoscape_with(opts)).unwrap();
+    assert_eq!(cyto, json!({"elements": {"nodes": [], "edges": []}}));
+    assert_eq!(graph.to_graphml_with(opts).matches("<node ").count(), 0);
+    assert!(graph.to_dot_with(opts).starts_with("digraph"));
+
+    assert_eq!(
+        parse_adjacency_json("{}").unwrap(),
+        GraphSnapshot::default()
+    );
+}
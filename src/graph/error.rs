@@ -0,0 +1,56 @@
+use std::fmt;
+
+/// Failures of the graph API, match on these instead of error strings
+///
+/// add_edge still reports duplicates as Ok(AlreadyExisted) since
+/// re-encountering a link is routine while crawling, add_new_edge returns
+/// EdgeExists for callers that need the edge to be new.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    EdgeExists {
+        parent: String,
+        child: String,
+    },
+    NodeNotFound(String),
+
+    /// the event receiver was dropped, the graph itself is still updated
+    EventChannelClosed,
+
+    RootRemovalForbidden,
+
+    /// merge_nodes was asked to merge a node into itself
+    SelfMerge(String),
+
+    /// name longer than the graph's configured max_name_len, in bytes
+    NameTooLong {
+        len: usize,
+        max: usize,
+    },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::EdgeExists { parent, child } => {
+                write!(f, "Edge ({} -> {}) already exists", parent, child)
+            }
+            GraphError::NodeNotFound(name) => {
+                write!(f, "Node {} not found", name)
+            }
+            GraphError::EventChannelClosed => {
+                write!(f, "Event dropped: channel closed")
+            }
+            GraphError::RootRemovalForbidden => {
+                write!(f, "The root node can't be removed")
+            }
+            GraphError::SelfMerge(name) => {
+                write!(f, "Can't merge {} into itself", name)
+            }
+            GraphError::NameTooLong { len, max } => {
+                write!(f, "Node name of {} bytes exceeds limit of {}", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
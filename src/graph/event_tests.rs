@@ -4,7 +4,8 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::task;
 
-use crate::graph::core::{Graph, GraphEvent};
+use crate::graph::core::{EdgeOutcome, Graph, GraphEvent};
+use crate::graph::error::GraphError;
 
 // Helper function to collect events from the channel
 async fn collect_events(
@@ -192,15 +193,19 @@ async fn test_no_events_on_failed_edge() {
     // Try to add duplicate (should fail)
     let result = graph.add_edge("root", "child");
     assert!(match result {
-        Ok(false) => true,
+        Ok(EdgeOutcome::AlreadyExisted) => true,
         Err(_) => true,
-        Ok(true) => false,
+        Ok(EdgeOutcome::Inserted) => false,
     });
 
     let events = collect_events(&mut rx, 10, Duration::from_millis(100)).await;
 
-    // Should only have events from the first successful add_edge
-    assert_eq!(events.len(), 2); // NodeAdded + EdgeAdded
+    // No NodeAdded or EdgeAdded again, just the duplicate marker
+    assert_eq!(events.len(), 3); // NodeAdded + EdgeAdded + DuplicateEdge
+    assert_eq!(
+        events[2],
+        GraphEvent::DuplicateEdge("root".into(), "child".into())
+    );
 }
 
 #[tokio::test]
@@ -411,3 +416,200 @@ async fn test_event_order_consistency() {
     assert!(matches!(&events[4], GraphEvent::NodeAdded(n) if n == "C"));
     assert!(matches!(&events[5], GraphEvent::EdgeAdded(p, c) if p == "B" && c == "C"));
 }
+
+#[tokio::test]
+async fn test_edge_removed_events_for_self_loops() {
+    let (graph, mut rx) = Graph::new();
+
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "A").unwrap();
+    let _ = collect_events(&mut rx, 3, Duration::from_millis(100)).await;
+
+    assert_eq!(graph.remove_self_loops(), 1);
+
+    let events = collect_events(&mut rx, 10, Duration::from_millis(100)).await;
+    assert_eq!(events.len(), 1);
+
+    match &events[0] {
+        GraphEvent::EdgeRemoved(parent, child) => {
+            assert_eq!(parent, "A");
+            assert_eq!(child, "A");
+        }
+        _ => panic!("Expected EdgeRemoved event"),
+    }
+}
+
+#[test]
+fn test_closed_channel_is_typed_error() {
+    let (graph, rx) = Graph::new();
+    drop(rx);
+
+    let result = graph.add_edge("root", "child");
+    assert_eq!(result, Err(GraphError::EventChannelClosed));
+
+    // the node went in before its NodeAdded send failed
+    assert!(graph.contains("child"));
+}
+
+#[tokio::test]
+async fn test_apply_events_reproduces_graph() {
+    let (graph, mut rx) = Graph::new();
+
+    // same graph as test_events_match_graph_state, plus a removal
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("C", "C").unwrap();
+    graph.remove_self_loops();
+    graph.set_node_meta("A", "fetch_ms", 3).unwrap();
+
+    let events = collect_events(&mut rx, 20, Duration::from_millis(100)).await;
+    assert!(events.contains(&GraphEvent::EdgeRemoved("C".into(), "C".into())));
+
+    let (replayed, mut replayed_rx) = Graph::new();
+    replayed.apply_events(&events).unwrap();
+
+    let (mut expected, actual) = (graph.snapshot(), replayed.snapshot());
+    expected.meta.clear(); // NodeUpdated doesn't carry the values
+    assert_eq!(actual, expected);
+
+    // and applying emits the same structural events again
+    let reemitted =
+        collect_events(&mut replayed_rx, 20, Duration::from_millis(100)).await;
+    let structural =
+        |e: &&GraphEvent| !matches!(e, GraphEvent::NodeUpdated(_));
+    assert!(reemitted.iter().eq(events.iter().filter(structural)));
+}
+
+#[test]
+fn test_apply_events_reports_failing_event() {
+    let graph = Graph::new_without_events();
+    let events = [
+        GraphEvent::EdgeAdded("root".into(), "A".into()),
+        GraphEvent::NodeUpdated("missing".into()),
+        GraphEvent::EdgeAdded("root".into(), "B".into()),
+    ];
+
+    let err = graph.apply_events(&events).unwrap_err();
+    assert!(err.to_string().starts_with("event 1"), "{}", err);
+    assert_eq!(
+        err.downcast_ref::<GraphError>(),
+        Some(&GraphError::NodeNotFound("missing".into()))
+    );
+
+    // applied up to the failure, not beyond
+    assert!(graph.contains("A"));
+    assert!(!graph.contains("B"));
+}
+
+#[tokio::test]
+async fn test_merge_nodes_events_replay_to_same_graph() {
+    let (graph, mut rx) = Graph::new();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("B", "A").unwrap();
+    let before = collect_events(&mut rx, 20, Duration::from_millis(100)).await;
+
+    graph.merge_nodes("A", "B").unwrap();
+    let merge = collect_events(&mut rx, 20, Duration::from_millis(100)).await;
+    assert_eq!(
+        merge,
+        [
+            GraphEvent::EdgeRemoved("root".into(), "B".into()),
+            GraphEvent::EdgeRemoved("B".into(), "C".into()),
+            GraphEvent::EdgeRemoved("B".into(), "A".into()),
+            GraphEvent::NodeRemoved("B".into()),
+        ]
+    );
+
+    let replayed = Graph::new_without_events();
+    replayed.apply_events(&before).unwrap();
+    replayed.apply_events(&merge).unwrap();
+    assert_eq!(replayed.snapshot(), graph.snapshot());
+}
+
+#[tokio::test]
+async fn test_add_edges_emits_nodes_then_edges() {
+    let (graph, mut rx) = Graph::new();
+    graph.add_edge("root", "page").unwrap();
+    collect_events(&mut rx, 10, Duration::from_millis(50)).await;
+
+    graph.add_edges("page", &["A", "root", "B", "A"]).unwrap();
+    let events = collect_events(&mut rx, 10, Duration::from_millis(100)).await;
+    assert_eq!(
+        events,
+        [
+            GraphEvent::NodeAdded("A".into()),
+            GraphEvent::NodeAdded("B".into()),
+            GraphEvent::EdgeAdded("page".into(), "A".into()),
+            GraphEvent::EdgeAdded("page".into(), "root".into()),
+            GraphEvent::EdgeAdded("page".into(), "B".into()),
+            GraphEvent::DuplicateEdge("page".into(), "A".into()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_two_subscribers_see_same_events() {
+    let (graph, _rx) = Graph::new();
+    let mut first = graph.subscribe();
+    let mut second = graph.subscribe();
+
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+
+    let expected = vec![
+        GraphEvent::NodeAdded("A".to_string()),
+        GraphEvent::EdgeAdded("root".to_string(), "A".to_string()),
+        GraphEvent::NodeAdded("B".to_string()),
+        GraphEvent::EdgeAdded("A".to_string(), "B".to_string()),
+    ];
+    for rx in [&mut first, &mut second] {
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(events, expected);
+    }
+
+    // a late subscriber only gets what comes after it
+    let mut late = graph.subscribe();
+    graph.add_edge("B", "C").unwrap();
+    assert_eq!(
+        late.try_recv().unwrap(),
+        GraphEvent::NodeAdded("C".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_second_add_edge_emits_one_duplicate() {
+    let (graph, mut rx) = Graph::new();
+    graph.add_edge("root", "child").unwrap();
+    collect_events(&mut rx, 10, Duration::from_millis(50)).await;
+
+    assert!(!graph.add_edge("root", "child").unwrap().is_inserted());
+    let events = collect_events(&mut rx, 10, Duration::from_millis(100)).await;
+    assert_eq!(
+        events,
+        [GraphEvent::DuplicateEdge("root".into(), "child".into())]
+    );
+}
+
+#[test]
+fn test_graph_without_events_adds_edges() {
+    let graph = Graph::builder()
+        .root_name("index")
+        .build_without_events();
+
+    assert!(graph.add_edge("index", "A").unwrap().is_inserted());
+    assert!(graph.add_edge("A", "B").unwrap().is_inserted());
+    assert!(!graph.add_edge("A", "B").unwrap().is_inserted());
+    graph.add_edges("B", &["C", "index"]).unwrap();
+
+    assert_eq!(graph.get_root().get_data(), "index");
+    assert_eq!(graph.node_count(), 4);
+    assert!(graph.contains_edge("B", "index"));
+}
@@ -0,0 +1,365 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use serde_json::{Value, json};
+use tracing::debug;
+
+use crate::graph::core::Graph;
+use crate::graph::replay::LogEntry;
+use crate::graph::snapshot::GraphSnapshot;
+
+/// Export options, `pretty` only affects json, the rest apply to every
+/// format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonOptions {
+    /// indented output for debugging, compact otherwise
+    pub pretty: bool,
+
+    /// leave out the synthetic root and every edge touching it
+    pub exclude_root: bool,
+
+    /// list nodes in `discovered_at` order instead of by name, so a
+    /// client can play the crawl back in order
+    pub order_by_discovery: bool,
+}
+
+impl Graph {
+    /// The snapshot every exporter serializes, with `opts` applied
+    pub fn snapshot_with(&self, opts: JsonOptions) -> GraphSnapshot {
+        let mut snapshot = self.snapshot();
+        if opts.exclude_root {
+            snapshot = snapshot.without_node(self.get_root().get_data());
+        }
+        if opts.order_by_discovery {
+            snapshot.order_by_discovery();
+        }
+
+        snapshot
+    }
+
+    /// `{"nodes": [...], "edges": [["parent", "child"], ...]}`, compact
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        self.to_json_with(JsonOptions::default())
+    }
+
+    /// The returned string's `len()` is the body size in bytes
+    pub fn to_json_with(&self, opts: JsonOptions) -> anyhow::Result<String> {
+        let snapshot = self.snapshot_with(opts);
+
+        let json = if opts.pretty {
+            serde_json::to_string_pretty(&snapshot)?
+        } else {
+            serde_json::to_string(&snapshot)?
+        };
+
+        debug!(
+            bytes = json.len(),
+            nodes = snapshot.node_count(),
+            edges = snapshot.edge_count(),
+            "Serialized graph to json"
+        );
+
+        Ok(json)
+    }
+}
+
+/// Quotes a node name as a DOT identifier, escaping quotes, backslashes
+/// and newlines
+pub fn dot_quote(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 2);
+    out.push('"');
+    for c in name.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `"name";` statement, as emitted per node by `to_dot`
+pub fn dot_node(name: &str) -> String {
+    format!("{};", dot_quote(name))
+}
+
+/// `"parent" -> "child";` statement, as emitted per edge by `to_dot`
+pub fn dot_edge(parent: &str, child: &str) -> String {
+    format!("{} -> {};", dot_quote(parent), dot_quote(child))
+}
+
+impl Graph {
+    /// Graphviz `digraph`, one statement per node then one per edge
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(JsonOptions::default())
+    }
+
+    pub fn to_dot_with(&self, opts: JsonOptions) -> String {
+        let snapshot = self.snapshot_with(opts);
+
+        let mut dot = String::from("digraph {\n");
+        for node in &snapshot.nodes {
+            let _ = writeln!(dot, "    {}", dot_node(node));
+        }
+        for (parent, child) in &snapshot.edges {
+            let _ = writeln!(dot, "    {}", dot_edge(parent, child));
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+/// Escapes the five XML special characters for text and attribute values
+pub fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// GraphML `attr.type` of a metadata value, non-scalars are written as
+/// their JSON text
+fn graphml_type(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "double",
+        _ => "string",
+    }
+}
+
+impl Graph {
+    /// GraphML document with node ids being the node names
+    ///
+    /// Node metadata becomes `<data>` elements, with a `<key>` per
+    /// metadata key typed by its values, `string` if they disagree.
+    pub fn to_graphml(&self) -> String {
+        self.to_graphml_with(JsonOptions::default())
+    }
+
+    pub fn to_graphml_with(&self, opts: JsonOptions) -> String {
+        let snapshot = self.snapshot_with(opts);
+
+        let mut types: BTreeMap<&str, &str> = BTreeMap::new();
+        for (key, value) in snapshot.meta.values().flatten() {
+            let ty = graphml_type(value);
+            types
+                .entry(key)
+                .and_modify(|t| {
+                    if *t != ty {
+                        *t = "string";
+                    }
+                })
+                .or_insert(ty);
+        }
+        let key_ids: BTreeMap<&str, String> = types
+            .keys()
+            .enumerate()
+            .map(|(i, key)| (*key, format!("d{}", i)))
+            .collect();
+
+        let mut xml = String::from(concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+            "\n",
+        ));
+        for (key, ty) in &types {
+            let _ = writeln!(
+                xml,
+                r#"  <key id="{}" for="node" attr.name="{}" attr.type="{}"/>"#,
+                key_ids[key],
+                xml_escape(key),
+                ty
+            );
+        }
+        xml.push_str("  <graph edgedefault=\"directed\">\n");
+
+        for node in &snapshot.nodes {
+            let Some(meta) = snapshot.meta.get(node) else {
+                let _ =
+                    writeln!(xml, r#"    <node id="{}"/>"#, xml_escape(node));
+                continue;
+            };
+
+            let _ = writeln!(xml, r#"    <node id="{}">"#, xml_escape(node));
+            for (key, value) in meta {
+                let text = match value {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                let _ = writeln!(
+                    xml,
+                    r#"      <data key="{}">{}</data>"#,
+                    key_ids[key.as_str()],
+                    xml_escape(&text)
+                );
+            }
+            xml.push_str("    </node>\n");
+        }
+        for (parent, child) in &snapshot.edges {
+            let _ = writeln!(
+                xml,
+                r#"    <edge source="{}" target="{}"/>"#,
+                xml_escape(parent),
+                xml_escape(child)
+            );
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+
+        xml
+    }
+
+    /// Cytoscape.js `{"elements": {"nodes": [...], "edges": [...]}}`
+    pub fn to_cytoscape(&self) -> String {
+        self.to_cytoscape_with(JsonOptions::default())
+    }
+
+    pub fn to_cytoscape_with(&self, opts: JsonOptions) -> String {
+        let snapshot = self.snapshot_with(opts);
+
+        let nodes: Vec<_> = snapshot
+            .nodes
+            .iter()
+            .map(|id| json!({"data": {"id": id}}))
+            .collect();
+        let edges: Vec<_> = snapshot
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, (source, target))| {
+                json!({"data": {
+                    "id": format!("e{}", i),
+                    "source": source,
+                    "target": target,
+                }})
+            })
+            .collect();
+
+        json!({"elements": {"nodes": nodes, "edges": edges}}).to_string()
+    }
+}
+
+/// Quotes a CSV field per RFC 4180 when it holds a comma, quote, CR or
+/// LF, doubling any quotes, other fields are returned as they are
+pub fn csv_quote(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl Graph {
+    /// `parent,child` header and one row per edge, sorted, CRLF line
+    /// endings as in RFC 4180
+    pub fn to_csv(&self) -> String {
+        self.to_csv_with(JsonOptions::default())
+    }
+
+    pub fn to_csv_with(&self, opts: JsonOptions) -> String {
+        let snapshot = self.snapshot_with(opts);
+
+        let mut csv = String::from("parent,child\r\n");
+        for (parent, child) in &snapshot.edges {
+            let _ =
+                write!(csv, "{},{}\r\n", csv_quote(parent), csv_quote(child));
+        }
+
+        csv
+    }
+}
+
+impl Graph {
+    /// Event log that rebuilds this graph with `Graph::replay`, one json
+    /// object per line: nodes, then edges, then metadata
+    pub fn to_ndjson(&self) -> String {
+        self.to_ndjson_with(JsonOptions::default())
+    }
+
+    pub fn to_ndjson_with(&self, opts: JsonOptions) -> String {
+        let snapshot = self.snapshot_with(opts);
+
+        let nodes = snapshot
+            .nodes
+            .into_iter()
+            .map(|id| LogEntry::NodeAdded { id });
+        let edges = snapshot
+            .edges
+            .into_iter()
+            .map(|(source, target)| LogEntry::EdgeAdded { source, target });
+        let meta = snapshot
+            .meta
+            .into_iter()
+            .map(|(id, meta)| LogEntry::NodeUpdated { id, meta });
+
+        let mut out = String::new();
+        for entry in nodes.chain(edges).chain(meta) {
+            // plain strings and json values, can't fail
+            out.push_str(&serde_json::to_string(&entry).unwrap());
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+impl Graph {
+    /// `{"root": ["A", "B"], "A": ["C"], ...}`, every node mapped to its
+    /// sorted child names, an empty list for leaves
+    pub fn to_adjacency_json(&self) -> String {
+        self.to_adjacency_json_with(JsonOptions::default())
+    }
+
+    pub fn to_adjacency_json_with(&self, opts: JsonOptions) -> String {
+        let snapshot = self.snapshot_with(opts);
+
+        let mut adjacency: BTreeMap<&str, Vec<&str>> = snapshot
+            .nodes
+            .iter()
+            .map(|n| (n.as_str(), Vec::new()))
+            .collect();
+        for (parent, child) in &snapshot.edges {
+            adjacency.entry(parent).or_default().push(child);
+        }
+
+        if opts.pretty {
+            serde_json::to_string_pretty(&adjacency).unwrap()
+        } else {
+            serde_json::to_string(&adjacency).unwrap()
+        }
+    }
+}
+
+/// Snapshot of a `to_adjacency_json` document, children that aren't keys
+/// themselves still become nodes
+pub fn parse_adjacency_json(json: &str) -> anyhow::Result<GraphSnapshot> {
+    let adjacency: BTreeMap<String, Vec<String>> = serde_json::from_str(json)?;
+
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for (parent, children) in adjacency {
+        for child in children {
+            nodes.insert(child.clone());
+            edges.insert((parent.clone(), child));
+        }
+        nodes.insert(parent);
+    }
+
+    Ok(GraphSnapshot {
+        nodes: nodes.into_iter().collect(),
+        edges: edges.into_iter().collect(),
+        meta: BTreeMap::new(),
+    })
+}
@@ -0,0 +1,628 @@
+#![cfg(test)]
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use crate::graph::core::{Graph, GraphEvent, Node};
+use crate::graph::export::{JsonOptions, parse_adjacency_json};
+use crate::graph::snapshot::{DISCOVERED_AT, GraphDiff, GraphSnapshot};
+
+fn diamond() -> Graph {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph
+}
+
+#[test]
+fn test_to_json_compact_shape() {
+    let graph = diamond();
+    let json = graph.to_json().unwrap();
+
+    assert!(!json.contains('\n'));
+
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "nodes": ["A", "B", "C", "root"],
+            "edges": [["A", "C"], ["B", "C"], ["root", "A"], ["root", "B"]],
+        })
+    );
+}
+
+#[test]
+fn test_to_json_roundtrips_inserted_structure() {
+    let inserted = [("root", "A"), ("A", "B"), ("B", "A"), ("Z", "A")];
+    let graph = Graph::new_without_events();
+    for (parent, child) in inserted {
+        graph.add_edge(parent, child).unwrap();
+    }
+
+    let json = graph.to_json().unwrap();
+    assert_eq!(graph.to_json().unwrap(), json, "stable across calls");
+
+    let snapshot: GraphSnapshot = serde_json::from_str(&json).unwrap();
+    let edges: BTreeSet<(String, String)> =
+        snapshot.edges.iter().cloned().collect();
+    let expected: BTreeSet<(String, String)> = inserted
+        .iter()
+        .map(|(p, c)| (p.to_string(), c.to_string()))
+        .collect();
+    assert_eq!(edges, expected);
+    assert_eq!(snapshot.nodes, ["A", "B", "Z", "root"]);
+
+    let (rebuilt, _rx) = Graph::from_snapshot(&snapshot).unwrap();
+    assert_eq!(rebuilt.to_json().unwrap(), json);
+}
+
+#[test]
+fn test_to_json_pretty_same_content() {
+    let graph = diamond();
+
+    let pretty = JsonOptions {
+        pretty: true,
+        ..Default::default()
+    };
+    let compact = graph.to_json_with(JsonOptions::default()).unwrap();
+    let pretty = graph.to_json_with(pretty).unwrap();
+
+    assert!(pretty.contains('\n'));
+    assert!(pretty.len() > compact.len());
+
+    let a: Value = serde_json::from_str(&compact).unwrap();
+    let b: Value = serde_json::from_str(&pretty).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_to_json_len_is_byte_length() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "Zürich").unwrap(); // multi-byte
+
+    let json = graph.to_json().unwrap();
+    let bytes = serde_json::to_vec(&graph.snapshot()).unwrap();
+
+    assert_eq!(json.len(), bytes.len());
+    assert!(json.len() > json.chars().count());
+}
+
+#[test]
+fn test_to_dot_counts_and_escaping() {
+    let graph = diamond();
+    graph.add_edge("C", "node/with\\special$chars").unwrap();
+    graph.add_edge("C", "say \"hi\"").unwrap();
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let statements: Vec<&str> = dot
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.ends_with(';'))
+        .collect();
+    let edges = statements.iter().filter(|l| l.contains(" -> ")).count();
+
+    assert_eq!(statements.len() - edges, graph.node_count());
+    assert_eq!(edges, 6);
+
+    assert!(dot.contains(r#""node/with\\special$chars";"#));
+    assert!(dot.contains(r#""C" -> "say \"hi\"";"#));
+}
+
+#[test]
+fn test_to_dot_skips_dead_children() {
+    let graph = diamond();
+    let before = graph.to_dot();
+
+    // a child that's gone by the time the export runs
+    let dropped = Arc::new(Node::new("gone"));
+    graph
+        .get_root()
+        .children_write()
+        .push(Arc::downgrade(&dropped));
+    drop(dropped);
+
+    assert_eq!(graph.to_dot(), before);
+    assert!(!graph.to_dot().contains("gone"));
+}
+
+#[test]
+fn test_to_json_includes_meta_when_present() {
+    let graph = diamond();
+    graph.set_node_meta("A", "fetch_ms", 7).unwrap();
+
+    let value: Value = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+    assert_eq!(value["meta"], json!({"A": {"fetch_ms": 7}}));
+}
+
+#[test]
+fn test_to_graphml_escapes_and_counts() {
+    let graph = diamond();
+    graph.add_edge("C", "a&b <\"c\">").unwrap();
+
+    let xml = graph.to_graphml();
+    assert!(xml.contains(r#"<graph edgedefault="directed">"#));
+    assert_eq!(xml.matches("<node ").count(), graph.node_count());
+    assert_eq!(xml.matches("<edge ").count(), 5);
+    assert!(xml.contains(r#"<node id="a&amp;b &lt;&quot;c&quot;&gt;"/>"#));
+}
+
+#[test]
+fn test_to_cytoscape_shape() {
+    let graph = diamond();
+
+    let value: Value = serde_json::from_str(&graph.to_cytoscape()).unwrap();
+    let nodes = value["elements"]["nodes"].as_array().unwrap();
+    let edges = value["elements"]["edges"].as_array().unwrap();
+
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(edges.len(), 4);
+    assert_eq!(nodes[0], json!({"data": {"id": "A"}}));
+    assert_eq!(
+        edges[0],
+        json!({"data": {"id": "e0", "source": "A", "target": "C"}})
+    );
+}
+
+#[test]
+fn test_exclude_root_in_every_format() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+
+    let opts = JsonOptions {
+        exclude_root: true,
+        ..Default::default()
+    };
+
+    let value: Value =
+        serde_json::from_str(&graph.to_json_with(opts).unwrap()).unwrap();
+    assert_eq!(value, json!({"nodes": ["A", "B"], "edges": [["A", "B"]]}));
+
+    let dot = graph.to_dot_with(opts);
+    assert!(!dot.contains("\"root\""));
+    assert!(dot.contains(r#""A" -> "B";"#));
+
+    let xml = graph.to_graphml_with(opts);
+    assert_eq!(xml.matches("<node ").count(), 2);
+    assert_eq!(xml.matches("<edge ").count(), 1);
+
+    let cyto: Value =
+        serde_json::from_str(&graph.to_cytoscape_with(opts)).unwrap();
+    assert_eq!(cyto["elements"]["nodes"].as_array().unwrap().len(), 2);
+
+    // the default still includes it
+    assert!(graph.to_dot().contains("\"root\""));
+}
+
+#[test]
+fn test_to_adjacency_json_shape() {
+    let graph = diamond();
+    graph.add_edge("C", "C").unwrap();
+    graph.get_or_create_node("isolated").unwrap();
+
+    let value: Value =
+        serde_json::from_str(&graph.to_adjacency_json()).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "root": ["A", "B"],
+            "A": ["C"],
+            "B": ["C"],
+            "C": ["C"],
+            "isolated": [],
+        })
+    );
+
+    let opts = JsonOptions {
+        exclude_root: true,
+        ..Default::default()
+    };
+    let value: Value =
+        serde_json::from_str(&graph.to_adjacency_json_with(opts)).unwrap();
+    assert!(value.get("root").is_none());
+}
+
+#[test]
+fn test_adjacency_json_roundtrip() {
+    let graph = diamond();
+    graph.add_edge("C", "root").unwrap();
+    graph.add_edge("B", "B").unwrap();
+    graph.get_or_create_node("isolated").unwrap();
+
+    let snapshot = parse_adjacency_json(&graph.to_adjacency_json()).unwrap();
+    assert_eq!(snapshot, graph.snapshot());
+
+    let (rebuilt, _rx) = Graph::from_snapshot(&snapshot).unwrap();
+    assert_eq!(rebuilt.to_adjacency_json(), graph.to_adjacency_json());
+
+    // children missing as keys still become nodes
+    let partial = parse_adjacency_json(r#"{"root":["A"]}"#).unwrap();
+    assert_eq!(partial.nodes, ["A", "root"]);
+    assert!(parse_adjacency_json(r#"{"root":"A"}"#).is_err());
+}
+
+#[test]
+fn test_order_by_discovery_sorts_stamped_nodes_first() {
+    let graph = diamond();
+    graph.set_node_meta("C", DISCOVERED_AT, 0).unwrap();
+    graph.set_node_meta("B", DISCOVERED_AT, 1).unwrap();
+    graph.set_node_meta("A", DISCOVERED_AT, 2).unwrap();
+
+    let opts = JsonOptions {
+        order_by_discovery: true,
+        exclude_root: true,
+        ..Default::default()
+    };
+    let value: Value =
+        serde_json::from_str(&graph.to_json_with(opts).unwrap()).unwrap();
+    assert_eq!(value["nodes"], json!(["C", "B", "A"]));
+
+    // unstamped nodes trail in name order, the default stays by name
+    graph.get_or_create_node("D").unwrap();
+    let ordered = graph.snapshot_with(JsonOptions {
+        order_by_discovery: true,
+        ..Default::default()
+    });
+    assert_eq!(ordered.nodes, ["C", "B", "A", "D", "root"]);
+    assert_eq!(graph.snapshot().nodes, ["A", "B", "C", "D", "root"]);
+}
+
+#[test]
+fn test_from_edges_builds_diamond() {
+    let (graph, mut rx) = Graph::from_edges(&[
+        ("root", "A"),
+        ("root", "B"),
+        ("A", "C"),
+        ("B", "C"),
+        ("A", "C"), // duplicate, skipped
+    ])
+    .unwrap();
+
+    assert_eq!(graph.snapshot(), diamond().snapshot());
+
+    let mut edge_events = 0;
+    while let Ok(event) = rx.try_recv() {
+        if let GraphEvent::EdgeAdded(..) = event {
+            edge_events += 1;
+        }
+    }
+    assert_eq!(edge_events, 4);
+}
+
+#[test]
+fn test_subgraph_of_diamond_to_depth_one() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "D").unwrap();
+    graph.add_edge("C", "D").unwrap();
+    graph.add_edge("C", "B").unwrap();
+
+    let (sub, _rx) = graph.subgraph("A", Some(1));
+    assert_eq!(sub.get_root().get_data(), "A");
+    assert_eq!(sub.node_count(), 3);
+    assert!(sub.get_node("root").is_none());
+    assert!(sub.get_node("D").is_none());
+    // edges among included nodes survive, not only the tree edges
+    assert!(sub.contains_edge("C", "B"));
+
+    let (full, _rx) = graph.subgraph("A", None);
+    assert_eq!(full.node_count(), 4);
+    assert_eq!(full.snapshot().edge_count(), 5);
+}
+
+#[test]
+fn test_save_to_path_roundtrips_diamond() {
+    let graph = diamond();
+    graph.set_node_attr("C", "kind", "article").unwrap();
+    let path = std::env::temp_dir().join("mycelia_saved_diamond.json");
+
+    graph.save_to_path(&path).unwrap();
+    let first = std::fs::read(&path).unwrap();
+    let (loaded, _rx) = Graph::load_from_path(&path).unwrap();
+    assert_eq!(loaded.get_root().get_data(), "root");
+    assert_eq!(loaded.snapshot(), graph.snapshot());
+
+    // saving the reloaded graph gives the same bytes
+    loaded.save_to_path(&path).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), first);
+
+    std::fs::write(&path, "{\"root\": ").unwrap();
+    let err = Graph::load_from_path(&path).unwrap_err();
+    assert!(err.to_string().starts_with("parsing"), "{}", err);
+
+    std::fs::remove_file(&path).unwrap();
+    let err = Graph::load_from_path(&path).unwrap_err();
+    assert!(err.to_string().starts_with("reading"), "{}", err);
+}
+
+/// Element name and unescaped attributes of every start tag, panicking
+/// unless `xml` is well-formed: balanced tags, quoted attributes, no bare
+/// `<` or `&` in text or attribute values
+fn parse_xml(xml: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    fn unescape(s: &str) -> String {
+        let mut out = String::new();
+        let mut rest = s;
+        while let Some(i) = rest.find('&') {
+            out.push_str(&rest[..i]);
+            let end = rest[i..].find(';').expect("unterminated entity") + i;
+            out.push(match &rest[i + 1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                other => panic!("unknown entity &{};", other),
+            });
+            rest = &rest[end + 1..];
+        }
+        out + rest
+    }
+
+    let mut elements = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut rest = xml.strip_prefix("<?xml").expect("xml declaration");
+    rest = &rest[rest.find("?>").unwrap() + 2..];
+
+    while let Some(start) = rest.find('<') {
+        unescape(&rest[..start]); // text may only hold known entities
+        let end = rest[start..].find('>').expect("unterminated tag") + start;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            assert_eq!(open.pop().as_deref(), Some(name), "mismatched tag");
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (name, mut attrs_src) = tag.split_once(' ').unwrap_or((tag, ""));
+        let mut attrs = BTreeMap::new();
+        while let Some((key, value)) = attrs_src.trim_start().split_once("=\"")
+        {
+            let close = value.find('"').expect("unquoted attribute");
+            assert!(!value[..close].contains('<'), "bare < in attribute");
+            attrs.insert(key.to_owned(), unescape(&value[..close]));
+            attrs_src = &value[close + 1..];
+        }
+        assert!(attrs_src.trim().is_empty(), "bad attributes {:?}", tag);
+
+        if !self_closing {
+            open.push(name.to_owned());
+        }
+        elements.push((name.to_owned(), attrs));
+    }
+    assert!(open.is_empty(), "unclosed {:?}", open);
+    assert!(rest.trim().is_empty());
+
+    elements
+}
+
+#[test]
+fn test_to_graphml_is_well_formed() {
+    let graph = diamond();
+    graph.add_edge("C", "a/b\\c$d & <e>").unwrap();
+    graph.set_node_meta("A", "fetch_ms", 3.5).unwrap();
+    graph.set_node_attr("C", "kind", "\"category\"").unwrap();
+
+    let elements = parse_xml(&graph.to_graphml());
+    let named = |name: &str| {
+        elements
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, attrs)| attrs)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(elements[0].0, "graphml");
+    assert_eq!(named("graph")[0]["edgedefault"], "directed");
+
+    let ids: BTreeSet<&str> =
+        named("node").iter().map(|a| a["id"].as_str()).collect();
+    assert_eq!(ids.len(), graph.node_count());
+    assert!(ids.contains("a/b\\c$d & <e>"));
+
+    let edges = named("edge");
+    assert_eq!(edges.len(), 5);
+    assert!(edges.iter().all(|e| ids.contains(e["source"].as_str())
+        && ids.contains(e["target"].as_str())));
+
+    let keys = named("key");
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0]["attr.name"], "fetch_ms");
+    assert_eq!(keys[0]["attr.type"], "double");
+    assert_eq!(keys[1]["attr.type"], "string");
+    assert_eq!(named("data").len(), 2);
+}
+
+/// Rows of an RFC 4180 document, quoted fields unescaped
+fn parse_csv(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {
+                chars.next();
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    assert!(
+        !quoted && field.is_empty() && row.is_empty(),
+        "unterminated"
+    );
+
+    rows
+}
+
+#[test]
+fn test_to_csv_quotes_and_roundtrips() {
+    let graph = diamond();
+    graph.add_edge("C", "Hello, \"World\"").unwrap();
+    graph.add_edge("Hello, \"World\"", "two\nlines").unwrap();
+
+    let csv = graph.to_csv();
+    assert!(csv.starts_with("parent,child\r\n"));
+    assert!(csv.contains("C,\"Hello, \"\"World\"\"\"\r\n"));
+
+    let rows = parse_csv(&csv);
+    assert_eq!(rows[0], ["parent", "child"]);
+    let edges: Vec<(String, String)> = rows[1..]
+        .iter()
+        .map(|row| (row[0].clone(), row[1].clone()))
+        .collect();
+    assert_eq!(edges, graph.snapshot().edges);
+}
+
+#[test]
+fn test_merge_unions_disjoint_chains() {
+    let (graph, mut rx) = Graph::new();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    let other = Graph::new_without_events();
+    other.add_edge("root", "X").unwrap();
+    other.add_edge("X", "Y").unwrap();
+    other.add_edge("Y", "A").unwrap();
+    other.add_edge("A", "B").unwrap();
+    other.set_node_attr("Y", "kind", "category").unwrap();
+    while rx.try_recv().is_ok() {}
+
+    assert_eq!(graph.merge(&other).unwrap(), 3);
+    // root, A, B plus X and Y, the roots merged into one
+    assert_eq!(graph.node_count(), 5);
+    assert!(graph.contains_edge("root", "X"));
+    assert!(graph.contains_edge("Y", "A"));
+    assert_eq!(
+        graph.get_node("Y").unwrap().get_attr("kind").unwrap(),
+        "category"
+    );
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, GraphEvent::NodeAdded(_)))
+            .count(),
+        2
+    );
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, GraphEvent::EdgeAdded(..)))
+            .count(),
+        3
+    );
+    assert!(
+        !events
+            .iter()
+            .any(|e| matches!(e, GraphEvent::DuplicateEdge(..)))
+    );
+
+    // merging again changes nothing
+    assert_eq!(graph.merge(&other).unwrap(), 0);
+    assert_eq!(graph.node_count(), 5);
+}
+
+#[test]
+fn test_diff_against_copy_with_one_more_edge() {
+    let graph = diamond();
+    let copy = graph.clone_structure();
+    assert!(graph.diff(&copy).is_empty());
+
+    copy.add_edge("C", "A").unwrap();
+    assert_eq!(
+        graph.diff(&copy),
+        GraphDiff {
+            added_edges: vec![("C".to_string(), "A".to_string())],
+            ..Default::default()
+        }
+    );
+
+    copy.add_edge("C", "D").unwrap();
+    let diff = copy.diff(&graph);
+    assert_eq!(diff.removed_nodes, ["D"]);
+    assert_eq!(diff.removed_edges.len(), 2);
+    assert!(diff.added_nodes.is_empty() && diff.added_edges.is_empty());
+}
+
+#[test]
+fn test_adjacency_json_while_edges_are_added() {
+    let graph = Arc::new(Graph::new_without_events());
+    let writer = {
+        let graph = graph.clone();
+        std::thread::spawn(move || {
+            for i in 0..5_000 {
+                graph.add_edge("root", &format!("n{}", i)).unwrap();
+            }
+        })
+    };
+
+    while !writer.is_finished() {
+        let value: Value =
+            serde_json::from_str(&graph.to_adjacency_json()).unwrap();
+        assert!(value["root"].is_array());
+    }
+    writer.join().unwrap();
+
+    let value: Value =
+        serde_json::from_str(&graph.to_adjacency_json()).unwrap();
+    assert_eq!(value["root"].as_array().unwrap().len(), 5_000);
+}
+
+#[test]
+fn test_copies_keep_root_and_settings() {
+    let graph = Graph::builder()
+        .root_name("index")
+        .max_name_len(8)
+        .edge_index(true)
+        .build_without_events();
+    graph.add_edge("index", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+
+    let copy = graph.clone_structure();
+    assert_eq!(copy.get_root().get_data(), "index");
+    assert_eq!(copy.max_name_len(), Some(8));
+    assert!(copy.indexed_edges().is_some());
+    assert!(!copy.contains("root"));
+    assert_eq!(copy.snapshot(), graph.snapshot());
+
+    let (sub, _rx) = graph.subgraph("A", None);
+    assert_eq!(sub.max_name_len(), Some(8));
+    assert_eq!(sub.clone_structure().get_root().get_data(), "A");
+
+    let (rebuilt, _rx) = Graph::builder()
+        .root_name("index")
+        .build_from_snapshot(&graph.snapshot())
+        .unwrap();
+    assert_eq!(rebuilt.node_count(), 3);
+    assert_eq!(rebuilt.get_root().get_children().len(), 1);
+
+    let (from_edges, _rx) = graph
+        .to_builder()
+        .build_from_edges(&[("index", "C"), ("C", "D")])
+        .unwrap();
+    assert_eq!(from_edges.node_count(), 3);
+}
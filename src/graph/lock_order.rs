@@ -0,0 +1,113 @@
+//! Lock acquisition order, checked in tests and with `lock-order-checks`
+//!
+//! A thread takes locks in increasing rank: the nodes map, the name index,
+//! a node's children, a node's parents, a node's edge weights, the edge
+//! index, a node's meta. Taking a lock of equal or lower rank than one it
+//! already holds panics, equal covers two children locks at once, which
+//! two threads could take in opposite order.
+//!
+//! add_edge never nests nodes and children at all, it releases the nodes
+//! lock before touching the parent's children.
+
+use std::ops::{Deref, DerefMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Rank {
+    Nodes,
+    NameIndex,
+    Children,
+    Parents,
+    Weights,
+    EdgeIndex,
+    Meta,
+}
+
+/// A lock guard that counts as held for the order check until dropped
+pub(crate) struct Ordered<G> {
+    // declared first so the lock is released before the rank is
+    guard: G,
+    _held: imp::Held,
+}
+
+impl<G: Deref> Deref for Ordered<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &G::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for Ordered<G> {
+    fn deref_mut(&mut self) -> &mut G::Target {
+        &mut self.guard
+    }
+}
+
+/// Checks `rank` against the locks this thread holds, then runs `acquire`
+pub(crate) fn ordered<G: Deref>(
+    rank: Rank,
+    acquire: impl FnOnce() -> G,
+) -> Ordered<G> {
+    let held = imp::Held::enter(rank);
+    Ordered {
+        guard: acquire(),
+        _held: held,
+    }
+}
+
+#[cfg(any(test, feature = "lock-order-checks"))]
+mod imp {
+    use std::cell::RefCell;
+
+    use super::Rank;
+
+    thread_local! {
+        static HELD: RefCell<Vec<Rank>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(crate) struct Held(Rank);
+
+    impl Held {
+        pub(crate) fn enter(rank: Rank) -> Held {
+            HELD.with(|held| {
+                let mut held = held.borrow_mut();
+                if let Some(&top) = held.iter().max()
+                    && top >= rank
+                {
+                    drop(held);
+                    panic!(
+                        "Lock order violated: {:?} taken while holding {:?}",
+                        rank, top
+                    );
+                }
+                held.push(rank);
+            });
+            Held(rank)
+        }
+    }
+
+    impl Drop for Held {
+        fn drop(&mut self) {
+            HELD.with(|held| {
+                let mut held = held.borrow_mut();
+                if let Some(i) = held.iter().rposition(|&r| r == self.0) {
+                    held.remove(i);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(not(any(test, feature = "lock-order-checks")))]
+mod imp {
+    use super::Rank;
+
+    pub(crate) struct Held;
+
+    impl Held {
+        #[inline(always)]
+        pub(crate) fn enter(_rank: Rank) -> Held {
+            Held
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(test)]
+use std::sync::RwLock;
+
+use crate::graph::core::Graph;
+use crate::graph::lock_order::{Rank, ordered};
+
+#[test]
+#[should_panic(expected = "Lock order violated")]
+fn test_children_before_nodes_panics() {
+    let graph = Graph::new_without_events();
+    let root = graph.get_root();
+
+    let _children = root.children_read();
+    let _nodes = graph.nodes_read();
+}
+
+#[test]
+#[should_panic(expected = "Lock order violated")]
+fn test_two_children_locks_panic() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    let a = graph.get_node("A").unwrap();
+
+    let root = graph.get_root();
+    let _root_children = root.children_write();
+    let _a_children = a.children_read();
+}
+
+#[test]
+fn test_increasing_order_and_release() {
+    let graph = Graph::new_without_events();
+    let root = graph.get_root();
+    let (index, meta) = (RwLock::new(()), RwLock::new(()));
+
+    {
+        let _nodes = graph.nodes_read();
+        let _index = ordered(Rank::NameIndex, || index.write().unwrap());
+        let _children = root.children_read();
+        let _meta = ordered(Rank::Meta, || meta.read().unwrap());
+    }
+
+    // dropped guards no longer count as held
+    let children = root.children_read();
+    drop(children);
+    let _nodes = graph.nodes_write();
+}
+
+#[test]
+fn test_graph_api_keeps_lock_order() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.set_node_meta("A", "fetch_ms", 1.0).unwrap();
+
+    graph.search_prefix("A", 10);
+    graph.snapshot();
+    graph.stats();
+    graph.reachable_from_root_count();
+    graph.audit_duplicate_edges();
+    graph.remove_self_loops();
+    graph.to_json().unwrap();
+}
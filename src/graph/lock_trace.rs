@@ -0,0 +1,47 @@
+//! Lock hold-time instrumentation, compiled out without `lock-tracing`
+//!
+//! add_edge and get_or_create_node open a trace-level span each; the fields
+//! below are recorded on the current span when the lock guard is dropped.
+
+#[cfg(feature = "lock-tracing")]
+mod imp {
+    use std::time::Instant;
+
+    pub(crate) struct LockTimer(Instant);
+
+    impl LockTimer {
+        pub(crate) fn start() -> LockTimer {
+            LockTimer(Instant::now())
+        }
+
+        /// Records microseconds since `start` into `field`
+        pub(crate) fn record(self, field: &'static str) {
+            let us = self.0.elapsed().as_micros() as u64;
+            tracing::Span::current().record(field, us);
+        }
+    }
+
+    pub(crate) fn record_duplicate(duplicate: bool) {
+        tracing::Span::current().record("duplicate", duplicate);
+    }
+}
+
+#[cfg(not(feature = "lock-tracing"))]
+mod imp {
+    pub(crate) struct LockTimer;
+
+    impl LockTimer {
+        #[inline(always)]
+        pub(crate) fn start() -> LockTimer {
+            LockTimer
+        }
+
+        #[inline(always)]
+        pub(crate) fn record(self, _field: &'static str) {}
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_duplicate(_duplicate: bool) {}
+}
+
+pub(crate) use imp::{LockTimer, record_duplicate};
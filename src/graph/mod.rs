@@ -1,6 +1,22 @@
+pub mod algo;
 pub mod core;
+pub mod error;
+pub mod export;
+mod lock_order;
+mod lock_trace;
+pub mod shard;
+pub mod replay;
+pub mod snapshot;
+pub mod stats;
 pub mod sync_tests;
 pub mod async_tests;
 pub mod tokio_tests;
 pub mod aggressive_async_tests;
 pub mod event_tests;
+pub mod export_tests;
+pub mod algo_tests;
+pub mod shard_tests;
+pub mod stats_tests;
+pub mod lock_order_tests;
+pub mod replay_tests;
+pub mod edge_case_tests;
@@ -0,0 +1,173 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::graph::core::{Graph, GraphEvent};
+use crate::graph::error::GraphError;
+
+/// One line of an NDJSON event log
+///
+/// Same shape as the visualizer's json event frames, so a recorded socket
+/// stream replays as well as a `to_ndjson` export. Logs from before the
+/// types were camelCase (`NodeAdded`, ...) still parse.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LogEntry {
+    #[serde(alias = "NodeAdded")]
+    NodeAdded {
+        id: String,
+    },
+    #[serde(alias = "EdgeAdded")]
+    EdgeAdded {
+        source: String,
+        target: String,
+    },
+    #[serde(alias = "EdgeRemoved")]
+    EdgeRemoved {
+        source: String,
+        target: String,
+    },
+    #[serde(alias = "NodeRemoved")]
+    NodeRemoved {
+        id: String,
+    },
+    #[serde(alias = "NodeUpdated")]
+    NodeUpdated {
+        id: String,
+        #[serde(default)]
+        meta: BTreeMap<String, Value>,
+    },
+
+    /// any other frame type, e.g. Stats, replays as a no-op
+    #[serde(other)]
+    Other,
+}
+
+/// Line counts from `Graph::replay`, no-op entries count as neither
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub applied: usize,
+    pub skipped: usize,
+}
+
+impl Graph {
+    /// Applies an NDJSON event log in order
+    ///
+    /// Lines that don't parse or can't be applied are logged and skipped,
+    /// only a read error ends the replay early.
+    pub fn replay(&self, reader: impl BufRead) -> io::Result<ReplayReport> {
+        let mut report = ReplayReport::default();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = match serde_json::from_str::<LogEntry>(&line) {
+                Ok(LogEntry::Other) => continue,
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!(line = i + 1, "Skipping malformed event: {}", e);
+                    report.skipped += 1;
+                    continue;
+                }
+            };
+
+            match self.apply(&entry) {
+                Ok(()) => report.applied += 1,
+                Err(e) => {
+                    warn!(line = i + 1, "Skipping event: {}", e);
+                    report.skipped += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Applies one log entry, re-adding what exists already is a no-op
+    pub fn apply(&self, entry: &LogEntry) -> Result<(), GraphError> {
+        match entry {
+            LogEntry::NodeAdded { id } => {
+                self.apply_event(&GraphEvent::NodeAdded(id.clone()))
+            }
+            LogEntry::EdgeAdded { source, target } => self.apply_event(
+                &GraphEvent::EdgeAdded(source.clone(), target.clone()),
+            ),
+            LogEntry::EdgeRemoved { source, target } => self.apply_event(
+                &GraphEvent::EdgeRemoved(source.clone(), target.clone()),
+            ),
+            LogEntry::NodeRemoved { id } => {
+                self.apply_event(&GraphEvent::NodeRemoved(id.clone()))
+            }
+            LogEntry::NodeUpdated { id, meta } => {
+                for (key, value) in meta {
+                    self.set_node_meta(id, key, value.clone())?;
+                }
+                Ok(())
+            }
+            LogEntry::Other => Ok(()),
+        }
+    }
+
+    /// Applies events in order, the inverse of emitting them, stops at the
+    /// first one that fails
+    ///
+    /// A graph fed every event another one emitted ends up with the same
+    /// nodes and edges, though not the metadata, NodeUpdated only names
+    /// the node.
+    pub fn apply_events(&self, events: &[GraphEvent]) -> anyhow::Result<()> {
+        for (i, event) in events.iter().enumerate() {
+            self.apply_event(event).map_err(|e| {
+                anyhow::Error::new(e)
+                    .context(format!("event {}: {:?}", i, event))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// One event of `apply_events`, re-adding what exists already and
+    /// removing an absent edge or node are no-ops
+    ///
+    /// NodeRemoved only unlinks the node, the EdgeRemoved events emitted
+    /// before it are what take its edges away.
+    pub fn apply_event(&self, event: &GraphEvent) -> Result<(), GraphError> {
+        match event {
+            GraphEvent::NodeAdded(name) => {
+                self.get_or_create_node(name)?;
+            }
+            GraphEvent::EdgeAdded(parent, child) => {
+                self.add_edge(parent, child)?;
+            }
+            GraphEvent::EdgeRemoved(parent, child) => {
+                self.remove_edge(parent, child)?;
+            }
+            GraphEvent::NodeRemoved(name) => {
+                if let Some(node) = self.get_node(name) {
+                    if Arc::ptr_eq(&node, &self.get_root()) {
+                        return Err(GraphError::RootRemovalForbidden);
+                    }
+                    self.unlink_node(&node);
+                }
+            }
+            GraphEvent::NodeUpdated(name) => {
+                if !self.contains(name) {
+                    return Err(GraphError::NodeNotFound(name.clone()));
+                }
+            }
+            GraphEvent::DuplicateEdge(..)
+            | GraphEvent::Stats(_)
+            | GraphEvent::CrawlProgress { .. } => (),
+        }
+
+        Ok(())
+    }
+}
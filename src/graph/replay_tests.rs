@@ -0,0 +1,100 @@
+#![cfg(test)]
+use std::io::Cursor;
+
+use crate::graph::core::Graph;
+use crate::graph::error::GraphError;
+use crate::graph::replay::{LogEntry, ReplayReport};
+
+#[test]
+fn test_ndjson_roundtrip_rebuilds_structure() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "A").unwrap();
+    graph.add_edge("B", "B").unwrap();
+    graph.get_or_create_node("isolated").unwrap();
+    graph.set_node_meta("A", "fetch_ms", 12.5).unwrap();
+
+    let log = graph.to_ndjson();
+    assert_eq!(log.lines().count(), 4 + 4 + 1);
+
+    let replayed = Graph::new_without_events();
+    let report = replayed.replay(Cursor::new(log)).unwrap();
+
+    assert_eq!(
+        report,
+        ReplayReport {
+            applied: 9,
+            skipped: 0
+        }
+    );
+    assert_eq!(replayed.snapshot(), graph.snapshot());
+}
+
+#[test]
+fn test_replay_applies_in_order_and_skips_bad_lines() {
+    let log = r#"{"type":"nodeAdded","id":"A"}
+{"type":"edgeAdded","source":"root","target":"A"}
+not json at all
+{"type":"edgeAdded","source":"root"}
+
+{"type":"edgeAdded","source":"A","target":"B"}
+{"type":"stats","stats":{},"seq":7}
+{"type":"edgeRemoved","source":"root","target":"A"}
+{"type":"edgeRemoved","source":"root","target":"missing"}
+{"type":"nodeUpdated","id":"B","meta":{"fetch_ms":3}}
+"#;
+
+    let graph = Graph::new_without_events();
+    let report = graph.replay(Cursor::new(log)).unwrap();
+
+    assert_eq!(
+        report,
+        ReplayReport {
+            applied: 5,
+            skipped: 3
+        }
+    );
+
+    let snapshot = graph.snapshot();
+    assert_eq!(snapshot.nodes, ["A", "B", "root"]);
+    assert_eq!(snapshot.edges, [("A".into(), "B".into())]);
+    assert_eq!(snapshot.meta["B"]["fetch_ms"], 3);
+}
+
+#[test]
+fn test_replay_reads_logs_with_pascal_case_types() {
+    let log = r#"{"type":"EdgeAdded","source":"root","target":"A"}
+{"type":"NodeUpdated","id":"A","meta":{"fetch_ms":3}}
+"#;
+
+    let graph = Graph::new_without_events();
+    let report = graph.replay(Cursor::new(log)).unwrap();
+
+    assert_eq!(report.applied, 2);
+    assert_eq!(graph.snapshot().meta["A"]["fetch_ms"], 3);
+}
+
+#[test]
+fn test_apply_edge_removed() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+
+    let removed = LogEntry::EdgeRemoved {
+        source: "root".into(),
+        target: "A".into(),
+    };
+    graph.apply(&removed).unwrap();
+    // already gone, still fine
+    graph.apply(&removed).unwrap();
+    assert!(graph.get_root().get_children().is_empty());
+
+    let unknown = LogEntry::EdgeRemoved {
+        source: "nope".into(),
+        target: "A".into(),
+    };
+    assert_eq!(
+        graph.apply(&unknown),
+        Err(GraphError::NodeNotFound("nope".into()))
+    );
+}
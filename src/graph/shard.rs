@@ -0,0 +1,52 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::graph::{core::Graph, snapshot::GraphSnapshot};
+
+/// Shard owning `name` among `n`, same scheme as examples/sharded-db-server
+///
+/// DefaultHasher::new() is unkeyed so this is stable across processes of
+/// the same build, but not guaranteed across Rust versions.
+pub fn shard_of(name: &str, n: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish() as usize % n
+}
+
+impl Graph {
+    /// Hash-partitions the graph into `n` self-contained shards
+    ///
+    /// Each node is owned by exactly one shard (`shard_of`) and listed in
+    /// its `nodes`, with its metadata. A shard's `edges` are every edge
+    /// with at least one owned endpoint, so edges crossing shards appear
+    /// in both. An edge endpoint missing from `nodes` is such a foreign
+    /// node, owned by `shard_of(name, n)`.
+    ///
+    /// Panics if `n` is 0.
+    pub fn partition(&self, n: usize) -> Vec<GraphSnapshot> {
+        assert!(n > 0, "cannot partition into 0 shards");
+
+        let snapshot = self.snapshot();
+        let mut shards = vec![GraphSnapshot::default(); n];
+
+        // snapshot is sorted, pushing in order keeps every shard sorted
+        for name in snapshot.nodes {
+            let shard = &mut shards[shard_of(&name, n)];
+            if let Some(meta) = snapshot.meta.get(&name) {
+                shard.meta.insert(name.clone(), meta.clone());
+            }
+            shard.nodes.push(name);
+        }
+
+        for (parent, child) in snapshot.edges {
+            let from = shard_of(&parent, n);
+            let to = shard_of(&child, n);
+
+            if from != to {
+                shards[to].edges.push((parent.clone(), child.clone()));
+            }
+            shards[from].edges.push((parent, child));
+        }
+
+        shards
+    }
+}
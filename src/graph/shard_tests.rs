@@ -0,0 +1,77 @@
+#![cfg(test)]
+use std::collections::BTreeSet;
+
+use crate::graph::core::Graph;
+use crate::graph::shard::shard_of;
+
+fn sample() -> Graph {
+    let graph = Graph::new_without_events();
+    for i in 0..50 {
+        graph.add_edge("root", &format!("n{}", i)).unwrap();
+        graph
+            .add_edge(&format!("n{}", i), &format!("n{}", (i * 7) % 50))
+            .unwrap();
+    }
+    graph.set_node_meta("n3", "fetch_ms", 1).unwrap();
+    graph
+}
+
+#[test]
+fn test_partition_reassembles_full_graph() {
+    let graph = sample();
+    let full = graph.snapshot();
+    let shards = graph.partition(4);
+
+    assert_eq!(shards.len(), 4);
+
+    let nodes: BTreeSet<String> =
+        shards.iter().flat_map(|s| s.nodes.clone()).collect();
+    let edges: BTreeSet<(String, String)> =
+        shards.iter().flat_map(|s| s.edges.clone()).collect();
+
+    assert_eq!(nodes.into_iter().collect::<Vec<_>>(), full.nodes);
+    assert_eq!(edges.into_iter().collect::<Vec<_>>(), full.edges);
+
+    // each node owned once, metadata travels with it
+    let owned: usize = shards.iter().map(|s| s.node_count()).sum();
+    assert_eq!(owned, full.node_count());
+    assert!(shards[shard_of("n3", 4)].meta.contains_key("n3"));
+}
+
+#[test]
+fn test_partition_cross_shard_edges_in_both() {
+    let graph = sample();
+    let shards = graph.partition(4);
+
+    for (i, shard) in shards.iter().enumerate() {
+        for node in &shard.nodes {
+            assert_eq!(shard_of(node, 4), i);
+        }
+
+        // every edge touches an owned node, the other end may be foreign
+        for (parent, child) in &shard.edges {
+            assert!(shard_of(parent, 4) == i || shard_of(child, 4) == i);
+
+            for end in [parent, child] {
+                let owner = shard_of(end, 4);
+                assert!(
+                    shards[owner]
+                        .edges
+                        .contains(&(parent.clone(), child.clone()))
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_partition_single_shard_is_snapshot() {
+    let graph = sample();
+    assert_eq!(graph.partition(1), vec![graph.snapshot()]);
+}
+
+#[test]
+#[should_panic]
+fn test_partition_zero_shards_panics() {
+    Graph::new_without_events().partition(0);
+}
@@ -0,0 +1,377 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::graph::core::{Graph, GraphBuilder, GraphEvent, Node};
+use crate::graph::error::GraphError;
+
+/// Metadata key holding the order a node was discovered in, a number
+/// counting up from 0, see `CrawlConfig::record_discovery`
+pub const DISCOVERED_AT: &str = "discovered_at";
+
+/// Owned, lock-free copy of the graph structure
+///
+/// Nodes and edges are sorted so snapshots of equal graphs compare and
+/// serialize identically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    pub nodes: Vec<String>,
+    pub edges: Vec<(String, String)>,
+
+    /// metadata of the nodes that have any, keyed by node name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl GraphSnapshot {
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Reorders nodes by their `discovered_at` metadata, nodes without
+    /// one go last in name order, edges stay sorted
+    pub fn order_by_discovery(&mut self) {
+        let meta = &self.meta;
+        let discovered_at = |name: &String| {
+            meta.get(name)
+                .and_then(|m| m.get(DISCOVERED_AT))
+                .and_then(Value::as_u64)
+        };
+
+        self.nodes
+            .sort_by(|a, b| match (discovered_at(a), discovered_at(b)) {
+                (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.cmp(b)),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.cmp(b),
+            });
+    }
+
+    /// Drops `name` along with its metadata and every edge touching it
+    pub fn without_node(mut self, name: &str) -> GraphSnapshot {
+        self.nodes.retain(|n| n != name);
+        self.edges.retain(|(p, c)| p != name && c != name);
+        self.meta.remove(name);
+        self
+    }
+}
+
+/// What changes from one graph to another, see `Graph::diff`
+///
+/// Every list is sorted, so diffs of the same pair of graphs compare equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<String>,
+    pub removed_nodes: Vec<String>,
+    pub added_edges: Vec<(String, String)>,
+    pub removed_edges: Vec<(String, String)>,
+}
+
+impl GraphDiff {
+    /// True if both graphs have the same nodes and edges
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+/// Items of sorted `to` missing from sorted `from`
+fn missing<T: Ord + Clone>(from: &[T], to: &[T]) -> Vec<T> {
+    to.iter()
+        .filter(|item| from.binary_search(item).is_err())
+        .cloned()
+        .collect()
+}
+
+impl Graph {
+    /// Nodes and edges `other` has on top of this graph (added) or lacks
+    /// (removed), by name, metadata isn't compared
+    ///
+    /// Each graph is snapshotted on its own, so their locks are never
+    /// held together.
+    pub fn diff(&self, other: &Graph) -> GraphDiff {
+        let before = self.snapshot();
+        let after = other.snapshot();
+
+        GraphDiff {
+            added_nodes: missing(&before.nodes, &after.nodes),
+            removed_nodes: missing(&after.nodes, &before.nodes),
+            added_edges: missing(&before.edges, &after.edges),
+            removed_edges: missing(&after.edges, &before.edges),
+        }
+    }
+}
+
+impl Graph {
+    /// WARN: acquires nodes lock, then each node's children lock in turn
+    ///
+    /// Never holds both at once, so it can't deadlock against add_edge.
+    pub fn snapshot(&self) -> GraphSnapshot {
+        let nodes: Vec<Arc<Node>> =
+            self.nodes_read().values().cloned().collect();
+
+        let mut names = Vec::with_capacity(nodes.len());
+        let mut edges = Vec::new();
+        let mut meta = BTreeMap::new();
+
+        for node in &nodes {
+            names.push(node.get_data().to_owned());
+
+            let node_meta = node.get_all_meta();
+            if !node_meta.is_empty() {
+                meta.insert(node.get_data().to_owned(), node_meta);
+            }
+
+            node.with_children(|children| {
+                edges.extend(children.map(|child| {
+                    (node.get_data().to_owned(), child.get_data().to_owned())
+                }));
+            });
+        }
+
+        // a child added after the node list was copied isn't in it, keep
+        // edges among listed nodes only so every edge joins two of them
+        let listed: HashSet<&str> = names.iter().map(String::as_str).collect();
+        edges.retain(|(_, child)| listed.contains(child.as_str()));
+
+        names.sort();
+        edges.sort();
+
+        GraphSnapshot {
+            nodes: names,
+            edges,
+            meta,
+        }
+    }
+}
+
+/// On-disk form of `Graph::save_to_path`, the snapshot plus the root's
+/// name since a snapshot alone doesn't say which node is the root
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedGraph {
+    root: String,
+
+    #[serde(flatten)]
+    graph: GraphSnapshot,
+}
+
+impl Graph {
+    /// Writes the graph as pretty-printed JSON, sorted like `snapshot` so
+    /// saves of equal graphs are byte-identical and diff line by line
+    ///
+    /// Goes through a sibling tmp file renamed over `path`, so a crash
+    /// mid-write never leaves a truncated file behind.
+    pub fn save_to_path(&self, path: &Path) -> anyhow::Result<()> {
+        let saved = SavedGraph {
+            root: self.get_root().get_data().to_owned(),
+            graph: self.snapshot(),
+        };
+        let tmp = path.with_extension("tmp");
+
+        let json = serde_json::to_vec_pretty(&saved)?;
+        fs::write(&tmp, &json)
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("renaming to {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Rebuilds a graph written by `save_to_path`, under the same root
+    pub fn load_from_path(
+        path: &Path,
+    ) -> anyhow::Result<(Graph, mpsc::UnboundedReceiver<GraphEvent>)> {
+        let bytes = fs::read(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let saved: SavedGraph = serde_json::from_slice(&bytes)
+            .with_context(|| format!("parsing {}", path.display()))?;
+
+        let (graph, rx) = Graph::builder().root_name(saved.root).build();
+        graph
+            .load_snapshot(&saved.graph)
+            .with_context(|| format!("loading {}", path.display()))?;
+
+        Ok((graph, rx))
+    }
+
+    /// GraphBuilder::build_from_snapshot with the default settings, so
+    /// rooted at `"root"`
+    pub fn from_snapshot(
+        snapshot: &GraphSnapshot,
+    ) -> Result<(Graph, mpsc::UnboundedReceiver<GraphEvent>), GraphError> {
+        Graph::builder().build_from_snapshot(snapshot)
+    }
+
+    /// GraphBuilder::build_from_edges with the default settings, so
+    /// rooted at `"root"`
+    pub fn from_edges(
+        edges: &[(&str, &str)],
+    ) -> Result<(Graph, mpsc::UnboundedReceiver<GraphEvent>), GraphError> {
+        Graph::builder().build_from_edges(edges)
+    }
+
+    /// Event-less deep copy: fresh nodes and weak refs, same names, edges
+    /// (shared children and cycles included) and metadata
+    ///
+    /// Handy as an immutable baseline to diff a later crawl against.
+    ///
+    /// The copy keeps this graph's root and builder settings.
+    pub fn clone_structure(&self) -> Graph {
+        let graph = self.to_builder().build_without_events();
+        graph
+            .load_snapshot(&self.snapshot())
+            .expect("event-less graph can't fail to load a snapshot");
+
+        graph
+    }
+
+    /// Adds `other`'s nodes, edges and metadata to this graph, returns how
+    /// many edges were new
+    ///
+    /// Nodes are matched by name, so roots of the same name become one.
+    /// Only new nodes and edges emit events, edges already here are
+    /// skipped rather than reported as duplicates, and metadata keys a
+    /// node already has keep this graph's value. `other` is snapshotted
+    /// first, so the two graphs' locks are never held together.
+    pub fn merge(&self, other: &Graph) -> Result<usize, GraphError> {
+        let snapshot = other.snapshot();
+
+        for name in &snapshot.nodes {
+            self.get_or_create_node(name)?;
+        }
+
+        let mut added = 0;
+        for (parent, child) in &snapshot.edges {
+            if !self.contains_edge(parent, child)
+                && self.add_edge(parent, child)?.is_inserted()
+            {
+                added += 1;
+            }
+        }
+
+        for (name, meta) in &snapshot.meta {
+            let Some(node) = self.get_node(name) else {
+                continue;
+            };
+            for (key, value) in meta {
+                if node.get_meta(key).is_none() {
+                    self.set_node_meta(name, key, value.clone())?;
+                }
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Copy of `root_name` and its descendants at most `max_depth` edges
+    /// down (all of them if None), rooted at `root_name` itself
+    ///
+    /// Edges and metadata among the copied nodes are kept, a missing
+    /// `root_name` gives a graph of just that root.
+    pub fn subgraph(
+        &self,
+        root_name: &str,
+        max_depth: Option<usize>,
+    ) -> (Graph, mpsc::UnboundedReceiver<GraphEvent>) {
+        let nodes = match self.get_node(root_name) {
+            Some(start) => {
+                self.within_hops(&start, max_depth.unwrap_or(usize::MAX))
+            }
+            None => Vec::new(),
+        };
+        let included: HashSet<&str> =
+            nodes.iter().map(|n| n.get_data()).collect();
+
+        let mut snapshot = GraphSnapshot::default();
+        for node in &nodes {
+            let name = node.get_data();
+            snapshot.nodes.push(name.to_owned());
+
+            let meta = node.get_all_meta();
+            if !meta.is_empty() {
+                snapshot.meta.insert(name.to_owned(), meta);
+            }
+
+            node.with_children(|children| {
+                snapshot.edges.extend(
+                    children
+                        .filter(|c| included.contains(c.get_data()))
+                        .map(|c| (name.to_owned(), c.get_data().to_owned())),
+                );
+            });
+        }
+        snapshot.nodes.sort();
+        snapshot.edges.sort();
+
+        let (graph, rx) = self.to_builder().root_name(root_name).build();
+        graph
+            .load_snapshot(&snapshot)
+            .expect("names from a graph can't fail to load");
+
+        (graph, rx)
+    }
+
+    pub(crate) fn load_snapshot(
+        &self,
+        snapshot: &GraphSnapshot,
+    ) -> Result<(), GraphError> {
+        for name in &snapshot.nodes {
+            self.get_or_create_node(name)?;
+        }
+        for (parent, child) in &snapshot.edges {
+            self.add_edge(parent, child)?;
+        }
+        for (name, meta) in &snapshot.meta {
+            for (key, value) in meta {
+                self.set_node_meta(name, key, value.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GraphBuilder {
+    /// Rebuilds a graph from a snapshot, nodes first so isolated ones
+    /// survive, then edges and metadata
+    ///
+    /// A snapshot doesn't say which node was the root, set `root_name`
+    /// for one that wasn't rooted at `"root"`.
+    pub fn build_from_snapshot(
+        self,
+        snapshot: &GraphSnapshot,
+    ) -> Result<(Graph, mpsc::UnboundedReceiver<GraphEvent>), GraphError> {
+        let (graph, rx) = self.build();
+        graph.load_snapshot(snapshot)?;
+
+        Ok((graph, rx))
+    }
+
+    /// Fresh graph with each `(parent, child)` applied through add_edge in
+    /// order, a repeated edge is skipped like any duplicate
+    pub fn build_from_edges(
+        self,
+        edges: &[(&str, &str)],
+    ) -> Result<(Graph, mpsc::UnboundedReceiver<GraphEvent>), GraphError> {
+        let (graph, rx) = self.build();
+        for (parent, child) in edges {
+            graph.add_edge(parent, child)?;
+        }
+
+        Ok((graph, rx))
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{runtime::Handle, sync::oneshot, task::JoinHandle};
+use tracing::debug;
+
+use crate::graph::algo::strongly_connected;
+use crate::graph::core::{Graph, GraphEvent};
+
+/// Aggregate metrics of the graph, all taken from one snapshot
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct GraphStats {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub max_out_degree: usize,
+    pub num_self_loops: usize,
+
+    /// weakly connected, i.e. ignoring edge direction
+    pub num_components: usize,
+    pub has_cycle: bool,
+}
+
+impl Graph {
+    /// WARN: acquires nodes lock, then each node's children lock in turn
+    pub fn stats(&self) -> GraphStats {
+        let snapshot = self.snapshot();
+        let ids: HashMap<&str, usize> = snapshot
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.as_str(), i))
+            .collect();
+
+        let mut out_degree = vec![0; snapshot.nodes.len()];
+        let mut components = UnionFind::new(snapshot.nodes.len());
+        let mut num_self_loops = 0;
+
+        for (parent, child) in &snapshot.edges {
+            let (Some(&p), Some(&c)) =
+                (ids.get(parent.as_str()), ids.get(child.as_str()))
+            else {
+                continue;
+            };
+            out_degree[p] += 1;
+            if p == c {
+                num_self_loops += 1;
+            }
+            components.union(p, c);
+        }
+
+        let has_cycle = num_self_loops > 0
+            || strongly_connected(&snapshot).iter().any(|c| c.len() > 1);
+
+        GraphStats {
+            node_count: snapshot.node_count(),
+            edge_count: snapshot.edge_count(),
+            max_out_degree: out_degree.into_iter().max().unwrap_or(0),
+            num_self_loops,
+            num_components: components.count(),
+            has_cycle,
+        }
+    }
+
+    /// One-line `key=value` summary for logs, from one snapshot:
+    /// `nodes=4 edges=4 leaves=1 cyclic=false depth=2`
+    ///
+    /// leaves are nodes without children, depth is the furthest BFS
+    /// distance from root over the nodes root reaches.
+    pub fn summary(&self) -> String {
+        let snapshot = self.snapshot();
+        let ids: HashMap<&str, usize> = snapshot
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.as_str(), i))
+            .collect();
+
+        let mut adj = vec![Vec::new(); snapshot.nodes.len()];
+        let mut has_self_loop = false;
+        for (parent, child) in &snapshot.edges {
+            let (Some(&p), Some(&c)) =
+                (ids.get(parent.as_str()), ids.get(child.as_str()))
+            else {
+                continue;
+            };
+            adj[p].push(c);
+            has_self_loop |= p == c;
+        }
+        let leaves = adj.iter().filter(|kids| kids.is_empty()).count();
+
+        let mut depths = vec![None; snapshot.nodes.len()];
+        let mut depth = 0;
+        if let Some(&root) = ids.get(self.get_root().get_data()) {
+            depths[root] = Some(0);
+            let mut queue = VecDeque::from([root]);
+            while let Some(node) = queue.pop_front() {
+                let next = depths[node].unwrap() + 1;
+                for &child in &adj[node] {
+                    if depths[child].is_none() {
+                        depths[child] = Some(next);
+                        depth = depth.max(next);
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+
+        let cyclic = has_self_loop
+            || strongly_connected(&snapshot).iter().any(|c| c.len() > 1);
+
+        format!(
+            "nodes={} edges={} leaves={} cyclic={} depth={}",
+            snapshot.node_count(),
+            snapshot.edge_count(),
+            leaves,
+            cyclic,
+            depth
+        )
+    }
+
+    /// Spawns a task on `handle` sending a `GraphEvent::Stats` every
+    /// `interval`, the first one right away
+    ///
+    /// Only holds the graph weakly, so it ends by itself once the graph is
+    /// dropped or the event receiver is gone, as well as on
+    /// `MetricsTask::stop` or when the MetricsTask is dropped.
+    pub fn start_metrics_task(
+        self: &Arc<Self>,
+        interval: Duration,
+        handle: &Handle,
+    ) -> MetricsTask {
+        let graph = Arc::downgrade(self);
+        let (stop_tx, stop_rx) = oneshot::channel();
+
+        let task = handle.spawn(metrics_loop(graph, interval, stop_rx));
+
+        MetricsTask {
+            stop: Some(stop_tx),
+            task,
+        }
+    }
+}
+
+async fn metrics_loop(
+    graph: Weak<Graph>,
+    interval: Duration,
+    mut stop: oneshot::Receiver<()>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        tokio::select! {
+            _ = &mut stop => break,
+            _ = ticker.tick() => {}
+        }
+
+        let Some(graph) = graph.upgrade() else {
+            break;
+        };
+        if graph.emit(GraphEvent::Stats(graph.stats())).is_err() {
+            break;
+        }
+    }
+
+    debug!("Metrics task stopped");
+}
+
+/// Handle of the task started by `start_metrics_task`
+#[derive(Debug)]
+pub struct MetricsTask {
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl MetricsTask {
+    /// Signals the task and waits for it to finish
+    pub async fn stop(mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        let _ = (&mut self.task).await;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parent[a] = b;
+    }
+
+    fn count(&mut self) -> usize {
+        (0..self.parent.len())
+            .filter(|&x| self.find(x) == x)
+            .count()
+    }
+}
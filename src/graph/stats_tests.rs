@@ -0,0 +1,162 @@
+#![cfg(test)]
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+
+use crate::graph::core::{Graph, GraphEvent};
+use crate::graph::stats::GraphStats;
+
+#[test]
+fn test_stats_on_diamond() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+
+    assert_eq!(
+        graph.stats(),
+        GraphStats {
+            node_count: 4,
+            edge_count: 4,
+            max_out_degree: 2,
+            num_self_loops: 0,
+            num_components: 1,
+            has_cycle: false,
+        }
+    );
+}
+
+#[test]
+fn test_stats_cycles_loops_and_islands() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "A").unwrap();
+    graph.add_edge("X", "X").unwrap(); // island with a self-loop
+    graph.get_or_create_node("lonely").unwrap();
+
+    let stats = graph.stats();
+    assert_eq!(stats.num_self_loops, 1);
+    assert_eq!(stats.num_components, 3);
+    assert!(stats.has_cycle);
+
+    let graph = Graph::new_without_events();
+    assert_eq!(graph.stats().num_components, 1); // just root
+    assert!(!graph.stats().has_cycle);
+}
+
+#[test]
+fn test_stats_while_edges_are_added() {
+    let graph = Arc::new(Graph::new_without_events());
+    let writer = {
+        let graph = graph.clone();
+        std::thread::spawn(move || {
+            for i in 0..5_000 {
+                graph.add_edge("root", &format!("n{}", i)).unwrap();
+            }
+        })
+    };
+
+    while !writer.is_finished() {
+        let stats = graph.stats();
+        assert!(stats.edge_count < stats.node_count);
+    }
+    writer.join().unwrap();
+
+    assert_eq!(graph.stats().max_out_degree, 5_000);
+}
+
+#[test]
+fn test_summary_while_edges_are_added() {
+    let graph = Arc::new(Graph::new_without_events());
+    let writer = {
+        let graph = graph.clone();
+        std::thread::spawn(move || {
+            for i in 0..5_000 {
+                graph.add_edge(&format!("n{}", i), "root").unwrap();
+            }
+        })
+    };
+
+    while !writer.is_finished() {
+        assert!(graph.summary().starts_with("nodes="));
+    }
+    writer.join().unwrap();
+
+    assert!(graph.summary().contains(" edges=5000 "));
+}
+
+fn count_stats(
+    rx: &mut tokio::sync::mpsc::UnboundedReceiver<GraphEvent>,
+) -> usize {
+    let mut count = 0;
+    while let Ok(event) = rx.try_recv() {
+        if matches!(event, GraphEvent::Stats(_)) {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_metrics_task_cadence_and_stop() {
+    let (graph, mut rx) = Graph::new();
+    let graph = Arc::new(graph);
+    graph.add_edge("root", "A").unwrap();
+    let _ = count_stats(&mut rx); // just the add_edge events
+
+    let task =
+        graph.start_metrics_task(Duration::from_millis(50), &Handle::current());
+
+    // ticks at 0, 50, ..., 250
+    tokio::time::sleep(Duration::from_millis(275)).await;
+    let ticks = count_stats(&mut rx);
+    assert!((5..=7).contains(&ticks), "{} ticks", ticks);
+
+    task.stop().await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(count_stats(&mut rx), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_metrics_task_ends_with_graph() {
+    let (graph, _rx) = Graph::new();
+    let graph = Arc::new(graph);
+
+    let task =
+        graph.start_metrics_task(Duration::from_millis(10), &Handle::current());
+    tokio::time::sleep(Duration::from_millis(25)).await;
+    assert!(!task.is_finished());
+
+    drop(graph);
+    tokio::time::sleep(Duration::from_millis(25)).await;
+    assert!(task.is_finished());
+}
+
+#[test]
+fn test_summary_format() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    assert_eq!(
+        graph.summary(),
+        "nodes=4 edges=4 leaves=1 cyclic=false depth=2"
+    );
+
+    graph.add_edge("C", "A").unwrap();
+    graph.add_edge("island", "D").unwrap();
+    assert_eq!(
+        graph.summary(),
+        "nodes=6 edges=6 leaves=1 cyclic=true depth=2"
+    );
+
+    let lone = Graph::new_without_events();
+    assert_eq!(
+        lone.summary(),
+        "nodes=1 edges=0 leaves=1 cyclic=false depth=0"
+    );
+}
@@ -1,7 +1,8 @@
 #![cfg(test)]
 use std::sync::Arc;
 
-use crate::graph::core::{Graph, Node};
+use crate::graph::core::{EdgeOutcome, Graph, GraphEvent, Node, NodeData};
+use crate::graph::error::GraphError;
 
 #[test]
 fn test_create_graph() {
@@ -141,8 +142,16 @@ fn test_duplicate_children_rejected() {
     let res1 = graph.add_edge("root", "child");
     let res2 = graph.add_edge("root", "child");
 
-    assert!(matches!(res1, Ok(true)), "Should succeed in adding first edge");
-    assert!(matches!(res2, Ok(false)), "Should reject duplicate edge");
+    assert_eq!(
+        res1,
+        Ok(EdgeOutcome::Inserted),
+        "Should succeed in adding first edge"
+    );
+    assert_eq!(
+        res2,
+        Ok(EdgeOutcome::AlreadyExisted),
+        "Should reject duplicate edge"
+    );
 
     let root = graph.get_node("root").expect("Root should exist in graph");
     assert_eq!(root.get_children().len(), 1);
@@ -288,3 +297,684 @@ fn test_graph_query_methods() {
 
     assert_eq!(graph.node_count(), 2);
 }
+
+#[test]
+fn test_get_data_arc_shares_allocation() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "child").unwrap();
+
+    let child = graph.get_node("child").unwrap();
+    let a = child.get_data_arc();
+    let b = child.get_data_arc();
+
+    assert_eq!(&*a, "child");
+    assert!(Arc::ptr_eq(&a, &b));
+
+    // map key and node data are the same allocation
+    let guard = graph.nodes.read().unwrap();
+    let (key, _) = guard.get_key_value("child").unwrap();
+    assert!(Arc::ptr_eq(key, &a));
+}
+
+#[test]
+fn test_remove_self_loops() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "root").unwrap();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+
+    assert_eq!(graph.remove_self_loops(), 2);
+    assert_eq!(graph.remove_self_loops(), 0);
+
+    let root = graph.get_node("root").unwrap();
+    let a = graph.get_node("A").unwrap();
+    assert_eq!(root.get_children().len(), 1);
+    assert_eq!(a.get_children().len(), 1);
+    assert_eq!(a.get_children()[0].get_data(), "B");
+
+    // nodes themselves stay
+    assert_eq!(graph.node_count(), 3);
+}
+
+#[test]
+fn test_audit_duplicate_edges_clean_graph() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "A").unwrap();
+
+    assert!(graph.audit_duplicate_edges().is_empty());
+}
+
+#[test]
+fn test_audit_duplicate_edges_reports_phantoms() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+
+    // bypass add_edge dedup to simulate a broken insert path
+    let root = graph.get_root();
+    let a = graph.get_node("A").unwrap();
+    root.children.write().unwrap().push(Arc::downgrade(&a));
+    root.children.write().unwrap().push(Arc::downgrade(&a));
+
+    assert_eq!(
+        graph.audit_duplicate_edges(),
+        vec![("root".to_owned(), "A".to_owned(), 3)]
+    );
+
+    // dead refs are tombstones, not duplicates
+    drop(a);
+    graph.nodes.write().unwrap().remove("A");
+    assert!(graph.audit_duplicate_edges().is_empty());
+}
+
+#[test]
+fn test_set_node_meta() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+
+    graph.set_node_meta("A", "fetch_ms", 42).unwrap();
+    graph.set_node_meta("A", "fetch_ms", 43).unwrap();
+
+    let a = graph.get_node("A").unwrap();
+    assert_eq!(a.get_meta("fetch_ms"), Some(43.into()));
+    assert_eq!(a.get_all_meta().len(), 1);
+    assert!(a.get_meta("missing").is_none());
+
+    assert_eq!(
+        graph.set_node_meta("nonexistent", "k", 1),
+        Err(GraphError::NodeNotFound("nonexistent".into()))
+    );
+}
+
+#[test]
+fn test_clone_structure_is_deep_copy() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "shared").unwrap();
+    graph.add_edge("B", "shared").unwrap();
+    graph.add_edge("shared", "A").unwrap(); // cycle
+    graph.get_or_create_node("island").unwrap();
+
+    let clone = graph.clone_structure();
+    assert_eq!(clone.snapshot(), graph.snapshot());
+
+    // fresh nodes, with the cycle closing over the clone's own A
+    let a = clone.get_node("A").unwrap();
+    assert!(!Arc::ptr_eq(&a, &graph.get_node("A").unwrap()));
+    let shared = a.get_children().pop().unwrap();
+    assert!(Arc::ptr_eq(&shared.get_children()[0], &a));
+
+    // and independent from then on
+    clone.add_edge("A", "only_in_clone").unwrap();
+    assert!(!graph.contains("only_in_clone"));
+}
+
+#[test]
+fn test_search_prefix() {
+    let graph = Graph::new_without_events();
+    for name in ["Rust", "Rustacean", "Ruby", "rust_lower", "Go", "Rusty"] {
+        graph.add_edge("root", name).unwrap();
+    }
+
+    assert_eq!(
+        graph.search_prefix("Rust", 10),
+        ["Rust", "Rustacean", "Rusty"]
+    );
+    assert_eq!(graph.search_prefix("Rust", 2), ["Rust", "Rustacean"]);
+    assert_eq!(graph.search_prefix("Ru", 10).len(), 4);
+    assert!(graph.search_prefix("Zig", 10).is_empty());
+    assert!(graph.search_prefix("Rust", 0).is_empty());
+
+    // duplicates don't double-index, "" matches everything
+    graph.add_edge("root", "Rust").unwrap();
+    assert_eq!(graph.search_prefix("", 100).len(), graph.node_count());
+}
+
+#[test]
+fn test_search_index_consistent_under_concurrency() {
+    let graph = Arc::new(Graph::new_without_events());
+
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let graph = graph.clone();
+            std::thread::spawn(move || {
+                for i in 0..200 {
+                    // overlapping names across threads
+                    let name = format!("n_{}", (t * 100 + i) % 500);
+                    graph.add_edge("root", &name).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(graph.search_prefix("n_", usize::MAX).len(), 500);
+    assert_eq!(graph.search_prefix("", usize::MAX).len(), graph.node_count());
+}
+
+#[test]
+fn test_max_name_len_rejects_before_inserting() {
+    let (graph, mut rx) = Graph::builder().max_name_len(8).build();
+
+    assert!(graph.add_edge("root", "12345678").unwrap().is_inserted());
+    assert_eq!(
+        graph.add_edge("root", "123456789"),
+        Err(GraphError::NameTooLong { len: 9, max: 8 })
+    );
+    assert_eq!(
+        graph.add_edge("123456789", "root"),
+        Err(GraphError::NameTooLong { len: 9, max: 8 })
+    );
+
+    assert!(!graph.contains("123456789"));
+    assert_eq!(graph.node_count(), 2);
+
+    // only the successful edge produced events
+    let mut events = 0;
+    while rx.try_recv().is_ok() {
+        events += 1;
+    }
+    assert_eq!(events, 2);
+}
+
+#[test]
+fn test_names_unlimited_by_default() {
+    let graph = Graph::new_without_events();
+    let long = "x".repeat(1 << 16);
+
+    assert!(graph.add_edge("root", &long).unwrap().is_inserted());
+    assert!(graph.add_edge("root", "").unwrap().is_inserted());
+}
+
+#[test]
+fn test_with_children_matches_get_children() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("root", "C").unwrap();
+
+    let root = graph.get_root();
+    let owned: Vec<String> = root
+        .get_children()
+        .iter()
+        .map(|c| c.get_data().to_owned())
+        .collect();
+    let borrowed: Vec<String> = root.with_children(|children| {
+        children.map(|c| c.get_data().to_owned()).collect()
+    });
+    assert_eq!(borrowed, owned);
+    assert_eq!(borrowed, ["A", "B", "C"]);
+
+    // dead refs are skipped like in get_children
+    graph.nodes.write().unwrap().remove("B");
+    assert_eq!(root.with_children(|children| children.count()), 2);
+
+    // can stop early without visiting the rest
+    let first = root.with_children(|mut children| children.next());
+    assert_eq!(first.unwrap().get_data(), "A");
+}
+
+#[test]
+fn test_remove_edge() {
+    let (graph, mut rx) = Graph::new();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    while rx.try_recv().is_ok() {}
+
+    assert_eq!(graph.remove_edge("root", "A"), Ok(true));
+    assert_eq!(graph.remove_edge("root", "A"), Ok(false));
+    assert_eq!(
+        graph.remove_edge("root", "missing"),
+        Err(GraphError::NodeNotFound("missing".into()))
+    );
+
+    // the node itself stays
+    assert!(graph.contains("A"));
+    let children = graph.get_root().get_children();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].get_data(), "B");
+
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        GraphEvent::EdgeRemoved("root".into(), "A".into())
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_contains_edge_with_and_without_index() {
+    let (indexed, _rx) = Graph::builder().edge_index(true).build();
+    let plain = Graph::new_without_events();
+
+    for graph in [&indexed, &plain] {
+        graph.add_edge("root", "A").unwrap();
+        graph.add_edge("A", "A").unwrap();
+        graph.add_edge("A", "B").unwrap();
+
+        assert!(graph.contains_edge("root", "A"));
+        assert!(graph.contains_edge("A", "A"));
+        assert!(!graph.contains_edge("A", "root"));
+        assert!(!graph.contains_edge("missing", "A"));
+
+        graph.remove_edge("A", "B").unwrap();
+        graph.remove_self_loops();
+        assert!(!graph.contains_edge("A", "B"));
+        assert!(!graph.contains_edge("A", "A"));
+    }
+
+    assert_eq!(
+        indexed.indexed_edges().unwrap(),
+        [("root".to_string(), "A".to_string())]
+    );
+    assert_eq!(plain.indexed_edges(), None);
+}
+
+#[test]
+fn test_nodes_dedup_by_name_in_hash_set() {
+    use std::collections::HashSet;
+
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("root", "B").unwrap();
+
+    // only the immutable name is hashed, the inner locks don't matter
+    #[allow(clippy::mutable_key_type)]
+    let mut set: HashSet<Arc<Node>> = HashSet::new();
+    set.extend(graph.get_root().get_children());
+    set.extend(graph.get_node("A").unwrap().get_children());
+    set.insert(graph.get_node("A").unwrap());
+    assert_eq!(set.len(), 2);
+
+    // a detached node with a taken name counts as the same one
+    assert!(set.contains(&Arc::new(Node::new("B"))));
+    assert!(!set.contains(&Arc::new(Node::new("C"))));
+
+    // add_edge still dedups children by identity: a same-named stranger
+    // among root's children doesn't block the real "C"
+    let stranger = Arc::new(Node::new("C"));
+    graph.get_root().children_write().push(Arc::downgrade(&stranger));
+    assert!(graph.add_edge("root", "C").unwrap().is_inserted());
+    assert!(!graph.add_edge("root", "C").unwrap().is_inserted());
+}
+
+fn edges_of(graph: &Graph) -> Vec<(String, String)> {
+    graph.snapshot().edges
+}
+
+fn edge(parent: &str, child: &str) -> (String, String) {
+    (parent.to_string(), child.to_string())
+}
+
+#[test]
+fn test_merge_nodes_redirects_parents_and_unions_children() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "Linux").unwrap();
+    graph.add_edge("root", "Linux_(kernel)").unwrap();
+    graph.add_edge("Unix", "Linux_(kernel)").unwrap();
+    graph.add_edge("Linux", "GNU").unwrap();
+    graph.add_edge("Linux_(kernel)", "GNU").unwrap(); // shared child
+    graph.add_edge("Linux_(kernel)", "Torvalds").unwrap();
+    graph.set_node_meta("Linux_(kernel)", "fetch_ms", 1).unwrap();
+
+    graph.merge_nodes("Linux", "Linux_(kernel)").unwrap();
+
+    assert!(!graph.contains("Linux_(kernel)"));
+    assert!(graph.search_prefix("Linux_", 10).is_empty());
+    assert_eq!(
+        edges_of(&graph),
+        [
+            edge("Linux", "GNU"),
+            edge("Linux", "Torvalds"),
+            edge("Unix", "Linux"),
+            edge("root", "Linux"),
+        ]
+    );
+    assert!(graph.audit_duplicate_edges().is_empty());
+    assert!(graph.snapshot().meta.is_empty());
+}
+
+#[test]
+fn test_merge_nodes_drops_edges_between_the_two() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+    graph.add_edge("B", "A").unwrap();
+    graph.add_edge("B", "B").unwrap();
+    graph.add_edge("B", "C").unwrap();
+
+    graph.merge_nodes("A", "B").unwrap();
+    assert_eq!(edges_of(&graph), [edge("A", "C"), edge("root", "A")]);
+
+    // a self-loop keep had already survives the merge
+    graph.add_edge("A", "A").unwrap();
+    graph.add_edge("C", "D").unwrap();
+    graph.merge_nodes("A", "D").unwrap();
+    assert!(graph.contains_edge("A", "A"));
+    assert!(graph.contains_edge("C", "A"));
+}
+
+#[test]
+fn test_merge_nodes_rejects_bad_pairs() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+
+    assert_eq!(
+        graph.merge_nodes("A", "A"),
+        Err(GraphError::SelfMerge("A".into()))
+    );
+    assert_eq!(
+        graph.merge_nodes("A", "missing"),
+        Err(GraphError::NodeNotFound("missing".into()))
+    );
+    assert_eq!(
+        graph.merge_nodes("A", "root"),
+        Err(GraphError::RootRemovalForbidden)
+    );
+    assert_eq!(edges_of(&graph), [edge("root", "A")]);
+}
+
+#[test]
+fn test_merge_nodes_keeps_edge_index_in_sync() {
+    let (graph, _rx) = Graph::builder().edge_index(true).build();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "C").unwrap();
+    graph.add_edge("B", "C").unwrap();
+    graph.add_edge("B", "D").unwrap();
+
+    graph.merge_nodes("A", "B").unwrap();
+
+    assert_eq!(graph.indexed_edges().unwrap(), edges_of(&graph));
+    assert!(graph.contains_edge("A", "D"));
+    assert!(!graph.contains_edge("B", "D"));
+}
+
+#[test]
+fn test_add_edges_dedups_within_batch_and_against_existing() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("page", "A").unwrap();
+
+    let added = graph.add_edges("page", &["A", "B", "C", "B", "page"]).unwrap();
+    assert_eq!(added, 3); // B, C and the self-loop
+
+    let children: Vec<String> = graph
+        .get_node("page")
+        .unwrap()
+        .get_children()
+        .iter()
+        .map(|c| c.get_data().to_owned())
+        .collect();
+    assert_eq!(children, ["A", "B", "C", "page"]);
+    assert_eq!(graph.search_prefix("", 10), ["A", "B", "C", "page", "root"]);
+    assert!(graph.audit_duplicate_edges().is_empty());
+    assert_eq!(graph.add_edges::<&str>("page", &[]).unwrap(), 0);
+}
+
+#[test]
+fn test_add_edges_rejects_whole_batch_on_overlong_name() {
+    let (graph, _rx) =
+        Graph::builder().max_name_len(4).edge_index(true).build();
+
+    let err = graph.add_edges("page", &["ok", "too_long"]).unwrap_err();
+    assert_eq!(err, GraphError::NameTooLong { len: 8, max: 4 });
+    assert!(!graph.contains("page"));
+    assert!(!graph.contains("ok"));
+
+    graph.add_edges("page", &["ok"]).unwrap();
+    assert!(graph.contains_edge("page", "ok"));
+    assert_eq!(
+        graph.indexed_edges().unwrap(),
+        [("page".to_string(), "ok".to_string())]
+    );
+}
+
+#[test]
+fn test_parents_track_added_and_removed_edges() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "shared").unwrap();
+    graph.add_edge("B", "shared").unwrap();
+
+    let parents = |name: &str| -> Vec<String> {
+        let node = graph.get_node(name).unwrap();
+        node.get_parents()
+            .iter()
+            .map(|p| p.get_data().to_owned())
+            .collect()
+    };
+    assert_eq!(parents("shared"), ["A", "B"]);
+    assert_eq!(parents("A"), ["root"]);
+    assert!(parents("root").is_empty());
+
+    graph.remove_edge("A", "shared").unwrap();
+    assert_eq!(parents("shared"), ["B"]);
+
+    graph.add_edges("C", &["shared", "A"]).unwrap();
+    assert_eq!(parents("shared"), ["B", "C"]);
+
+    // C's edges move over to B, B -> shared stays a single backlink
+    graph.merge_nodes("B", "C").unwrap();
+    assert_eq!(parents("shared"), ["B"]);
+    assert_eq!(parents("A"), ["root", "B"]);
+}
+
+#[test]
+fn test_node_attrs_set_and_read_concurrently() {
+    let graph = Arc::new(Graph::new_without_events());
+    graph.add_edge("root", "Page").unwrap();
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let graph = graph.clone();
+            std::thread::spawn(move || {
+                let key = format!("attr_{}", i);
+                for round in 0..50 {
+                    let value = format!("{}-{}", i, round);
+                    graph.set_node_attr("Page", &key, value.clone()).unwrap();
+                    let node = graph.get_node("Page").unwrap();
+                    assert_eq!(node.get_attr(&key).unwrap(), value);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let node = graph.get_node("Page").unwrap();
+    for i in 0..8 {
+        let key = format!("attr_{}", i);
+        assert_eq!(node.get_attr(&key).unwrap(), format!("{}-49", i));
+    }
+    assert_eq!(node.get_attr("title"), None);
+
+    // non-string meta isn't an attribute
+    graph.set_node_meta("Page", "http_status", 200).unwrap();
+    assert_eq!(node.get_attr("http_status"), None);
+    assert!(graph.set_node_attr("missing", "title", "x").is_err());
+}
+
+#[test]
+fn test_counted_duplicates_raise_edge_weight() {
+    let (graph, _rx) = Graph::builder().count_duplicates(true).build();
+    assert!(graph.add_edge("root", "A").unwrap().is_inserted());
+    assert!(!graph.add_edge("root", "A").unwrap().is_inserted());
+    assert!(!graph.add_edge("root", "A").unwrap().is_inserted());
+
+    let root = graph.get_root();
+    let a = graph.get_node("A").unwrap();
+    assert_eq!(root.edge_weight_to(&a), Some(3));
+    assert_eq!(a.edge_weight_to(&root), None);
+
+    graph.add_edges("root", &["A", "B"]).unwrap();
+    let b = graph.get_node("B").unwrap();
+    assert_eq!(root.edge_weight_to(&a), Some(4));
+    assert_eq!(root.edge_weight_to(&b), Some(1));
+
+    // a removed edge comes back at its new weight
+    graph.remove_edge("root", "A").unwrap();
+    graph.add_weighted_edge("root", "A", 5).unwrap();
+    assert_eq!(root.edge_weight_to(&a), Some(5));
+
+    // duplicates are still just rejected by default
+    let plain = Graph::new_without_events();
+    plain.add_edge("root", "A").unwrap();
+    assert!(!plain.add_edge("root", "A").unwrap().is_inserted());
+    let a = plain.get_node("A").unwrap();
+    assert_eq!(plain.get_root().edge_weight_to(&a), Some(1));
+    plain.add_weighted_edge("root", "A", 2).unwrap();
+    assert_eq!(plain.get_root().edge_weight_to(&a), Some(3));
+}
+
+#[test]
+fn test_remove_node_and_clear() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("root", "B").unwrap();
+    graph.add_edge("A", "shared").unwrap();
+    graph.add_edge("B", "shared").unwrap();
+    graph.add_edge("shared", "A").unwrap();
+    graph.add_edge("shared", "shared").unwrap();
+
+    let held = graph.get_node("shared").unwrap();
+    assert!(graph.remove_node("shared"));
+    assert_eq!(graph.node_count(), 3);
+    assert!(graph.get_node("shared").is_none());
+
+    // gone from its former parents even while still referenced
+    let names = |name: &str| -> Vec<String> {
+        graph
+            .get_node(name)
+            .unwrap()
+            .get_children()
+            .iter()
+            .map(|c| c.get_data().to_owned())
+            .collect()
+    };
+    assert!(names("A").is_empty());
+    assert!(names("B").is_empty());
+    assert!(held.get_children().is_empty());
+    assert_eq!(graph.in_degree("A"), Some(1));
+    assert_eq!(edges_of(&graph), [edge("root", "A"), edge("root", "B")]);
+
+    assert!(!graph.remove_node("root"));
+    assert!(!graph.remove_node("missing"));
+
+    graph.add_edge("root", "root").unwrap();
+    graph.set_node_meta("root", "fetch_ms", 1).unwrap();
+    graph.clear();
+    assert_eq!(graph.node_count(), 1);
+    assert!(edges_of(&graph).is_empty());
+    assert!(graph.get_root().get_all_meta().is_empty());
+
+    // and usable again
+    graph.add_edge("root", "A").unwrap();
+    assert_eq!(edges_of(&graph), [edge("root", "A")]);
+}
+
+#[test]
+fn test_snapshot_edges_join_snapshot_nodes_under_concurrent_add_edge() {
+    let graph = Arc::new(Graph::new_without_events());
+
+    let writer = {
+        let graph = graph.clone();
+        std::thread::spawn(move || {
+            for i in 0..5_000 {
+                graph.add_edge("root", &format!("n{}", i)).unwrap();
+            }
+        })
+    };
+
+    while !writer.is_finished() {
+        let snapshot = graph.snapshot();
+        for (parent, child) in &snapshot.edges {
+            assert!(snapshot.nodes.binary_search(parent).is_ok());
+            assert!(snapshot.nodes.binary_search(child).is_ok());
+        }
+    }
+    writer.join().unwrap();
+
+    assert_eq!(graph.snapshot().edge_count(), 5_000);
+}
+
+#[test]
+fn test_add_new_edge_rejects_existing_edge() {
+    let (graph, _rx) = Graph::builder().count_duplicates(true).build();
+
+    assert_eq!(graph.add_new_edge("root", "A"), Ok(()));
+    assert_eq!(
+        graph.add_new_edge("root", "A"),
+        Err(GraphError::EdgeExists {
+            parent: "root".into(),
+            child: "A".into(),
+        })
+    );
+    let a = graph.get_node("A").unwrap();
+    assert_eq!(graph.get_root().edge_weight_to(&a), Some(1));
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PageMeta {
+    title: String,
+    summary: Option<String>,
+    fetched_at: Option<u64>,
+}
+
+impl NodeData for PageMeta {
+    fn key(&self) -> &str {
+        &self.title
+    }
+
+    fn from_key(key: &str) -> PageMeta {
+        PageMeta {
+            title: key.to_owned(),
+            summary: None,
+            fetched_at: None,
+        }
+    }
+}
+
+#[test]
+fn test_graph_of_page_meta_resolves_children() {
+    let (graph, mut rx) = Graph::builder().build_for::<PageMeta>();
+
+    let rust = PageMeta {
+        title: "Rust".into(),
+        summary: Some("A systems programming language".into()),
+        fetched_at: Some(1_700_000_000),
+    };
+    graph.insert_node(rust.clone()).unwrap();
+    graph.add_edge("root", "Rust").unwrap();
+    graph.add_edge("Rust", "Cargo").unwrap();
+    graph.add_edge("Rust", "LLVM").unwrap();
+
+    let node = graph.get_node("Rust").unwrap();
+    assert_eq!(node.data(), &rust);
+    let children: Vec<PageMeta> =
+        node.get_children().iter().map(|c| c.data().clone()).collect();
+    assert_eq!(
+        children,
+        [PageMeta::from_key("Cargo"), PageMeta::from_key("LLVM")]
+    );
+    assert_eq!(graph.get_root().get_children()[0].data(), &rust);
+
+    // an existing node keeps its data
+    graph.insert_node(PageMeta::from_key("Rust")).unwrap();
+    assert_eq!(graph.get_node("Rust").unwrap().data(), &rust);
+
+    let mut added = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let GraphEvent::NodeAdded(name) = event {
+            added.push(name);
+        }
+    }
+    assert_eq!(added, ["Rust", "Cargo", "LLVM"]);
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::task;
 
-use crate::graph::core::Graph;
+use crate::graph::core::{EdgeOutcome, Graph, GraphEvent};
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
 async fn test_massive_concurrent_edge_addition() {
@@ -49,7 +49,7 @@ async fn test_heavy_duplicate_contention() {
 
     let successes = results
         .iter()
-        .filter(|r| matches!(r, Ok(true)))
+        .filter(|r| matches!(r, Ok(EdgeOutcome::Inserted)))
         .count();
 
     assert_eq!(successes, 1, "Only one task should succeed");
@@ -333,7 +333,7 @@ async fn test_concurrent_self_loop_tokio() {
 
     let successes = results
         .iter()
-        .filter(|r| matches!(r, Ok(true)))
+        .filter(|r| matches!(r, Ok(EdgeOutcome::Inserted)))
         .count();
 
     assert_eq!(successes, 1, "Only one self-loop should succeed");
@@ -441,3 +441,72 @@ async fn test_node_count_monotonic_async() {
         handle.await.unwrap();
     }
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_blocking_mutation_with_bounded_channel() {
+    // far fewer slots than events, the blocking path has to wait on the
+    // consumer instead of dropping anything
+    let (graph, mut rx) = Graph::new_bounded(4);
+    let graph = Arc::new(graph);
+
+    let consumer = task::spawn(async move {
+        let mut count = 0;
+        while rx.recv().await.is_some() {
+            count += 1;
+        }
+        count
+    });
+
+    let writer = graph.clone();
+    tokio::task::spawn_blocking(move || {
+        for i in 0..100 {
+            let node = format!("blocking_{}", i);
+            let outcome = writer.add_edge_blocking("root", &node).unwrap();
+            assert!(outcome.is_inserted());
+        }
+    })
+    .await
+    .unwrap();
+
+    drop(graph); // closes the channel so the consumer finishes
+    assert_eq!(consumer.await.unwrap(), 200); // NodeAdded + EdgeAdded each
+}
+
+#[tokio::test]
+async fn test_bounded_channel_full_drops_events() {
+    let (graph, mut rx) = Graph::new_bounded(2);
+
+    for i in 0..10 {
+        let node = format!("node_{}", i);
+        assert!(graph.add_edge("root", &node).unwrap().is_inserted());
+    }
+
+    assert_eq!(graph.node_count(), 11);
+
+    let mut received = 0;
+    while rx.try_recv().is_ok() {
+        received += 1;
+    }
+    assert_eq!(received, 2);
+}
+
+#[tokio::test]
+async fn test_full_channel_counts_dropped_events() {
+    let (graph, mut rx) = Graph::new_bounded(2);
+
+    // NodeAdded + EdgeAdded per edge, only the first 2 events fit
+    for i in 0..10 {
+        let node = format!("node_{}", i);
+        assert!(graph.add_edge("root", &node).unwrap().is_inserted());
+    }
+    assert_eq!(graph.add_edges("root", &["node_0", "extra"]).unwrap(), 1);
+
+    assert_eq!(graph.node_count(), 12);
+    assert_eq!(graph.dropped_events(), 21);
+
+    // room again once the consumer catches up
+    while rx.try_recv().is_ok() {}
+    graph.add_edge("root", "last").unwrap();
+    assert_eq!(graph.dropped_events(), 21);
+    assert_eq!(rx.try_recv().unwrap(), GraphEvent::NodeAdded("last".into()));
+}
@@ -0,0 +1,4 @@
+pub mod crawler;
+pub mod graph;
+pub mod log;
+pub mod visualizer;
@@ -1,3 +1,8 @@
+pub mod json;
 pub mod setup;
+pub mod setup_tests;
 
-pub use setup::setup_logging;
+pub use json::JsonFormat;
+pub use setup::{
+    LogConfig, LogFormat, file_appender, file_layer, setup_logging,
+};
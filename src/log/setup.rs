@@ -1,15 +1,108 @@
-use anyhow::Result;
+use std::{path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result};
+use tracing::Subscriber;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    Layer, fmt, layer::SubscriberExt, registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+use crate::log::json::JsonFormat;
+
+/// Line format of the log file, stdout always stays `Full`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// one line per event with spans and fields, tracing's default
+    #[default]
+    Full,
+
+    /// multi-line and indented, for reading by eye
+    Pretty,
+
+    /// `Full` without span fields
+    Compact,
+
+    /// one JSON object per line, see `JsonFormat`
+    Json,
+}
 
-pub fn setup_logging() -> Result<()> {
-    std::fs::create_dir_all("logs")?;
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
 
-    #[rustfmt::skip]
-    let file_appender = RollingFileAppender::new(
-        Rotation::DAILY, "logs", "app.log"
-    );
+    fn from_str(s: &str) -> Result<LogFormat> {
+        match s {
+            "full" => Ok(LogFormat::Full),
+            "pretty" => Ok(LogFormat::Pretty),
+            "compact" => Ok(LogFormat::Compact),
+            "json" => Ok(LogFormat::Json),
+            _ => anyhow::bail!("unknown log format {:?}", s),
+        }
+    }
+}
+
+/// Where the log file goes, stdout gets the same lines regardless
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// created if missing, `logs` in the working directory by default
+    pub dir: PathBuf,
+
+    /// file name before the rotation's date suffix, `app.log` by default
+    pub file_prefix: String,
+
+    /// how often a new file is started, daily by default
+    pub rotation: Rotation,
+
+    pub format: LogFormat,
+}
 
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            dir: PathBuf::from("logs"),
+            file_prefix: String::from("app.log"),
+            rotation: Rotation::DAILY,
+            format: LogFormat::default(),
+        }
+    }
+}
+
+/// Rolling file writer for `config`, creating its directory first
+pub fn file_appender(config: &LogConfig) -> Result<RollingFileAppender> {
+    std::fs::create_dir_all(&config.dir).with_context(|| {
+        format!("creating log directory {}", config.dir.display())
+    })?;
+
+    RollingFileAppender::builder()
+        .rotation(config.rotation.clone())
+        .filename_prefix(&config.file_prefix)
+        .build(&config.dir)
+        .with_context(|| {
+            format!("opening log file in {}", config.dir.display())
+        })
+}
+
+/// The log file layer, in `config.format` with span close events
+pub fn file_layer<S>(
+    config: &LogConfig,
+) -> Result<Box<dyn Layer<S> + Send + Sync>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let layer = fmt::layer()
+        .with_writer(file_appender(config)?)
+        .with_ansi(false)
+        .with_span_events(fmt::format::FmtSpan::CLOSE);
+
+    Ok(match config.format {
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Json => layer.event_format(JsonFormat).boxed(),
+    })
+}
+
+pub fn setup_logging(config: &LogConfig) -> Result<()> {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -20,12 +113,7 @@ pub fn setup_logging() -> Result<()> {
                 .with_writer(std::io::stdout)
                 .with_span_events(fmt::format::FmtSpan::CLOSE),
         )
-        .with(
-            fmt::layer()
-                .with_writer(file_appender)
-                .with_ansi(false)
-                .with_span_events(fmt::format::FmtSpan::CLOSE),
-        )
+        .with(file_layer(config)?)
         .init();
 
     Ok(())
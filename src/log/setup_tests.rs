@@ -0,0 +1,106 @@
+#![cfg(test)]
+use std::fs;
+
+use serde_json::Value;
+use tracing_appender::rolling::Rotation;
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::log::{LogConfig, LogFormat, file_appender, file_layer};
+
+fn temp_log_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "mycelia_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_log_lines_land_in_configured_dir() {
+    let dir = temp_log_dir("logs");
+    let config = LogConfig {
+        dir: dir.clone(),
+        file_prefix: String::from("test.log"),
+        rotation: Rotation::NEVER,
+        ..Default::default()
+    };
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(file_appender(&config).unwrap())
+        .with_ansi(false)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("hello from the test");
+    });
+
+    let files: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(files, ["test.log"]);
+    let log = fs::read_to_string(dir.join("test.log")).unwrap();
+    assert!(log.contains("hello from the test"), "{}", log);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_unusable_log_dir_is_a_clear_error() {
+    // a file where the directory should go
+    let path = std::env::temp_dir()
+        .join(format!("mycelia_log_file_{}", std::process::id()));
+    fs::write(&path, "").unwrap();
+    let config = LogConfig {
+        dir: path.join("logs"),
+        ..Default::default()
+    };
+
+    let err = file_appender(&config).unwrap_err();
+    assert!(
+        err.to_string().starts_with("creating log directory"),
+        "{}",
+        err
+    );
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_json_format_writes_one_object_per_line() {
+    let dir = temp_log_dir("json_logs");
+    let config = LogConfig {
+        dir: dir.clone(),
+        file_prefix: String::from("app.json"),
+        rotation: Rotation::NEVER,
+        format: LogFormat::Json,
+    };
+
+    let subscriber =
+        tracing_subscriber::registry().with(file_layer(&config).unwrap());
+    tracing::subscriber::with_default(subscriber, || {
+        let _span = tracing::info_span!("crawl", worker = 3).entered();
+        tracing::info!(url = "https://a.org", status = 503u64, "Fetch failed");
+    });
+
+    let log = fs::read_to_string(dir.join("app.json")).unwrap();
+    let lines: Vec<Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2, "{}", log);
+
+    let event = &lines[0];
+    assert_eq!(event["level"], "INFO");
+    assert_eq!(event["fields"]["message"], "Fetch failed");
+    assert_eq!(event["fields"]["url"], "https://a.org");
+    assert_eq!(event["fields"]["status"], 503);
+    assert_eq!(event["spans"], serde_json::json!(["crawl"]));
+
+    // the span closing is logged too
+    assert_eq!(lines[1]["fields"]["message"], "close");
+    assert_eq!(lines[1]["spans"], serde_json::json!(["crawl"]));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,87 +1,287 @@
-use anyhow::{Context, Result, anyhow};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use mycelia::crawler::{
+    Checkpoint, CrawlConfig, Crawler, DEFAULT_ARTICLE_REGEX,
+    DEFAULT_USER_AGENT, HttpFetcher, RetryPolicy, Scope, Timeouts,
+};
+use mycelia::graph::core::Graph;
+use mycelia::log::{self, LogConfig, LogFormat};
+use mycelia::visualizer;
 use regex::Regex;
-use scraper::{Html, Selector};
-use tracing::{error, info, instrument};
+use structopt::StructOpt;
+use tracing::{error, info};
 
-mod log;
-mod graph;
-mod visualizer;
+// far above any real article url, only there to stop runaway links
+const MAX_NAME_LEN: usize = 2048;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    log::setup_logging()?;
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mycelia")]
+struct Cli {
+    /// Page to start crawling from, without it (or --sitemap) only the
+    /// visualizer is served
+    #[structopt(long)]
+    start_url: Option<String>,
 
-    info!("Starting application");
+    /// Seed the crawl from a sitemap.xml (or sitemap index) instead
+    #[structopt(long)]
+    sitemap: Option<String>,
 
-    visualizer::server::start().await?;
+    /// Number of concurrent fetch workers
+    #[structopt(long, default_value = "4")]
+    workers: usize,
 
-    // if let Err(e) = run().await {
-    //     error!("Fatal error: {:?}", e);
-    //     return Err(e);
-    // }
+    /// Requests in flight at once across all workers, --workers if unset
+    #[structopt(long)]
+    max_concurrent_requests: Option<usize>,
 
-    info!("Shutting down gracefully");
-    Ok(())
+    /// Minimum milliseconds between two requests to the same host
+    #[structopt(long, default_value = "0")]
+    delay_ms: u64,
+
+    /// Random extra milliseconds on top of --delay-ms, drawn per request
+    #[structopt(long, default_value = "0")]
+    delay_jitter_ms: u64,
+
+    /// User-Agent header sent with every request, mycelia-crawler/<version>
+    /// by default
+    #[structopt(long)]
+    user_agent: Option<String>,
+
+    /// Give up on a request after this many seconds in total
+    #[structopt(long, default_value = "30")]
+    timeout_secs: u64,
+
+    /// Give up connecting to a host after this many seconds
+    #[structopt(long, default_value = "5")]
+    connect_timeout_secs: u64,
+
+    /// Give up on a response once it stalls for this many seconds
+    #[structopt(long, default_value = "10")]
+    read_timeout_secs: u64,
+
+    /// Retry a page failing with a 5xx, 429 or a broken connection up to
+    /// this many times, backing off exponentially
+    #[structopt(long, default_value = "2")]
+    max_retries: u32,
+
+    /// Only fetch pages within this many link hops of the start page
+    #[structopt(long)]
+    radius: Option<usize>,
+
+    /// Stop following urls that differ only in a numeric query value or an
+    /// opaque part once this many of one such pattern were queued
+    #[structopt(long)]
+    trap_threshold: Option<usize>,
+
+    /// Also follow plain http links, e.g. into the local-testing-server
+    #[structopt(long)]
+    allow_http: bool,
+
+    /// Also follow links to localhost and IP-literal hosts, any path on
+    /// them counts as an article
+    #[structopt(long)]
+    allow_ip_hosts: bool,
+
+    /// With --allow-ip-hosts, also follow their links with a query
+    /// string, e.g. `?id=N`, use --trap-threshold to cut off endless ones
+    #[structopt(long)]
+    allow_queries: bool,
+
+    /// MediaWiki host to crawl the articles of
+    #[structopt(long, default_value = "en.wikipedia.org")]
+    wiki_host: String,
+
+    /// Regex for the article paths on --wiki-host, capturing the title,
+    /// e.g. `^/index\.php/(.*)$`
+    #[structopt(long, default_value = DEFAULT_ARTICLE_REGEX)]
+    article_regex: Regex,
+
+    /// CSS selector for the anchors links are taken from
+    #[structopt(long, default_value = "a[href]")]
+    link_selector: String,
+
+    /// Follow at most this many links of a single page
+    #[structopt(long, default_value = "10000")]
+    max_links: usize,
+
+    /// Keep at most this many queued urls in memory, spilling the rest to
+    /// disk (beside the --checkpoint if set)
+    #[structopt(long)]
+    max_frontier: Option<usize>,
+
+    /// Also follow Category: links, tagging those nodes as categories
+    #[structopt(long)]
+    crawl_categories: bool,
+
+    /// Number pages in the order they're discovered, as discovered_at meta
+    #[structopt(long)]
+    record_discovery: bool,
+
+    /// Save each fetched page's raw HTML here as it's fetched, listed in a
+    /// manifest.json
+    #[structopt(long, parse(from_os_str))]
+    output_dir: Option<PathBuf>,
+
+    /// Log a JSON summary of requests per host and error statuses once
+    /// the crawl finishes
+    #[structopt(long)]
+    log_report: bool,
+
+    /// Skip links from a page back to itself
+    #[structopt(long)]
+    drop_self_loops: bool,
+
+    /// Save the crawl (graph, queued and visited pages) here as it goes,
+    /// and pick up where it left off if it exists, --resume is an alias
+    #[structopt(long, alias = "resume", parse(from_os_str))]
+    checkpoint: Option<PathBuf>,
+
+    /// With --checkpoint, only fetch pages the checkpoint hasn't expanded
+    /// yet, e.g. to extend a finished crawl
+    #[structopt(long, requires = "checkpoint")]
+    only_new: bool,
+
+    /// With --checkpoint, save it after every this many pages
+    #[structopt(long, default_value = "100")]
+    checkpoint_every: usize,
+
+    /// Directory the rolling log file is written to
+    #[structopt(long, parse(from_os_str), default_value = "logs")]
+    log_dir: PathBuf,
+
+    /// Log file format: full, pretty, compact or json, stdout stays full
+    #[structopt(long, default_value = "full")]
+    log_format: LogFormat,
+
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
 }
 
-#[instrument]
-async fn run() -> Result<()> {
-    let data = fetch_data().await?;
-    info!(records = data.len(), "Data fetched successfully");
-    Ok(())
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Serve the visualizer over a recorded crawl, without any network
+    Serve {
+        /// NDJSON event log to rebuild the graph from, e.g. an ndjson
+        /// export
+        #[structopt(long, parse(from_os_str))]
+        replay: PathBuf,
+    },
 }
 
-#[instrument]
-async fn fetch_data() -> Result<String> {
-    let resp = reqwest::get("http://localhost:8080/pages/linux.html")
-        .await
-        .context("Failed to connect")?
-        .text()
-        .await
-        .context("Failed to parse text")?;
-
-    let links = extract_links(&resp).await?;
-    for l in links {
-        println!("{}", l);
+impl Cli {
+    fn crawl_config(&self) -> Option<CrawlConfig> {
+        if self.start_url.is_none() && self.sitemap.is_none() {
+            return None;
+        }
+
+        Some(CrawlConfig {
+            start_url: self.start_url.clone().unwrap_or_default(),
+            sitemap: self.sitemap.clone(),
+            workers: self.workers,
+            max_concurrent_requests: self.max_concurrent_requests,
+            drop_self_loops: self.drop_self_loops,
+            only_new: self.only_new,
+            radius: self.radius,
+            crawl_categories: self.crawl_categories,
+            scope: self.scope(),
+            max_links: self.max_links,
+            max_frontier: self.max_frontier,
+            trap_threshold: self.trap_threshold,
+            log_report: self.log_report,
+            archive_dir: self.output_dir.clone(),
+            record_discovery: self.record_discovery,
+            spill_path: self.checkpoint.as_ref().map(Checkpoint::spill_path),
+            checkpoint_path: self.checkpoint.clone(),
+            checkpoint_every: self.checkpoint_every,
+            retry: RetryPolicy {
+                max_retries: self.max_retries,
+                ..Default::default()
+            },
+            request_delay: Duration::from_millis(self.delay_ms),
+            delay_jitter: Duration::from_millis(self.delay_jitter_ms),
+            ..CrawlConfig::wikipedia()
+        })
+    }
+
+    fn scope(&self) -> Scope {
+        let mut scope = Scope {
+            wiki_host: self.wiki_host.clone(),
+            article_regex: self.article_regex.clone(),
+            link_selector: self.link_selector.clone(),
+            allow_ip_hosts: self.allow_ip_hosts,
+            allow_queries: self.allow_queries,
+            ..Default::default()
+        };
+        if self.allow_http {
+            scope.schemes.push(String::from("http"));
+        }
+        scope
     }
 
-    Ok(resp)
+    fn timeouts(&self) -> Timeouts {
+        Timeouts {
+            request: Some(Duration::from_secs(self.timeout_secs)),
+            connect: Some(Duration::from_secs(self.connect_timeout_secs)),
+            read: Some(Duration::from_secs(self.read_timeout_secs)),
+        }
+    }
 }
 
-#[instrument(skip(body))]
-async fn extract_links(body: &str) -> Result<Vec<String>> {
-    let doc = Html::parse_document(body);
-    let selector = Selector::parse(
-        r#"
-        a[href^="https://en.wikipedia.org/wiki/"]:not([href*="?"]):not([href*="action="])
-    "#,
-    );
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::from_args();
+    log::setup_logging(&LogConfig {
+        dir: cli.log_dir.clone(),
+        format: cli.log_format,
+        ..Default::default()
+    })?;
 
-    if selector.is_err() {
-        return Err(anyhow!("failed to create selector"));
+    info!("Starting application");
+
+    if let Some(Command::Serve { replay }) = &cli.cmd {
+        return serve_replay(replay).await;
     }
 
-    #[rustfmt::skip]
-    let wiki_article_re = Regex::new(
-        r"^https://en\.wikipedia\.org/wiki/([^:?#]+)(?:#[^?]*)?$"
-    ).unwrap();
-
-    let namespace_re = Regex::new(
-        r"^(Category|Wikipedia|Special|Template|Help|Portal|Book|Draft|File|MediaWiki|Module|TimedText|User|Talk):"
-    ).unwrap();
-
-    Ok(doc
-        .select(&selector.unwrap())
-        .filter_map(|el| el.value().attr("href"))
-        .filter(|href| {
-            let group = wiki_article_re.captures(href);
-            if group.is_none() {
-                return false;
-            };
-
-            let article_name = &group.unwrap()[1]; // 1 as in \1
-            !namespace_re.is_match(article_name)
-        })
-        .map(|s| s.to_owned())
-        .collect::<Vec<String>>())
+    let (graph, events) = Graph::builder().max_name_len(MAX_NAME_LEN).build();
+    let graph = Arc::new(graph);
+
+    if let Some(config) = cli.crawl_config() {
+        let user_agent =
+            cli.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let fetcher = HttpFetcher::with_user_agent(cli.timeouts(), user_agent)?;
+        let crawler = Crawler::new(config, graph.clone(), fetcher);
+
+        tokio::spawn(async move {
+            if let Err(e) = crawler.run().await {
+                error!("Crawl failed: {:?}", e);
+            }
+        });
+    }
+
+    visualizer::server::start(graph, events).await?;
+
+    info!("Shutting down gracefully");
+    Ok(())
+}
+
+async fn serve_replay(path: &Path) -> Result<()> {
+    let (graph, events) = Graph::builder().max_name_len(MAX_NAME_LEN).build();
+
+    let report = graph.replay(BufReader::new(File::open(path)?))?;
+    info!(
+        applied = report.applied,
+        skipped = report.skipped,
+        nodes = graph.node_count(),
+        "Replayed event log"
+    );
+
+    visualizer::server::start(Arc::new(graph), events).await?;
+
+    info!("Shutting down gracefully");
+    Ok(())
 }
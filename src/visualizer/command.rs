@@ -0,0 +1,269 @@
+use std::{collections::HashSet, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::graph::core::{Graph, Node};
+use crate::graph::error::GraphError;
+use crate::visualizer::protocol::Encoding;
+
+/// Largest `data` payload of a single export frame, in bytes
+pub const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Text frames a client can send, `{"cmd": "...", ...}`
+///
+/// Every text frame is parsed once into this, anything that doesn't parse
+/// gets an `{"type":"error"}` reply and the connection stays open. A `cmd`
+/// that isn't one of these parses as `Unknown`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "camelCase")]
+pub enum ClientCommand {
+    /// direct children of a node
+    #[serde(alias = "getNeighbors")]
+    Neighbors { node: String },
+
+    /// add an edge to the shared graph, like a crawled link
+    AddEdge { parent: String, child: String },
+
+    /// the whole current graph in this client's encoding
+    Snapshot,
+
+    /// fewest-edges path between two nodes
+    Path { from: String, to: String },
+
+    /// the subgraph within `hops` edges of a node
+    Focus {
+        node: String,
+        #[serde(default = "default_hops")]
+        hops: usize,
+    },
+
+    /// node names containing `text`, case-insensitively
+    Query {
+        text: String,
+        #[serde(default = "default_limit")]
+        limit: usize,
+    },
+
+    /// the whole current graph, see `export_frames`
+    Export { format: ExportFormat },
+
+    /// stop forwarding graph events to this client
+    Pause,
+
+    /// forward events again, starting with a fresh snapshot
+    Resume,
+
+    /// switch this client's wire format
+    SetEncoding { encoding: Encoding },
+
+    /// replay the events after `seq`, or a snapshot if they're gone
+    #[serde(alias = "resume_from")]
+    ResumeFrom { seq: u64 },
+
+    /// any other `cmd`, answered with an `unknown command` error frame
+    #[serde(other)]
+    Unknown,
+}
+
+fn default_hops() -> usize {
+    1
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+impl ClientCommand {
+    pub fn parse(text: &str) -> Result<ClientCommand, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Dot,
+    Json,
+    Graphml,
+    Cytoscape,
+    Ndjson,
+    Adjacency,
+    Csv,
+}
+
+pub fn error_frame(message: &str) -> String {
+    json!({"type": "error", "message": message}).to_string()
+}
+
+/// Replies to one client text frame, in send order
+pub fn handle_text(graph: &Graph, text: &str) -> Vec<String> {
+    match ClientCommand::parse(text) {
+        Ok(cmd) => reply(graph, &cmd),
+        Err(e) => vec![error_frame(&e.to_string())],
+    }
+}
+
+/// Graph answers to a command, just an ack for the ones that only change
+/// the connection's own state
+pub fn reply(graph: &Graph, cmd: &ClientCommand) -> Vec<String> {
+    let frame = match cmd {
+        ClientCommand::Neighbors { node } => match graph.get_node(node) {
+            Some(n) => {
+                let children: Vec<String> = n.with_children(|children| {
+                    children.map(|c| c.get_data().to_owned()).collect()
+                });
+                json!({"type": "neighbors", "node": node, "children": children})
+            }
+            None => return not_found(node),
+        },
+        ClientCommand::AddEdge { parent, child } => {
+            match graph.add_edge(parent, child) {
+                Ok(outcome) => json!({
+                    "type": "addEdge",
+                    "parent": parent,
+                    "child": child,
+                    "added": outcome.is_inserted(),
+                }),
+                Err(e) => return vec![error_frame(&e.to_string())],
+            }
+        }
+        ClientCommand::Path { from, to } => {
+            let path = graph.shortest_path(from, to);
+            json!({"type": "path", "from": from, "to": to, "path": path})
+        }
+        ClientCommand::Focus { node, hops } => {
+            let Some(start) = graph.get_node(node) else {
+                return not_found(node);
+            };
+            let (nodes, edges) = subgraph(graph, &start, *hops);
+            json!({
+                "type": "focus",
+                "node": node,
+                "hops": hops,
+                "nodes": nodes,
+                "edges": edges,
+            })
+        }
+        ClientCommand::Query { text, limit } => {
+            let needle = text.to_lowercase();
+            let mut matches: Vec<String> = graph
+                .snapshot()
+                .nodes
+                .into_iter()
+                .filter(|n| n.to_lowercase().contains(&needle))
+                .collect();
+            matches.truncate(*limit);
+            json!({"type": "query", "text": text, "matches": matches})
+        }
+        ClientCommand::Export { format } => {
+            return export_frames(graph, *format);
+        }
+        ClientCommand::Pause => json!({"type": "ack", "cmd": "pause"}),
+        ClientCommand::Resume => json!({"type": "ack", "cmd": "resume"}),
+        ClientCommand::SetEncoding { encoding } => {
+            json!({"type": "ack", "cmd": "setEncoding", "encoding": encoding})
+        }
+        // need the hub's event history or the socket's encoding, the
+        // socket answers them
+        ClientCommand::ResumeFrom { .. } | ClientCommand::Snapshot => {
+            return Vec::new();
+        }
+        ClientCommand::Unknown => return vec![error_frame("unknown command")],
+    };
+
+    vec![frame.to_string()]
+}
+
+fn not_found(node: &str) -> Vec<String> {
+    let e = GraphError::NodeNotFound(node.to_owned());
+    vec![error_frame(&e.to_string())]
+}
+
+/// Sorted names and edges of the nodes within `hops` of `start`
+fn subgraph(
+    graph: &Graph,
+    start: &Arc<Node>,
+    hops: usize,
+) -> (Vec<String>, Vec<(String, String)>) {
+    let nodes = graph.within_hops(start, hops);
+    let names: HashSet<&str> = nodes.iter().map(|n| n.get_data()).collect();
+
+    let mut edges = Vec::new();
+    for node in &nodes {
+        node.with_children(|children| {
+            for child in children {
+                if names.contains(child.get_data()) {
+                    edges.push((
+                        node.get_data().to_owned(),
+                        child.get_data().to_owned(),
+                    ));
+                }
+            }
+        });
+    }
+    edges.sort();
+
+    let mut names: Vec<String> = names.into_iter().map(str::to_owned).collect();
+    names.sort();
+
+    (names, edges)
+}
+
+pub fn export(graph: &Graph, format: ExportFormat) -> anyhow::Result<String> {
+    Ok(match format {
+        ExportFormat::Dot => graph.to_dot(),
+        ExportFormat::Json => graph.to_json()?,
+        ExportFormat::Graphml => graph.to_graphml(),
+        ExportFormat::Cytoscape => graph.to_cytoscape(),
+        ExportFormat::Ndjson => graph.to_ndjson(),
+        ExportFormat::Adjacency => graph.to_adjacency_json(),
+        ExportFormat::Csv => graph.to_csv(),
+    })
+}
+
+/// The export split into `{"type":"export","chunk":i,"chunks":n,...}`
+/// frames, concatenating every `data` in order gives the full document
+pub fn export_frames(graph: &Graph, format: ExportFormat) -> Vec<String> {
+    let body = match export(graph, format) {
+        Ok(body) => body,
+        Err(e) => return vec![error_frame(&e.to_string())],
+    };
+
+    let chunks = chunk(&body, EXPORT_CHUNK_BYTES);
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, data)| {
+            json!({
+                "type": "export",
+                "format": format,
+                "chunk": i,
+                "chunks": chunks.len(),
+                "data": data,
+            })
+            .to_string()
+        })
+        .collect()
+}
+
+/// Splits on char boundaries into pieces of at most `max` bytes (at least
+/// one char each), one empty piece for an empty string
+pub(crate) fn chunk(s: &str, max: usize) -> Vec<&str> {
+    let max = max.max(4);
+    let mut pieces = Vec::new();
+    let mut rest = s;
+
+    while rest.len() > max {
+        let mut end = max;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (piece, tail) = rest.split_at(end);
+        pieces.push(piece);
+        rest = tail;
+    }
+    pieces.push(rest);
+
+    pieces
+}
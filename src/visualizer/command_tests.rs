@@ -0,0 +1,196 @@
+#![cfg(test)]
+use serde_json::{Value, json};
+
+use crate::graph::core::Graph;
+use crate::visualizer::command::{
+    ClientCommand, EXPORT_CHUNK_BYTES, ExportFormat, chunk, export,
+    export_frames, handle_text,
+};
+
+fn frames(replies: &[String]) -> Vec<Value> {
+    replies
+        .iter()
+        .map(|r| serde_json::from_str(r).unwrap())
+        .collect()
+}
+
+fn reassemble(frames: &[Value]) -> String {
+    frames.iter().map(|f| f["data"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_export_command_every_format() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "say \"<hi>\"").unwrap();
+
+    for (name, format) in [
+        ("dot", ExportFormat::Dot),
+        ("json", ExportFormat::Json),
+        ("graphml", ExportFormat::Graphml),
+        ("cytoscape", ExportFormat::Cytoscape),
+        ("csv", ExportFormat::Csv),
+    ] {
+        let cmd = format!(r#"{{"cmd":"export","format":"{}"}}"#, name);
+        let replies = frames(&handle_text(&graph, &cmd));
+
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0]["type"], "export");
+        assert_eq!(replies[0]["format"], name);
+        assert_eq!(replies[0]["chunks"], 1);
+        assert_eq!(reassemble(&replies), export(&graph, format).unwrap());
+    }
+}
+
+#[test]
+fn test_large_export_is_chunked() {
+    let graph = Graph::new_without_events();
+    for i in 0..5000 {
+        graph.add_edge("root", &format!("page_{}", i)).unwrap();
+    }
+
+    let replies = frames(&export_frames(&graph, ExportFormat::Dot));
+
+    assert!(replies.len() > 1);
+    for (i, frame) in replies.iter().enumerate() {
+        assert_eq!(frame["chunk"], i);
+        assert_eq!(frame["chunks"], replies.len());
+        assert!(frame["data"].as_str().unwrap().len() <= EXPORT_CHUNK_BYTES);
+    }
+    assert_eq!(reassemble(&replies), graph.to_dot());
+}
+
+#[test]
+fn test_chunk_respects_char_boundaries() {
+    let s = "aéb€c".repeat(10);
+    let pieces = chunk(&s, 5);
+
+    assert!(pieces.iter().all(|p| p.len() <= 5 && !p.is_empty()));
+    assert_eq!(pieces.concat(), s);
+    assert_eq!(chunk("", 5), vec![""]);
+}
+
+#[test]
+fn test_unknown_format_and_garbage_are_errors() {
+    let graph = Graph::new_without_events();
+
+    for text in [
+        r#"{"cmd":"export","format":"xlsx"}"#,
+        r#"{"cmd":"addEdge","parent":"root"}"#,
+        r#"{"node":"root"}"#,
+        "not json",
+    ] {
+        let replies = frames(&handle_text(&graph, text));
+
+        assert_eq!(replies.len(), 1, "{}", text);
+        assert_eq!(replies[0]["type"], "error");
+        assert!(replies[0]["message"].is_string());
+    }
+}
+
+#[test]
+fn test_unknown_command_is_named_in_reply() {
+    let graph = Graph::new_without_events();
+
+    for text in [r#"{"cmd":"dance"}"#, r#"{"cmd":"dance","node":"root"}"#] {
+        let replies = frames(&handle_text(&graph, text));
+
+        assert_eq!(replies.len(), 1, "{}", text);
+        assert_eq!(replies[0]["message"], "unknown command");
+        assert_eq!(replies[0]["type"], "error");
+    }
+    assert_eq!(
+        ClientCommand::parse(r#"{"cmd":"dance"}"#).unwrap(),
+        ClientCommand::Unknown
+    );
+}
+
+fn reply_to(graph: &Graph, text: &str) -> Value {
+    let replies = frames(&handle_text(graph, text));
+    assert_eq!(replies.len(), 1, "{}", text);
+    replies.into_iter().next().unwrap()
+}
+
+fn chain() -> Graph {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "Alpha").unwrap();
+    graph.add_edge("Alpha", "Beta").unwrap();
+    graph.add_edge("Beta", "Gamma").unwrap();
+    graph.add_edge("root", "alphabet").unwrap();
+    graph
+}
+
+#[test]
+fn test_graph_query_commands() {
+    let graph = chain();
+
+    let reply = reply_to(&graph, r#"{"cmd":"neighbors","node":"root"}"#);
+    assert_eq!(reply["children"], json!(["Alpha", "alphabet"]));
+
+    let reply =
+        reply_to(&graph, r#"{"cmd":"path","from":"root","to":"Gamma"}"#);
+    assert_eq!(reply["path"], json!(["root", "Alpha", "Beta", "Gamma"]));
+
+    let reply =
+        reply_to(&graph, r#"{"cmd":"path","from":"Gamma","to":"root"}"#);
+    assert!(reply["path"].is_null());
+
+    let reply = reply_to(&graph, r#"{"cmd":"focus","node":"Alpha"}"#);
+    assert_eq!(reply["nodes"], json!(["Alpha", "Beta"]));
+    assert_eq!(reply["edges"], json!([["Alpha", "Beta"]]));
+
+    let reply = reply_to(&graph, r#"{"cmd":"focus","node":"Alpha","hops":2}"#);
+    assert_eq!(reply["nodes"], json!(["Alpha", "Beta", "Gamma"]));
+
+    let reply = reply_to(&graph, r#"{"cmd":"query","text":"ALPHA"}"#);
+    assert_eq!(reply["matches"], json!(["Alpha", "alphabet"]));
+
+    let reply = reply_to(&graph, r#"{"cmd":"query","text":"a","limit":1}"#);
+    assert_eq!(reply["matches"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn test_missing_node_and_session_commands() {
+    let graph = chain();
+
+    let reply = reply_to(&graph, r#"{"cmd":"neighbors","node":"nope"}"#);
+    assert_eq!(reply["type"], "error");
+    assert_eq!(reply["message"], "Node nope not found");
+
+    let reply = reply_to(&graph, r#"{"cmd":"focus","node":"nope"}"#);
+    assert_eq!(reply["type"], "error");
+
+    for (text, cmd) in [
+        (r#"{"cmd":"pause"}"#, "pause"),
+        (r#"{"cmd":"resume"}"#, "resume"),
+        (r#"{"cmd":"setEncoding","encoding":"dot"}"#, "setEncoding"),
+    ] {
+        let reply = reply_to(&graph, text);
+        assert_eq!(reply["type"], "ack");
+        assert_eq!(reply["cmd"], cmd);
+    }
+}
+
+#[test]
+fn test_add_edge_and_get_neighbors_commands() {
+    let graph = Graph::new_without_events();
+
+    let text = r#"{"cmd":"addEdge","parent":"root","child":"A"}"#;
+    let reply = reply_to(&graph, text);
+    assert_eq!(reply["type"], "addEdge");
+    assert_eq!(reply["added"], true);
+    assert!(graph.contains_edge("root", "A"));
+
+    // a second time is a duplicate, not an error
+    assert_eq!(reply_to(&graph, text)["added"], false);
+
+    let reply = reply_to(&graph, r#"{"cmd":"getNeighbors","node":"root"}"#);
+    assert_eq!(reply["type"], "neighbors");
+    assert_eq!(reply["children"], json!(["A"]));
+
+    let reply = reply_to(&graph, r#"{"cmd":"addEdge","parent":"root"}"#);
+    assert_eq!(reply["type"], "error");
+
+    // the socket answers a snapshot, in its own encoding
+    assert!(handle_text(&graph, r#"{"cmd":"snapshot"}"#).is_empty());
+}
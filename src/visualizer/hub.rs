@@ -0,0 +1,195 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use actix::{Message, Recipient};
+use tokio::{sync::mpsc, time::Instant};
+use tracing::{info, warn};
+
+use crate::graph::core::GraphEvent;
+
+/// Recent events kept for `resume_from`, older cursors get a snapshot
+pub const HISTORY_LEN: usize = 1024;
+
+/// A graph event and its sequence number, numbered from 1 in send order
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub struct Forward(pub u64, pub GraphEvent);
+
+/// No events follow, sent to every client once the hub shuts down
+#[derive(Message, Debug, Clone, Copy)]
+#[rtype(result = "()")]
+pub struct Complete;
+
+#[derive(Debug)]
+struct Client {
+    events: Recipient<Forward>,
+    complete: Recipient<Complete>,
+}
+
+/// Registry of connected sockets, each gets a copy of every graph event
+#[derive(Debug)]
+pub struct Hub {
+    clients: Mutex<HashMap<usize, Client>>,
+    next_id: AtomicUsize,
+    history: Mutex<History>,
+}
+
+/// Ring buffer of the last `capacity` events
+#[derive(Debug)]
+struct History {
+    events: VecDeque<(u64, GraphEvent)>,
+    last_seq: u64,
+    capacity: usize,
+}
+
+impl Default for Hub {
+    fn default() -> Hub {
+        Hub::with_history(HISTORY_LEN)
+    }
+}
+
+impl Hub {
+    pub fn new() -> Hub {
+        Hub::default()
+    }
+
+    /// A hub replaying at most `capacity` events to resuming clients
+    pub fn with_history(capacity: usize) -> Hub {
+        Hub {
+            clients: Mutex::default(),
+            next_id: AtomicUsize::new(0),
+            history: Mutex::new(History {
+                events: VecDeque::with_capacity(capacity),
+                last_seq: 0,
+                capacity,
+            }),
+        }
+    }
+
+    /// Returns the id to pass to `unregister` once the client stops
+    pub fn register(
+        &self,
+        events: Recipient<Forward>,
+        complete: Recipient<Complete>,
+    ) -> usize {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.clients
+            .lock()
+            .unwrap()
+            .insert(id, Client { events, complete });
+        id
+    }
+
+    pub fn unregister(&self, id: usize) {
+        self.clients.lock().unwrap().remove(&id);
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Sequence number of the latest event, 0 before the first one
+    pub fn last_seq(&self) -> u64 {
+        self.history.lock().unwrap().last_seq
+    }
+
+    /// Events after `seq` in order, None if some of them were already
+    /// evicted or `seq` is ahead of the latest event
+    pub fn since(&self, seq: u64) -> Option<Vec<(u64, GraphEvent)>> {
+        let history = self.history.lock().unwrap();
+        if seq > history.last_seq {
+            return None;
+        }
+
+        let oldest = history
+            .events
+            .front()
+            .map_or(history.last_seq + 1, |(s, _)| *s);
+        if seq + 1 < oldest {
+            return None;
+        }
+
+        Some(
+            history
+                .events
+                .iter()
+                .filter(|(s, _)| *s > seq)
+                .cloned()
+                .collect(),
+        )
+    }
+
+    pub fn broadcast(&self, event: &GraphEvent) {
+        let seq = {
+            let mut history = self.history.lock().unwrap();
+            history.last_seq += 1;
+            let seq = history.last_seq;
+
+            if history.events.len() == history.capacity {
+                history.events.pop_front();
+            }
+            if history.capacity > 0 {
+                history.events.push_back((seq, event.clone()));
+            }
+            seq
+        };
+
+        for client in self.clients.lock().unwrap().values() {
+            client.events.do_send(Forward(seq, event.clone()));
+        }
+    }
+
+    /// Tells every client no more events are coming
+    pub fn complete(&self) {
+        for client in self.clients.lock().unwrap().values() {
+            client.complete.do_send(Complete);
+        }
+    }
+
+    /// Forwards events to all clients until the graph's sender is dropped
+    pub async fn run(&self, mut events: mpsc::UnboundedReceiver<GraphEvent>) {
+        self.forward(&mut events).await
+    }
+
+    /// `run` until `stop` resolves, then forwards what's still buffered,
+    /// for at most `drain_timeout`, and sends every client `Complete`
+    pub async fn run_until(
+        &self,
+        mut events: mpsc::UnboundedReceiver<GraphEvent>,
+        stop: impl Future<Output = ()>,
+        drain_timeout: Duration,
+    ) {
+        tokio::select! {
+            _ = self.forward(&mut events) => {}
+            _ = stop => {}
+        }
+
+        let deadline = Instant::now() + drain_timeout;
+        let mut drained = 0;
+        while let Ok(event) = events.try_recv() {
+            self.broadcast(&event);
+            drained += 1;
+
+            if Instant::now() >= deadline {
+                warn!(drained, left = events.len(), "Event drain timed out");
+                break;
+            }
+        }
+
+        info!(drained, clients = self.client_count(), "Events drained");
+        self.complete();
+    }
+
+    async fn forward(&self, events: &mut mpsc::UnboundedReceiver<GraphEvent>) {
+        while let Some(event) = events.recv().await {
+            self.broadcast(&event);
+        }
+    }
+}
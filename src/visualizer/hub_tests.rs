@@ -0,0 +1,111 @@
+#![cfg(test)]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix::{Actor, Context, Handler};
+
+use crate::graph::core::{Graph, GraphEvent};
+use crate::visualizer::hub::{Complete, Forward, Hub};
+
+type Received = Arc<Mutex<Vec<(u64, GraphEvent)>>>;
+
+/// Stand-in for a socket, keeps whatever the hub sends it
+#[derive(Default)]
+struct Collector {
+    events: Received,
+}
+
+impl Actor for Collector {
+    type Context = Context<Self>;
+}
+
+impl Handler<Forward> for Collector {
+    type Result = ();
+
+    fn handle(&mut self, Forward(seq, event): Forward, _: &mut Context<Self>) {
+        self.events.lock().unwrap().push((seq, event));
+    }
+}
+
+impl Handler<Complete> for Collector {
+    type Result = ();
+
+    fn handle(&mut self, _: Complete, _: &mut Context<Self>) {}
+}
+
+fn register(hub: &Hub) -> (usize, Received) {
+    let collector = Collector::default();
+    let events = collector.events.clone();
+    let addr = collector.start();
+    (
+        hub.register(addr.clone().recipient(), addr.recipient()),
+        events,
+    )
+}
+
+fn added(name: &str) -> GraphEvent {
+    GraphEvent::NodeAdded(name.into())
+}
+
+#[test]
+fn test_since_replays_the_gap() {
+    let hub = Hub::with_history(8);
+    assert_eq!(hub.last_seq(), 0);
+    assert_eq!(hub.since(0), Some(vec![]));
+
+    for name in ["A", "B", "C"] {
+        hub.broadcast(&added(name));
+    }
+
+    assert_eq!(hub.last_seq(), 3);
+    assert_eq!(hub.since(1), Some(vec![(2, added("B")), (3, added("C"))]));
+    assert_eq!(hub.since(3), Some(vec![]));
+}
+
+#[test]
+fn test_since_stale_or_future_cursor() {
+    let hub = Hub::with_history(2);
+    for name in ["A", "B", "C", "D"] {
+        hub.broadcast(&added(name));
+    }
+
+    // 3 and 4 are kept, 2 is the oldest cursor that still replays cleanly
+    assert_eq!(hub.since(2), Some(vec![(3, added("C")), (4, added("D"))]));
+    assert_eq!(hub.since(1), None);
+    assert_eq!(hub.since(0), None);
+
+    // e.g. a cursor from before a server restart
+    assert_eq!(hub.since(5), None);
+}
+
+#[actix_web::test]
+async fn test_run_forwards_graph_events_to_every_client() {
+    let hub = Arc::new(Hub::new());
+    let (first_id, first) = register(&hub);
+    let (_, second) = register(&hub);
+    assert_eq!(hub.client_count(), 2);
+
+    let (graph, rx) = Graph::new();
+    let forwarder = hub.clone();
+    let run = actix_web::rt::spawn(async move { forwarder.run(rx).await });
+
+    graph.add_edge("root", "A").unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    hub.unregister(first_id);
+    graph.add_edge("root", "B").unwrap();
+    drop(graph);
+    run.await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let edge = |c: &str| GraphEvent::EdgeAdded("root".into(), c.into());
+    assert_eq!(*first.lock().unwrap(), [(1, added("A")), (2, edge("A"))]);
+    assert_eq!(
+        *second.lock().unwrap(),
+        [
+            (1, added("A")),
+            (2, edge("A")),
+            (3, added("B")),
+            (4, edge("B")),
+        ]
+    );
+}
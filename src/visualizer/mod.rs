@@ -1 +1,8 @@
+pub mod command;
+pub mod command_tests;
+pub mod hub;
+pub mod hub_tests;
+pub mod protocol;
+pub mod protocol_tests;
 pub mod server;
+pub mod server_tests;
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::graph::core::{Graph, GraphEvent};
+use crate::graph::export::{dot_edge, dot_node};
+
+/// Per-client wire format, picked with `/ws?encoding=...`
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// a json snapshot on connect, then one json object per graph event
+    #[default]
+    Json,
+
+    /// full DOT snapshot on connect, then DOT statements to append
+    Dot,
+}
+
+/// Json frame for a graph event, in the shape static/index.html reads
+pub fn event_json(graph: &Graph, event: &GraphEvent) -> String {
+    event_value(graph, event).to_string()
+}
+
+/// `event_json` with the hub's sequence number added as `seq`, the cursor
+/// a reconnecting client passes to `resume_from`
+pub fn sequenced_event_json(
+    graph: &Graph,
+    seq: u64,
+    event: &GraphEvent,
+) -> String {
+    let mut value = event_value(graph, event);
+    value["seq"] = seq.into();
+    value.to_string()
+}
+
+fn event_value(graph: &Graph, event: &GraphEvent) -> Value {
+    match event {
+        GraphEvent::NodeAdded(id) => json!({"type": "nodeAdded", "id": id}),
+        GraphEvent::EdgeAdded(parent, child) => {
+            edge_value("edgeAdded", parent, child)
+        }
+        GraphEvent::EdgeRemoved(parent, child) => {
+            edge_value("edgeRemoved", parent, child)
+        }
+        GraphEvent::DuplicateEdge(parent, child) => {
+            edge_value("duplicateEdge", parent, child)
+        }
+        GraphEvent::NodeRemoved(id) => {
+            json!({"type": "nodeRemoved", "id": id})
+        }
+        GraphEvent::NodeUpdated(id) => {
+            let meta = graph
+                .get_node(id)
+                .map(|n| n.get_all_meta())
+                .unwrap_or_default();
+
+            json!({"type": "nodeUpdated", "id": id, "meta": meta})
+        }
+        GraphEvent::Stats(stats) => json!({"type": "stats", "stats": stats}),
+        GraphEvent::CrawlProgress {
+            visited,
+            queued,
+            depth,
+        } => json!({
+            "type": "crawlProgress",
+            "visited": visited,
+            "queued": queued,
+            "depth": depth,
+        }),
+    }
+}
+
+/// source and target are what the d3 frontend links by
+fn edge_value(kind: &str, parent: &str, child: &str) -> Value {
+    json!({"type": kind, "source": parent, "target": child})
+}
+
+/// Last frame a client gets on shutdown, after every remaining event
+pub fn complete_frame() -> String {
+    json!({"type": "complete"}).to_string()
+}
+
+pub fn dot_snapshot_frame(graph: &Graph) -> String {
+    json!({"type": "dotSnapshot", "dot": graph.to_dot()}).to_string()
+}
+
+/// Whole graph in one frame, for clients that need to resync
+pub fn snapshot_frame(graph: &Graph, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Json => {
+            let snapshot = graph.snapshot();
+            json!({
+                "type": "snapshot",
+                "nodes": snapshot.nodes,
+                "edges": snapshot.edges,
+            })
+            .to_string()
+        }
+        Encoding::Dot => dot_snapshot_frame(graph),
+    }
+}
+
+/// Dot frame for a graph event, None if it doesn't change the DOT source
+///
+/// Additions become a `dotFragment` the client appends to its source.
+/// DOT has no removal statement, so removals resend the full snapshot.
+pub fn event_dot(graph: &Graph, event: &GraphEvent) -> Option<String> {
+    let fragment = match event {
+        GraphEvent::NodeAdded(name) => dot_node(name),
+        GraphEvent::EdgeAdded(parent, child) => dot_edge(parent, child),
+        GraphEvent::EdgeRemoved(..) | GraphEvent::NodeRemoved(_) => {
+            return Some(dot_snapshot_frame(graph));
+        }
+        GraphEvent::DuplicateEdge(..)
+        | GraphEvent::NodeUpdated(_)
+        | GraphEvent::Stats(_)
+        | GraphEvent::CrawlProgress { .. } => return None,
+    };
+
+    Some(json!({"type": "dotFragment", "dot": fragment}).to_string())
+}
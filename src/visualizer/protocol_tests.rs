@@ -0,0 +1,92 @@
+#![cfg(test)]
+use serde_json::Value;
+
+use crate::graph::core::{Graph, GraphEvent};
+use crate::visualizer::protocol::{event_dot, event_json};
+
+fn frame(text: &str) -> Value {
+    serde_json::from_str(text).unwrap()
+}
+
+#[test]
+fn test_event_json_matches_frontend_shape() {
+    let graph = Graph::new_without_events();
+
+    let added = frame(&event_json(&graph, &GraphEvent::NodeAdded("A".into())));
+    assert_eq!(added["type"], "nodeAdded");
+    assert_eq!(added["id"], "A");
+
+    let edge = GraphEvent::EdgeAdded("root".into(), "A".into());
+    let edge = frame(&event_json(&graph, &edge));
+    assert_eq!(edge["type"], "edgeAdded");
+    assert_eq!(edge["source"], "root");
+    assert_eq!(edge["target"], "A");
+    assert!(edge.get("parent").is_none());
+}
+
+#[test]
+fn test_dot_fragment_for_edge() {
+    let graph = Graph::new_without_events();
+    let event = GraphEvent::EdgeAdded("root".into(), "child".into());
+
+    let value = frame(&event_dot(&graph, &event).unwrap());
+    assert_eq!(value["type"], "dotFragment");
+    assert_eq!(value["dot"], r#""root" -> "child";"#);
+}
+
+#[test]
+fn test_dot_fragments_match_full_export() {
+    let (graph, mut rx) = Graph::new();
+    graph.add_edge("root", "node/with\\special$chars").unwrap();
+    graph
+        .add_edge("node/with\\special$chars", "say \"hi\"")
+        .unwrap();
+
+    let full = graph.to_dot();
+
+    // every fragment is a statement of the full export, escaping included
+    let mut count = 0;
+    while let Ok(event) = rx.try_recv() {
+        let value = frame(&event_dot(&graph, &event).unwrap());
+        let fragment = value["dot"].as_str().unwrap();
+
+        assert!(
+            full.lines().any(|l| l.trim() == fragment),
+            "{} not in {}",
+            fragment,
+            full
+        );
+        count += 1;
+    }
+
+    assert_eq!(count, 4); // 2 NodeAdded + 2 EdgeAdded
+}
+
+#[test]
+fn test_dot_removal_resends_snapshot() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+
+    let event = GraphEvent::EdgeRemoved("A".into(), "A".into());
+    let value = frame(&event_dot(&graph, &event).unwrap());
+
+    assert_eq!(value["type"], "dotSnapshot");
+    assert_eq!(value["dot"], graph.to_dot());
+}
+
+#[test]
+fn test_node_updated_json_carries_meta() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    graph.set_node_meta("A", "fetch_ms", 12.5).unwrap();
+
+    let event = GraphEvent::NodeUpdated("A".into());
+    let value = frame(&event_json(&graph, &event));
+
+    assert_eq!(value["type"], "nodeUpdated");
+    assert_eq!(value["id"], "A");
+    assert_eq!(value["meta"]["fetch_ms"], 12.5);
+
+    // DOT source has no metadata, nothing to send
+    assert!(event_dot(&graph, &event).is_none());
+}
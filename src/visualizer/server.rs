@@ -1,12 +1,218 @@
-use actix::{Actor, StreamHandler, ActorContext};
+use std::{
+    future::Future,
+    net::TcpListener,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use actix::{Actor, ActorContext, AsyncContext, Handler, StreamHandler};
 use actix_files::Files;
-use actix_web::{middleware::Logger, web, App, Error, HttpRequest, HttpResponse, HttpServer};
+use actix_web::{
+    App, Error, HttpMessage, HttpRequest, HttpResponse, HttpServer,
+    dev::Server,
+    http::header::{self, EntityTag, IfNoneMatch},
+    middleware::Logger,
+    web,
+};
 use actix_web_actors::ws;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::graph::core::{Graph, GraphEvent, Node};
+use crate::graph::error::GraphError;
+use crate::visualizer::command::{ClientCommand, error_frame, reply};
+use crate::visualizer::hub::{Complete, Forward, Hub};
+use crate::visualizer::protocol::{
+    Encoding, complete_frame, event_dot, sequenced_event_json, snapshot_frame,
+};
+
+/// How often sockets are pinged, and how long one may go without
+/// answering before it's dropped as half-open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
 
-struct WebSocket;
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Heartbeat {
+            interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+struct WebSocket {
+    graph: Arc<Graph>,
+    hub: Arc<Hub>,
+    encoding: Encoding,
+    hub_id: Option<usize>,
+
+    heartbeat: Heartbeat,
+    // last time the client was heard from, a pong or anything else
+    last_heartbeat: Instant,
+
+    // highest event seq sent, replays can overlap with live events
+    last_seq: u64,
+
+    // set by the pause command, events are dropped until resume
+    paused: bool,
+}
 
 impl Actor for WebSocket {
     type Context = ws::WebsocketContext<Self>;
+
+    /// Sends the graph so far, then streams events from there on
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // register before the snapshot so no event falls in between, an
+        // event the snapshot already has may still follow it
+        let addr = ctx.address();
+        self.hub_id = Some(
+            self.hub
+                .register(addr.clone().recipient(), addr.recipient()),
+        );
+
+        ctx.text(snapshot_frame(&self.graph, self.encoding));
+
+        ctx.run_interval(self.heartbeat.interval, |socket, ctx| {
+            if socket.last_heartbeat.elapsed() > socket.heartbeat.timeout {
+                tracing::info!("WebSocket heartbeat timed out, dropping it");
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        if let Some(id) = self.hub_id.take() {
+            self.hub.unregister(id);
+        }
+    }
+}
+
+impl Handler<Forward> for WebSocket {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        Forward(seq, event): Forward,
+        ctx: &mut Self::Context,
+    ) {
+        if self.paused {
+            return;
+        }
+
+        self.send_event(seq, &event, ctx);
+    }
+}
+
+impl Handler<Complete> for WebSocket {
+    type Result = ();
+
+    /// Queued after every Forward, so the last event goes out first
+    fn handle(&mut self, _: Complete, ctx: &mut Self::Context) {
+        ctx.text(complete_frame());
+        ctx.close(None);
+        ctx.stop();
+    }
+}
+
+impl WebSocket {
+    fn encode(&self, seq: u64, event: &GraphEvent) -> Option<String> {
+        match self.encoding {
+            Encoding::Json => {
+                Some(sequenced_event_json(&self.graph, seq, event))
+            }
+            Encoding::Dot => event_dot(&self.graph, event),
+        }
+    }
+
+    fn send_event(
+        &mut self,
+        seq: u64,
+        event: &GraphEvent,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        if seq <= self.last_seq {
+            return;
+        }
+        self.last_seq = seq;
+
+        if let Some(frame) = self.encode(seq, event) {
+            ctx.text(frame);
+        }
+    }
+
+    /// Replays the events after `seq`, falling back to a snapshot when the
+    /// hub no longer has all of them
+    fn resume_from(&mut self, seq: u64, ctx: &mut ws::WebsocketContext<Self>) {
+        let replay = self.hub.since(seq);
+        let head = match &replay {
+            Some(events) => events.last().map_or(seq, |(s, _)| *s),
+            None => self.hub.last_seq(),
+        };
+
+        ctx.text(
+            json!({
+                "type": "ack",
+                "cmd": "resumeFrom",
+                "seq": head,
+                "snapshot": replay.is_none(),
+            })
+            .to_string(),
+        );
+
+        match replay {
+            Some(events) => {
+                // may already have been sent live since connecting
+                self.last_seq = self.last_seq.min(seq);
+                for (seq, event) in events {
+                    self.send_event(seq, &event, ctx);
+                }
+            }
+            None => {
+                self.last_seq = self.last_seq.max(head);
+                ctx.text(snapshot_frame(&self.graph, self.encoding));
+            }
+        }
+    }
+
+    fn command(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let cmd = match ClientCommand::parse(text) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                ctx.text(error_frame(&e.to_string()));
+                return;
+            }
+        };
+
+        for frame in reply(&self.graph, &cmd) {
+            ctx.text(frame);
+        }
+
+        match cmd {
+            ClientCommand::Pause => self.paused = true,
+            ClientCommand::Resume => {
+                // events missed while paused are gone, resync instead
+                self.paused = false;
+                ctx.text(snapshot_frame(&self.graph, self.encoding));
+            }
+            ClientCommand::ResumeFrom { seq } => self.resume_from(seq, ctx),
+            ClientCommand::Snapshot => {
+                ctx.text(snapshot_frame(&self.graph, self.encoding));
+            }
+            ClientCommand::SetEncoding { encoding }
+                if encoding != self.encoding =>
+            {
+                self.encoding = encoding;
+                ctx.text(snapshot_frame(&self.graph, encoding));
+            }
+            _ => (),
+        }
+    }
 }
 
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WebSocket {
@@ -15,12 +221,13 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WebSocket {
         msg: Result<ws::Message, ws::ProtocolError>,
         ctx: &mut Self::Context,
     ) {
+        if msg.is_ok() {
+            self.last_heartbeat = Instant::now();
+        }
+
         match msg {
             Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
-            Ok(ws::Message::Text(text)) => {
-                // echo the text back
-                ctx.text(format!("Echo: {}", text));
-            }
+            Ok(ws::Message::Text(text)) => self.command(&text, ctx),
             Ok(ws::Message::Binary(bin)) => ctx.binary(bin),
             Ok(ws::Message::Close(reason)) => {
                 ctx.close(reason);
@@ -31,21 +238,207 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WebSocket {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct WsParams {
+    #[serde(default)]
+    encoding: Encoding,
+}
+
 // route handler
-async fn ws_index(req: HttpRequest, stream: web::Payload) -> Result<HttpResponse, Error> {
-    ws::start(WebSocket {}, &req, stream)
+async fn ws_index(
+    req: HttpRequest,
+    stream: web::Payload,
+    params: web::Query<WsParams>,
+    graph: web::Data<Graph>,
+    hub: web::Data<Hub>,
+    heartbeat: web::Data<Heartbeat>,
+) -> Result<HttpResponse, Error> {
+    let socket = WebSocket {
+        graph: graph.into_inner(),
+        hub: hub.into_inner(),
+        encoding: params.encoding,
+        hub_id: None,
+        heartbeat: **heartbeat,
+        last_heartbeat: Instant::now(),
+        last_seq: 0,
+        paused: false,
+    };
+
+    ws::start(socket, &req, stream)
+}
+
+/// The REST exports: `/graph` and `/graph/node/{name}`, also served as
+/// `/api/graph.json` and `/api/node/{name}`, and `/api/graph.dot`
+pub(crate) fn api_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/graph", web::get().to(graph_json))
+        .route("/graph/node/{name:.*}", web::get().to(node_json))
+        .route("/api/graph.json", web::get().to(graph_json))
+        .route("/api/graph.dot", web::get().to(graph_dot))
+        .route("/api/node/{name:.*}", web::get().to(node_json));
+}
+
+/// A node's children, parents and metadata, 404 if it's missing
+///
+/// The name is the rest of the path, so urls with slashes need no
+/// escaping beyond their own percent-encoding.
+async fn node_json(
+    name: web::Path<String>,
+    graph: web::Data<Graph>,
+) -> HttpResponse {
+    let Some(node) = graph.get_node(&name) else {
+        let e = GraphError::NodeNotFound(name.into_inner());
+        return HttpResponse::NotFound().json(json!({"error": e.to_string()}));
+    };
+
+    let names = |nodes: Vec<Arc<Node>>| -> Vec<String> {
+        nodes.iter().map(|n| n.get_data().to_owned()).collect()
+    };
+    HttpResponse::Ok().json(json!({
+        "name": node.get_data(),
+        "children": names(node.get_children()),
+        "parents": names(node.get_parents()),
+        "meta": node.get_all_meta(),
+    }))
+}
+
+async fn graph_json(
+    req: HttpRequest,
+    graph: web::Data<Graph>,
+) -> Result<HttpResponse, Error> {
+    let body = |graph: &Graph| graph.to_json();
+    export(&req, &graph, "application/json", body)
 }
 
-pub async fn start() -> anyhow::Result<()> {
-    HttpServer::new(|| {
+async fn graph_dot(
+    req: HttpRequest,
+    graph: web::Data<Graph>,
+) -> Result<HttpResponse, Error> {
+    let body = |graph: &Graph| Ok(graph.to_dot());
+    export(&req, &graph, "text/vnd.graphviz", body)
+}
+
+/// `body` tagged with the graph's generation, or a bare 304 when the
+/// client's `If-None-Match` already has that generation
+///
+/// The generation is read before exporting, a mutation racing the export
+/// only makes the tag stale and the next request refetch.
+fn export(
+    req: &HttpRequest,
+    graph: &Graph,
+    content_type: &'static str,
+    body: impl FnOnce(&Graph) -> anyhow::Result<String>,
+) -> Result<HttpResponse, Error> {
+    let etag = EntityTag::new_strong(format!("g{}", graph.generation()));
+
+    let cached = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => {
+            tags.iter().any(|tag| tag.weak_eq(&etag))
+        }
+        None => false,
+    };
+    if cached {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(header::ETag(etag))
+            .finish());
+    }
+
+    let body =
+        body(graph).map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(header::ETag(etag))
+        .body(body))
+}
+
+/// How long shutdown keeps forwarding buffered events to clients
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves until ctrl-c, then drains the remaining events to clients and
+/// sends them a `complete` frame before closing their sockets
+pub async fn start(
+    graph: Arc<Graph>,
+    events: mpsc::UnboundedReceiver<GraphEvent>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", 8081))?;
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Listening for ctrl-c failed: {}", e);
+        }
+    };
+    serve_until(listener, graph, events, ctrl_c)?.await?;
+
+    Ok(())
+}
+
+/// The visualizer server on an already bound listener, the returned
+/// server runs once awaited or spawned
+pub fn serve(
+    listener: TcpListener,
+    graph: Arc<Graph>,
+    events: mpsc::UnboundedReceiver<GraphEvent>,
+) -> anyhow::Result<Server> {
+    let hub = Arc::new(Hub::new());
+
+    let forwarder = hub.clone();
+    tokio::spawn(async move { forwarder.run(events).await });
+
+    build_server(listener, graph, hub, Heartbeat::default(), None)
+}
+
+/// `serve` that shuts down once `stop` resolves, the drain phase
+/// described on `start` included
+pub fn serve_until(
+    listener: TcpListener,
+    graph: Arc<Graph>,
+    events: mpsc::UnboundedReceiver<GraphEvent>,
+    stop: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<Server> {
+    let hub = Arc::new(Hub::new());
+    let server = build_server(
+        listener,
+        graph,
+        hub.clone(),
+        Heartbeat::default(),
+        Some(DRAIN_TIMEOUT),
+    )?;
+
+    let handle = server.handle();
+    tokio::spawn(async move {
+        hub.run_until(events, stop, DRAIN_TIMEOUT).await;
+        handle.stop(true).await;
+    });
+
+    Ok(server)
+}
+
+/// With a `shutdown_timeout` the caller owns shutdown: signals are left
+/// alone and open connections get that long to finish once stopped
+pub(crate) fn build_server(
+    listener: TcpListener,
+    graph: Arc<Graph>,
+    hub: Arc<Hub>,
+    heartbeat: Heartbeat,
+    shutdown_timeout: Option<Duration>,
+) -> anyhow::Result<Server> {
+    let graph = web::Data::from(graph);
+    let hub = web::Data::from(hub);
+    let heartbeat = web::Data::new(heartbeat);
+
+    let mut server = HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
+            .app_data(graph.clone())
+            .app_data(hub.clone())
+            .app_data(heartbeat.clone())
             .route("/ws", web::get().to(ws_index))
+            .configure(api_routes)
             .service(Files::new("/", "static/").index_file("index.html"))
-    })
-    .bind(("127.0.0.1", 8081))?
-    .run()
-    .await?;
+    });
+    if let Some(timeout) = shutdown_timeout {
+        server = server.disable_signals().shutdown_timeout(timeout.as_secs());
+    }
+    let server = server.listen(listener)?.run();
 
-    Ok(())
+    Ok(server)
 }
@@ -0,0 +1,509 @@
+#![cfg(test)]
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::graph::core::Graph;
+use crate::visualizer::hub::{HISTORY_LEN, Hub};
+use crate::visualizer::server::{
+    Heartbeat, api_routes, build_server, serve, serve_until,
+};
+
+/// Bare-bones blocking WebSocket client, text frames only
+pub(crate) struct WsClient {
+    stream: BufReader<TcpStream>,
+
+    /// the frame sent on connect, once it's in the socket is registered
+    /// with the hub and sees every later event
+    pub snapshot: Value,
+}
+
+impl WsClient {
+    pub fn connect(port: u16, query: &str) -> WsClient {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        write!(
+            stream,
+            "GET /ws{} HTTP/1.1\r\n\
+             Host: 127.0.0.1:{}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n",
+            query, port
+        )
+        .unwrap();
+
+        let mut stream = BufReader::new(stream);
+        let mut line = String::new();
+        stream.read_line(&mut line).unwrap();
+        assert!(line.starts_with("HTTP/1.1 101"), "{}", line);
+        while line != "\r\n" {
+            line.clear();
+            stream.read_line(&mut line).unwrap();
+        }
+
+        let mut ws = WsClient {
+            stream,
+            snapshot: Value::Null,
+        };
+        ws.snapshot = ws.recv();
+        ws
+    }
+
+    pub fn send(&mut self, text: &str) {
+        // client frames must be masked, a zero mask keeps the payload as is
+        let mut frame = vec![0x81];
+        let len = text.len();
+        if len < 126 {
+            frame.push(0x80 | len as u8);
+        } else {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        frame.extend_from_slice(&[0; 4]);
+        frame.extend_from_slice(text.as_bytes());
+
+        self.stream.get_mut().write_all(&frame).unwrap();
+    }
+
+    pub fn recv(&mut self) -> Value {
+        let (opcode, payload) = self.recv_frame().expect("connection closed");
+        assert_eq!(opcode, 0x1, "expected a text frame");
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    /// Opcode and payload of the next frame of any kind, None once the
+    /// server hung up
+    pub fn recv_frame(&mut self) -> Option<(u8, Vec<u8>)> {
+        let mut head = [0; 2];
+        self.stream.read_exact(&mut head).ok()?;
+
+        let len = match head[1] & 0x7f {
+            126 => {
+                let mut ext = [0; 2];
+                self.stream.read_exact(&mut ext).unwrap();
+                u16::from_be_bytes(ext) as usize
+            }
+            127 => {
+                let mut ext = [0; 8];
+                self.stream.read_exact(&mut ext).unwrap();
+                u64::from_be_bytes(ext) as usize
+            }
+            n => n as usize,
+        };
+
+        let mut payload = vec![0; len];
+        self.stream.read_exact(&mut payload).unwrap();
+        Some((head[0] & 0x0f, payload))
+    }
+}
+
+/// Serves `graph` on a free port, returns the port
+pub(crate) fn spawn_server(graph: Graph) -> (u16, Arc<Graph>) {
+    let (_, events) = Graph::new();
+    let graph = Arc::new(graph);
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    actix_web::rt::spawn(serve(listener, graph.clone(), events).unwrap());
+
+    (port, graph)
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    tokio::task::spawn_blocking(f).await.unwrap()
+}
+
+#[actix_web::test]
+async fn test_malformed_command_keeps_connection() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    let (port, _graph) = spawn_server(graph);
+
+    blocking(move || {
+        let mut ws = WsClient::connect(port, "");
+
+        ws.send("{not json");
+        assert_eq!(ws.recv()["type"], "error");
+
+        ws.send(r#"{"cmd":"noSuchCommand"}"#);
+        assert_eq!(ws.recv()["message"], "unknown command");
+
+        // still alive and answering
+        ws.send(r#"{"cmd":"neighbors","node":"root"}"#);
+        let reply = ws.recv();
+        assert_eq!(reply["type"], "neighbors");
+        assert_eq!(reply["children"], serde_json::json!(["A"]));
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn test_set_encoding_resends_snapshot() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    let (port, graph) = spawn_server(graph);
+
+    let dot = graph.to_dot();
+    blocking(move || {
+        let mut ws = WsClient::connect(port, "");
+
+        ws.send(r#"{"cmd":"setEncoding","encoding":"dot"}"#);
+        assert_eq!(ws.recv()["type"], "ack");
+
+        let snapshot = ws.recv();
+        assert_eq!(snapshot["type"], "dotSnapshot");
+        assert_eq!(snapshot["dot"], dot);
+    })
+    .await;
+}
+
+/// Like `spawn_server` but with the graph's own events going out
+fn spawn_live_server() -> (u16, Arc<Graph>) {
+    let (graph, events) = Graph::new();
+    let graph = Arc::new(graph);
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    actix_web::rt::spawn(serve(listener, graph.clone(), events).unwrap());
+
+    (port, graph)
+}
+
+#[actix_web::test]
+async fn test_client_receives_edge_added_frame() {
+    let (port, graph) = spawn_live_server();
+
+    blocking(move || {
+        let mut ws = WsClient::connect(port, "");
+
+        graph.add_edge("root", "A").unwrap();
+        let node = ws.recv();
+        assert_eq!(node["type"], "nodeAdded");
+        assert_eq!(node["id"], "A");
+
+        let edge = ws.recv();
+        assert_eq!(edge["type"], "edgeAdded");
+        assert_eq!(edge["source"], "root");
+        assert_eq!(edge["target"], "A");
+        assert_eq!(edge["seq"], node["seq"].as_u64().unwrap() + 1);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn test_resume_from_replays_gap() {
+    let (port, graph) = spawn_live_server();
+
+    blocking(move || {
+        let mut watcher = WsClient::connect(port, "");
+        let mut ws = WsClient::connect(port, "");
+
+        graph.add_edge("root", "A").unwrap();
+        assert_eq!(ws.recv()["type"], "nodeAdded");
+        let edge = ws.recv();
+        assert_eq!(edge["type"], "edgeAdded");
+        let cursor = edge["seq"].as_u64().unwrap();
+        drop(ws);
+
+        graph.add_edge("root", "B").unwrap();
+        watcher.recv();
+        watcher.recv();
+        watcher.recv();
+        assert_eq!(watcher.recv()["target"], "B");
+
+        let mut ws = WsClient::connect(port, "");
+        ws.send(&format!(r#"{{"cmd":"resume_from","seq":{}}}"#, cursor));
+
+        let ack = ws.recv();
+        assert_eq!(ack["cmd"], "resumeFrom");
+        assert_eq!(ack["snapshot"], false);
+        assert_eq!(ack["seq"], cursor + 2);
+
+        let node = ws.recv();
+        assert_eq!(node["type"], "nodeAdded");
+        assert_eq!(node["id"], "B");
+        assert_eq!(node["seq"], cursor + 1);
+        let edge = ws.recv();
+        assert_eq!(edge["type"], "edgeAdded");
+        assert_eq!(edge["seq"], cursor + 2);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn test_resume_from_stale_cursor_sends_snapshot() {
+    let (port, graph) = spawn_live_server();
+
+    blocking(move || {
+        let mut watcher = WsClient::connect(port, "");
+
+        // two events per edge, pushes seq 1 out of the history
+        let edges = HISTORY_LEN / 2 + 1;
+        for i in 0..edges {
+            graph.add_edge("root", &format!("N{}", i)).unwrap();
+        }
+        let mut last = Value::Null;
+        for _ in 0..edges * 2 {
+            last = watcher.recv();
+        }
+        let head = last["seq"].as_u64().unwrap();
+
+        let mut ws = WsClient::connect(port, "");
+        ws.send(r#"{"cmd":"resume_from","seq":0}"#);
+
+        let ack = ws.recv();
+        assert_eq!(ack["snapshot"], true);
+        assert_eq!(ack["seq"], head);
+
+        let snapshot = ws.recv();
+        assert_eq!(snapshot["type"], "snapshot");
+        assert_eq!(snapshot["nodes"].as_array().unwrap().len(), edges + 1);
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn test_shutdown_drains_events_to_slow_client() {
+    let (graph, events) = Graph::new();
+    let graph = Arc::new(graph);
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let stop = async move {
+        let _ = stop_rx.await;
+    };
+    let server = serve_until(listener, graph.clone(), events, stop).unwrap();
+    let server = actix_web::rt::spawn(server);
+
+    blocking(move || {
+        let mut ws = WsClient::connect(port, "");
+
+        for i in 0..50 {
+            graph.add_edge("root", &format!("N{}", i)).unwrap();
+        }
+        stop_tx.send(()).unwrap();
+
+        // the client lags behind, shutdown still waits for it
+        let mut last_edge = None;
+        loop {
+            std::thread::sleep(Duration::from_millis(5));
+            let frame = ws.recv();
+            match frame["type"].as_str().unwrap() {
+                "complete" => break,
+                "edgeAdded" => last_edge = Some(frame["target"].clone()),
+                _ => {}
+            }
+        }
+        assert_eq!(last_edge.unwrap(), "N49");
+
+        let mut head = [0; 2];
+        ws.stream.read_exact(&mut head).unwrap();
+        assert_eq!(head[0] & 0x0f, 0x8, "expected a close frame");
+    })
+    .await;
+
+    server.await.unwrap().unwrap();
+}
+
+#[actix_web::test]
+async fn test_export_revalidates_by_generation() {
+    use actix_web::{App, http::StatusCode, test, web};
+
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    let graph = web::Data::new(graph);
+    let app = test::init_service(
+        App::new().app_data(graph.clone()).configure(api_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::get().uri("/api/graph.json").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let headers = resp.headers();
+    assert_eq!(headers.get("content-type").unwrap(), "application/json");
+    let etag = headers.get("etag").unwrap().clone();
+    let body = test::read_body(resp).await;
+    assert_eq!(body, graph.to_json().unwrap());
+
+    let conditional = || {
+        test::TestRequest::get()
+            .uri("/api/graph.json")
+            .insert_header(("If-None-Match", etag.clone()))
+            .to_request()
+    };
+    let resp = test::call_service(&app, conditional()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    assert!(test::read_body(resp).await.is_empty());
+
+    graph.add_edge("A", "B").unwrap();
+    let resp = test::call_service(&app, conditional()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_ne!(resp.headers().get("etag").unwrap(), etag);
+
+    let req = test::TestRequest::get().uri("/api/graph.dot").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/vnd.graphviz"
+    );
+    assert_eq!(test::read_body(resp).await, graph.to_dot());
+}
+
+#[actix_web::test]
+async fn test_snapshot_on_connect_has_existing_edges() {
+    let (port, graph) = spawn_live_server();
+    graph.add_edge("root", "A").unwrap();
+    graph.add_edge("A", "B").unwrap();
+
+    blocking(move || {
+        let mut ws = WsClient::connect(port, "");
+        assert_eq!(ws.snapshot["type"], "snapshot");
+        assert_eq!(ws.snapshot["nodes"], serde_json::json!(["A", "B", "root"]));
+        assert_eq!(
+            ws.snapshot["edges"],
+            serde_json::json!([["A", "B"], ["root", "A"]])
+        );
+
+        // then live events from there on
+        graph.add_edge("B", "C").unwrap();
+        let mut frame = ws.recv();
+        while frame["type"] != "edgeAdded" || frame["source"] != "B" {
+            frame = ws.recv();
+        }
+        assert_eq!(frame["target"], "C");
+
+        let dot = WsClient::connect(port, "?encoding=dot");
+        assert_eq!(dot.snapshot["type"], "dotSnapshot");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn test_snapshot_command_in_client_encoding() {
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "A").unwrap();
+    let (port, _graph) = spawn_server(graph);
+
+    blocking(move || {
+        let mut ws = WsClient::connect(port, "");
+        ws.send(r#"{"cmd":"addEdge","parent":"A","child":"B"}"#);
+        assert_eq!(ws.recv()["added"], true);
+
+        ws.send(r#"{"cmd":"snapshot"}"#);
+        let snapshot = ws.recv();
+        assert_eq!(snapshot["type"], "snapshot");
+        assert_eq!(
+            snapshot["edges"],
+            serde_json::json!([["A", "B"], ["root", "A"]])
+        );
+
+        let mut ws = WsClient::connect(port, "?encoding=dot");
+        ws.send(r#"{"cmd":"snapshot"}"#);
+        assert_eq!(ws.recv()["type"], "dotSnapshot");
+    })
+    .await;
+}
+
+#[actix_web::test]
+async fn test_unresponsive_client_is_dropped_after_heartbeat_timeout() {
+    let heartbeat = Heartbeat {
+        interval: Duration::from_millis(50),
+        timeout: Duration::from_millis(200),
+    };
+    let graph = Arc::new(Graph::new_without_events());
+    let hub = Arc::new(Hub::new());
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server =
+        build_server(listener, graph, hub.clone(), heartbeat, None).unwrap();
+    actix_web::rt::spawn(server);
+
+    let started = Instant::now();
+    let pings = blocking(move || {
+        // reads but never answers a ping
+        let mut ws = WsClient::connect(port, "");
+        let mut pings = 0;
+        while let Some((opcode, _)) = ws.recv_frame() {
+            if opcode == 0x9 {
+                pings += 1;
+            }
+        }
+        pings
+    })
+    .await;
+
+    assert!(pings >= 3, "{} pings", pings);
+    assert!(started.elapsed() >= heartbeat.timeout);
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(hub.client_count(), 0);
+}
+
+#[actix_web::test]
+async fn test_node_route_returns_neighbors_or_404() {
+    use actix_web::{App, http::StatusCode, test, web};
+
+    let graph = Graph::new_without_events();
+    graph.add_edge("root", "https://a.org/wiki/A").unwrap();
+    graph.add_edge("https://a.org/wiki/A", "B").unwrap();
+    graph
+        .set_node_attr("https://a.org/wiki/A", "kind", "article")
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(graph))
+            .configure(api_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/graph/node/https://a.org/wiki/A")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "name": "https://a.org/wiki/A",
+            "children": ["B"],
+            "parents": ["root"],
+            "meta": {"kind": "article"},
+        })
+    );
+
+    let req = test::TestRequest::get()
+        .uri("/graph/node/nope")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"], "Node nope not found");
+
+    // the /api alias serves the same
+    let req = test::TestRequest::get().uri("/api/node/B").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = test::TestRequest::get().uri("/graph").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["edges"].as_array().unwrap().len(), 2);
+}